@@ -1,5 +1,7 @@
+use std::collections::HashSet;
+
 use crate::error::Result;
-use crate::model::{BTree, BTreeNode, BTreeType, OverflowChain, Page, Cell};
+use crate::model::{BTree, BTreeNode, BTreeType, OverflowChain, Page};
 use crate::parser::overflow::follow_overflow_chain;
 
 /// Build a complete B-tree structure by traversing from the root page
@@ -16,77 +18,26 @@ where
     let mut nodes = Vec::new();
     let mut max_depth = 0;
     let mut total_cells = 0;
+    // A corrupt tree can reference a page twice or form a cycle; each page is read once
+    let mut visited = HashSet::new();
 
     // BFS traversal to build tree structure
     let mut queue: Vec<(u32, usize, Option<u32>)> = vec![(root_page_num, 0, None)];
 
     while let Some((page_num, depth, parent)) = queue.pop() {
+        if !visited.insert(page_num) {
+            continue;
+        }
         let page = read_page(page_num)?;
         max_depth = max_depth.max(depth);
         total_cells += page.cells.len();
 
-        // Collect children and overflow chains
-        let mut children = Vec::new();
-        let mut overflow_chains = Vec::new();
-
-        for (i, cell) in page.cells.iter().enumerate() {
-            // Collect child pointers
-            if let Some(child) = cell.left_child() {
-                children.push(child);
-            }
-
-            // Track overflow chains
-            if let Some(overflow_page) = cell.overflow_page() {
-                if let Some(payload_size) = cell.payload_size() {
-                    let local_size = match cell {
-                        Cell::TableLeaf(c) => c.local_payload_size,
-                        Cell::IndexLeaf(c) => c.local_payload_size,
-                        Cell::IndexInterior(c) => c.local_payload_size,
-                        _ => 0,
-                    };
-                    let overflow_bytes = payload_size as usize - local_size;
-
-                    // We'll collect the pages later if needed
-                    overflow_chains.push(OverflowChain {
-                        cell_index: i,
-                        pages: vec![overflow_page], // Just first page for now
-                        total_bytes: overflow_bytes,
-                    });
-                }
-            }
-        }
-
-        // Add rightmost pointer for interior pages
-        if let Some(header) = &page.header {
-            if let Some(right_ptr) = header.right_most_pointer {
-                children.push(right_ptr);
-            }
-        }
-
+        let node = btree_node(&page, depth, parent);
         // Queue children for traversal
-        for &child in &children {
+        for &child in &node.children {
             queue.push((child, depth + 1, Some(page_num)));
         }
-
-        // Calculate space usage
-        let header_size = if page.page_type.is_interior() { 12 } else { 8 };
-        let page1_offset = if page_num == 1 { 100 } else { 0 };
-        let cell_pointers_size = page.cells.len() * 2;
-        let cells_size: usize = page.cells.iter().map(|c| c.cell_size()).sum();
-        let size_used = page1_offset + header_size + cell_pointers_size + cells_size;
-        let size_free = page.free_space;
-
-        nodes.push(BTreeNode {
-            page_number: page_num,
-            page_type: page.page_type,
-            depth,
-            cell_count: page.cells.len(),
-            children,
-            overflow_chains,
-            size_used,
-            size_free,
-            parent,
-        });
+        nodes.push(node);
     }
 
     // Sort nodes by page number for consistent output
@@ -102,12 +53,70 @@ where
     })
 }
 
-/// Expand overflow chains to include all pages in each chain
+/// Summarize one page of a B-tree: its children, the first page of each overflow chain
+/// and its space usage. Overflow chains are not followed.
+pub fn btree_node(page: &Page, depth: usize, parent: Option<u32>) -> BTreeNode {
+    // Collect children and overflow chains
+    let mut children = Vec::new();
+    let mut overflow_chains = Vec::new();
+
+    for (i, cell) in page.cells.iter().enumerate() {
+        // Collect child pointers
+        if let Some(child) = cell.left_child() {
+            children.push(child);
+        }
+
+        // Track overflow chains
+        if let Some(overflow_page) = cell.overflow_page() {
+            if let Some(payload_size) = cell.payload_size() {
+                let local_size = cell.local_payload_size().unwrap_or(0);
+                let overflow_bytes = payload_size as usize - local_size;
+
+                // We'll collect the pages later if needed
+                overflow_chains.push(OverflowChain {
+                    cell_index: i,
+                    pages: vec![overflow_page], // Just first page for now
+                    total_bytes: overflow_bytes,
+                });
+            }
+        }
+    }
+
+    // Add rightmost pointer for interior pages
+    if let Some(header) = &page.header {
+        if let Some(right_ptr) = header.right_most_pointer {
+            children.push(right_ptr);
+        }
+    }
+
+    // Calculate space usage
+    let header_size = if page.page_type.is_interior() { 12 } else { 8 };
+    let page1_offset = if page.page_number == 1 { 100 } else { 0 };
+    let cell_pointers_size = page.cells.len() * 2;
+    let cells_size: usize = page.cells.iter().map(|c| c.cell_size()).sum();
+    let size_used = page1_offset + header_size + cell_pointers_size + cells_size;
+    let size_free = page.free_space;
+
+    BTreeNode {
+        page_number: page.page_number,
+        page_type: page.page_type,
+        depth,
+        cell_count: page.cells.len(),
+        children,
+        overflow_chains,
+        size_used,
+        size_free,
+        parent,
+    }
+}
+
+/// Expand overflow chains to include all pages in each chain.
+/// A chain that cannot be followed keeps only its first page.
 pub fn expand_overflow_chains<F>(
     btree: &mut BTree,
     mut read_page_raw: F,
     usable_size: u32,
-) -> Result<()>
+)
 where
     F: FnMut(u32) -> Result<Vec<u8>>,
 {
@@ -115,17 +124,17 @@ where
         for chain in &mut node.overflow_chains {
             if !chain.pages.is_empty() {
                 let first_page = chain.pages[0];
-                let chain_info = follow_overflow_chain(
+                if let Ok(chain_info) = follow_overflow_chain(
                     first_page,
                     usable_size,
                     chain.total_bytes,
                     &mut read_page_raw,
-                )?;
-                chain.pages = chain_info.pages.iter().map(|p| p.page_number).collect();
+                ) {
+                    chain.pages = chain_info.pages.iter().map(|p| p.page_number).collect();
+                }
             }
         }
     }
-    Ok(())
 }
 
 /// Get statistics about a B-tree
@@ -0,0 +1,631 @@
+//! Carving deleted records out of free space: freeblocks, the unallocated gap
+//! between the cell pointer array and the cell content area, and freelist pages.
+//!
+//! Carved records are scored against the column layout of the live rows of the
+//! tree they were found in, since free space also holds plenty of noise.
+
+use serde::Serialize;
+
+use crate::model::{Page, PageType, Record, SerialType, TextEncoding, Value};
+use crate::parser::{encode_varint, parse_record, parse_signed_varint, parse_varint};
+
+/// Lowest confidence a carved record needs to be reported at all
+pub const MIN_CARVE_CONFIDENCE: f32 = 0.3;
+
+/// Largest record header the carver will consider
+const MAX_HEADER_SIZE: u64 = 1024;
+
+/// Where a recovered record was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum CarveSource {
+    /// Inside a freeblock of a B-tree page
+    Freeblock,
+    /// Between the cell pointer array and the cell content area
+    Unallocated,
+    /// On a page in the freelist
+    FreelistPage,
+}
+
+/// A deleted record recovered from free space
+#[derive(Debug, Clone, Serialize)]
+pub struct RecoveredRecord {
+    /// Page the record was found on
+    pub page_number: u32,
+    /// Offset of the record header within the page
+    pub offset: usize,
+    /// Kind of free space the record was found in
+    pub source: CarveSource,
+    /// B-tree the record most likely belonged to
+    pub tree: Option<String>,
+    /// Rowid, if the cell prefix in front of the record survived
+    pub rowid: Option<i64>,
+    /// Decoded record
+    pub record: Record,
+    /// Whether part of the record header was overwritten and had to be inferred
+    pub header_reconstructed: bool,
+    /// How likely this is a real deleted row, from 0.0 to 1.0
+    pub confidence: f32,
+}
+
+/// Storage class of a value, used to compare carved records with live rows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageClass {
+    Null,
+    Integer,
+    Real,
+    Text,
+    Blob,
+}
+
+impl StorageClass {
+    fn of(serial_type: &SerialType) -> Option<Self> {
+        match serial_type {
+            SerialType::Null => Some(StorageClass::Null),
+            SerialType::Int8
+            | SerialType::Int16
+            | SerialType::Int24
+            | SerialType::Int32
+            | SerialType::Int48
+            | SerialType::Int64
+            | SerialType::Zero
+            | SerialType::One => Some(StorageClass::Integer),
+            SerialType::Float64 => Some(StorageClass::Real),
+            SerialType::Text(_) => Some(StorageClass::Text),
+            SerialType::Blob(_) => Some(StorageClass::Blob),
+            SerialType::Reserved(_) => None,
+        }
+    }
+}
+
+/// Column layout of the live rows of a B-tree
+#[derive(Debug, Clone, Default)]
+pub struct ColumnProfile {
+    /// Most common number of columns
+    pub column_count: usize,
+    /// Most common non-NULL storage class of each column (Null if always NULL)
+    pub classes: Vec<Option<StorageClass>>,
+}
+
+impl ColumnProfile {
+    /// Build a profile from live records. Returns None if there are none.
+    pub fn from_records<'a>(records: impl Iterator<Item = &'a Record>) -> Option<Self> {
+        let records: Vec<&Record> = records.collect();
+        let column_count = most_common(records.iter().map(|r| r.column_types.len()))?;
+
+        let classes = (0..column_count)
+            .map(|i| {
+                let column = records
+                    .iter()
+                    .filter(|r| r.column_types.len() == column_count)
+                    .filter_map(|r| StorageClass::of(&r.column_types[i]));
+                let non_null = most_common(column.clone().filter(|c| *c != StorageClass::Null));
+                non_null.or_else(|| column.clone().next())
+            })
+            .collect();
+
+        Some(Self { column_count, classes })
+    }
+
+    /// How well a record fits the profile, from 0.0 to 1.0
+    fn fit(&self, record: &Record) -> f32 {
+        if record.column_types.len() != self.column_count || self.column_count == 0 {
+            return 0.0;
+        }
+        let matching = record
+            .column_types
+            .iter()
+            .zip(&self.classes)
+            .map(|(st, expected)| {
+                let class = StorageClass::of(st);
+                if expected.is_none() || class == *expected || (is_numeric(class) && is_numeric(*expected)) {
+                    // Integral values in REAL columns are stored as integers
+                    1.0
+                } else if class == Some(StorageClass::Null) {
+                    // NULL fits any column, but says little about it
+                    0.5
+                } else {
+                    0.0
+                }
+            })
+            .sum::<f32>();
+        matching / self.column_count as f32
+    }
+}
+
+fn is_numeric(class: Option<StorageClass>) -> bool {
+    matches!(class, Some(StorageClass::Integer | StorageClass::Real))
+}
+
+fn most_common<T: PartialEq + Copy>(values: impl Iterator<Item = T>) -> Option<T> {
+    let mut counts: Vec<(T, usize)> = Vec::new();
+    for value in values {
+        match counts.iter_mut().find(|(v, _)| *v == value) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((value, 1)),
+        }
+    }
+    counts.into_iter().max_by_key(|&(_, count)| count).map(|(v, _)| v)
+}
+
+/// A record found in free space, before attribution to a tree
+#[derive(Clone)]
+struct Candidate {
+    offset: usize,
+    len: usize,
+    rowid: Option<i64>,
+    prefix_valid: bool,
+    header_reconstructed: bool,
+    record: Record,
+}
+
+/// Check whether a structurally valid record starts at `offset` and ends by `end`.
+/// Returns the header size and serial types.
+fn probe_record(data: &[u8], offset: usize, end: usize) -> Option<(usize, Vec<SerialType>)> {
+    let (header_size, len) = parse_varint(data.get(offset..end)?).ok()?;
+    if !(2..=MAX_HEADER_SIZE).contains(&header_size) {
+        return None;
+    }
+    let header_end = offset + header_size as usize;
+    if header_end > end {
+        return None;
+    }
+
+    let mut types = Vec::new();
+    let mut pos = offset + len;
+    while pos < header_end {
+        let (raw, len) = parse_varint(&data[pos..header_end]).ok()?;
+        let serial_type = SerialType::from_raw(raw);
+        if matches!(serial_type, SerialType::Reserved(_)) {
+            return None;
+        }
+        types.push(serial_type);
+        pos += len;
+    }
+    if pos != header_end || types.iter().all(|t| matches!(t, SerialType::Null)) {
+        return None;
+    }
+
+    let body: usize = types.iter().map(|t| t.size()).sum();
+    if header_end + body > end {
+        return None;
+    }
+    Some((header_size as usize, types))
+}
+
+/// Look for the cell prefix (payload size and, for table leaves, rowid) just
+/// before a record. Returns the rowid if one was found.
+fn find_cell_prefix(data: &[u8], region_start: usize, offset: usize, payload_len: usize, table: bool) -> Option<Option<i64>> {
+    for start in offset.saturating_sub(18).max(region_start)..offset {
+        let Ok((payload_size, len)) = parse_varint(&data[start..offset]) else { continue };
+        if payload_size as usize != payload_len {
+            continue;
+        }
+        if !table {
+            if start + len == offset {
+                return Some(None);
+            }
+            continue;
+        }
+        if let Ok((rowid, rowid_len)) = parse_signed_varint(&data[start + len..offset]) {
+            if start + len + rowid_len == offset {
+                return Some(Some(rowid));
+            }
+        }
+    }
+    None
+}
+
+/// Find every structurally valid record starting in `start..end`. Candidates may
+/// overlap; the best-scoring non-overlapping ones are picked later.
+fn scan_region(data: &[u8], start: usize, end: usize, table: bool, encoding: TextEncoding) -> Vec<Candidate> {
+    (start..end)
+        .filter_map(|offset| {
+            let (header_size, types) = probe_record(data, offset, end)?;
+            let len = header_size + types.iter().map(|t| t.size()).sum::<usize>();
+            let record = parse_record(&data[offset..offset + len], encoding).ok()?;
+            let prefix = find_cell_prefix(data, start, offset, len, table);
+            Some(Candidate {
+                offset,
+                len,
+                rowid: prefix.flatten(),
+                prefix_valid: prefix.is_some(),
+                header_reconstructed: false,
+                record,
+            })
+        })
+        .collect()
+}
+
+/// Read `count` serial types starting at `pos`, returning them and the offset after the last
+fn read_serial_types(data: &[u8], mut pos: usize, end: usize, count: usize) -> Option<(Vec<u64>, usize)> {
+    let mut types = Vec::with_capacity(count);
+    for _ in 0..count {
+        let (raw, len) = parse_varint(data.get(pos..end)?).ok()?;
+        if matches!(SerialType::from_raw(raw), SerialType::Reserved(_)) {
+            return None;
+        }
+        types.push(raw);
+        pos += len;
+    }
+    Some((types, pos))
+}
+
+/// Rebuild the record of a table leaf cell whose first four bytes were overwritten
+/// by a freeblock header. With 1-byte payload-size and rowid varints, the record
+/// header size, and possibly the first serial type, are lost; the profile of the
+/// live rows fills them in.
+fn reconstruct_freeblock_record(
+    data: &[u8],
+    block_start: usize,
+    block_end: usize,
+    profile: &ColumnProfile,
+    encoding: TextEncoding,
+) -> Option<Candidate> {
+    let types_start = block_start + 4;
+    let mut best: Option<(f32, Candidate)> = None;
+
+    // `lost` is the number of leading serial types overwritten along with the header size
+    for lost in 0..=1usize {
+        let Some(known_count) = profile.column_count.checked_sub(lost) else { continue };
+        let Some((mut types, pos)) = read_serial_types(data, types_start, block_end, known_count) else {
+            continue;
+        };
+
+        let body_start = pos;
+        let known_size: usize = types.iter().map(|&t| SerialType::from_raw(t).size()).sum();
+        if lost == 1 {
+            // Infer the lost type from the space left in the freeblock
+            let Some(remaining) = block_end.checked_sub(body_start + known_size) else { continue };
+            let lost_type = match profile.classes.first().copied().flatten() {
+                Some(StorageClass::Null) => 0,
+                Some(StorageClass::Integer) => match remaining {
+                    1 => 1,
+                    2 => 2,
+                    3 => 3,
+                    4 => 4,
+                    6 => 5,
+                    8 => 6,
+                    _ => continue,
+                },
+                Some(StorageClass::Real) if remaining == 8 => 7,
+                Some(StorageClass::Text) => remaining as u64 * 2 + 13,
+                Some(StorageClass::Blob) => remaining as u64 * 2 + 12,
+                _ => continue,
+            };
+            types.insert(0, lost_type);
+        }
+
+        let body_size: usize = types.iter().map(|&t| SerialType::from_raw(t).size()).sum();
+        if body_start + body_size > block_end {
+            continue;
+        }
+
+        // Reassemble a complete record from the inferred header and the surviving bytes
+        let type_bytes: Vec<u8> = types.iter().flat_map(|&t| encode_varint(t)).collect();
+        let mut bytes = encode_varint(1 + type_bytes.len() as u64);
+        if bytes.len() != 1 {
+            continue;
+        }
+        bytes.extend_from_slice(&type_bytes);
+        bytes.extend_from_slice(&data[body_start..body_start + body_size]);
+
+        let Ok(record) = parse_record(&bytes, encoding) else { continue };
+        if record.values.iter().all(|v| matches!(v, Value::Null)) || record.values.iter().any(implausible_text) {
+            continue;
+        }
+
+        let fit = profile.fit(&record);
+        if best.as_ref().is_none_or(|(best_fit, _)| fit > *best_fit) {
+            best = Some((
+                fit,
+                Candidate {
+                    offset: body_start + body_size - bytes.len(),
+                    len: bytes.len(),
+                    rowid: None,
+                    prefix_valid: false,
+                    header_reconstructed: true,
+                    record,
+                },
+            ));
+        }
+    }
+
+    best.map(|(_, candidate)| candidate)
+}
+
+/// Find byte ranges in `start..end` that still begin with a plausible freeblock header
+fn stale_freeblocks(data: &[u8], start: usize, end: usize, usable_size: u32) -> Vec<(usize, usize)> {
+    (start..end.saturating_sub(4))
+        .filter_map(|offset| {
+            let next = u16::from_be_bytes([data[offset], data[offset + 1]]) as usize;
+            let size = u16::from_be_bytes([data[offset + 2], data[offset + 3]]) as usize;
+            let valid_next = next == 0 || (next >= offset + size && next < usable_size as usize);
+            (size > 4 && offset + size <= end && valid_next).then_some((offset, offset + size))
+        })
+        .collect()
+}
+
+/// Text that is malformed or contains control characters, as random bytes decoded as text do
+fn implausible_text(value: &Value) -> bool {
+    match value {
+        Value::MalformedText(_) => true,
+        Value::Text(text) => text.chars().any(|c| c.is_control() && !c.is_whitespace()),
+        _ => false,
+    }
+}
+
+/// Score a candidate against the profile of the tree it is attributed to
+fn score(candidate: &Candidate, profile: Option<&ColumnProfile>) -> f32 {
+    let mut score = 0.4;
+    match profile.map(|p| p.fit(&candidate.record)) {
+        // Matching column count, then storage classes column by column
+        Some(fit) if fit > 0.0 => score += 0.1 + 0.3 * fit - 0.4 * (1.0 - fit),
+        Some(_) => score -= 0.1,
+        // Nothing to compare with
+        None => score -= 0.15,
+    }
+    if candidate.prefix_valid {
+        score += 0.2;
+    }
+    if candidate.header_reconstructed {
+        score -= 0.1;
+    }
+    if candidate.record.values.iter().any(implausible_text) {
+        score -= 0.4;
+    }
+    score.clamp(0.0, 1.0)
+}
+
+/// Keep the highest-scoring candidates that do not overlap each other
+fn select_candidates<T>(mut scored: Vec<(f32, Candidate, T)>) -> Vec<(f32, Candidate, T)> {
+    scored.retain(|(confidence, _, _)| *confidence >= MIN_CARVE_CONFIDENCE);
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.offset.cmp(&b.1.offset)));
+
+    let mut selected: Vec<(f32, Candidate, T)> = Vec::new();
+    for (confidence, candidate, extra) in scored {
+        let (start, end) = (candidate.offset, candidate.offset + candidate.len);
+        if selected.iter().all(|(_, c, _)| end <= c.offset || start >= c.offset + c.len) {
+            selected.push((confidence, candidate, extra));
+        }
+    }
+    selected
+}
+
+fn into_recovered(
+    candidate: Candidate,
+    page_number: u32,
+    source: CarveSource,
+    tree: Option<String>,
+    confidence: f32,
+) -> RecoveredRecord {
+    RecoveredRecord {
+        page_number,
+        offset: candidate.offset,
+        source,
+        tree,
+        rowid: candidate.rowid,
+        record: candidate.record,
+        header_reconstructed: candidate.header_reconstructed,
+        confidence,
+    }
+}
+
+/// Carve deleted records from the freeblocks and unallocated gap of a live leaf page
+pub fn carve_btree_page(
+    page: &Page,
+    tree: &str,
+    profile: Option<&ColumnProfile>,
+    usable_size: u32,
+    encoding: TextEncoding,
+) -> Vec<RecoveredRecord> {
+    let Some(header) = &page.header else { return Vec::new() };
+    if !page.page_type.is_leaf() {
+        return Vec::new();
+    }
+
+    let data = &page.raw_data;
+    let table = page.page_type == PageType::LeafTable;
+    let header_offset = if page.page_number == 1 { 100 } else { 0 };
+    let pointer_array_end = header_offset + page.page_type.header_size() + page.cell_pointers.len() * 2;
+    let content_start = match header.cell_content_start {
+        0 => 65536,
+        start => start as usize,
+    }
+    .min(usable_size as usize);
+
+    let mut found = Vec::new();
+
+    if pointer_array_end < content_start {
+        for candidate in scan_region(data, pointer_array_end, content_start, table, encoding) {
+            found.push((candidate, CarveSource::Unallocated));
+        }
+        // Freeblocks next to the content area are absorbed into the gap, headers and all
+        if let Some(profile) = profile.filter(|_| table) {
+            for (start, end) in stale_freeblocks(data, pointer_array_end, content_start, usable_size) {
+                if let Some(candidate) = reconstruct_freeblock_record(data, start, end, profile, encoding) {
+                    found.push((candidate, CarveSource::Unallocated));
+                }
+            }
+        }
+    }
+
+    for block in &page.freeblocks {
+        let start = block.offset as usize;
+        let end = start + block.size as usize;
+
+        // Freed cells merged into one freeblock each had their first four bytes overwritten
+        if let Some(profile) = profile.filter(|_| table) {
+            let mut cell_start = start;
+            while let Some(candidate) = reconstruct_freeblock_record(data, cell_start, end, profile, encoding) {
+                cell_start = candidate.offset + candidate.len;
+                found.push((candidate, CarveSource::Freeblock));
+            }
+        }
+        for candidate in scan_region(data, start + 4, end, table, encoding) {
+            found.push((candidate, CarveSource::Freeblock));
+        }
+    }
+
+    let scored = found
+        .into_iter()
+        .map(|(candidate, source)| (score(&candidate, profile), candidate, source))
+        .collect();
+
+    select_candidates(scored)
+        .into_iter()
+        .map(|(confidence, candidate, source)| {
+            into_recovered(candidate, page.page_number, source, Some(tree.to_string()), confidence)
+        })
+        .collect()
+}
+
+/// Carve records from a freelist page, which keeps whatever it held before it was freed.
+/// Each record is attributed to the tree whose live rows it fits best.
+pub fn carve_free_page(
+    data: &[u8],
+    page_number: u32,
+    skip: usize,
+    profiles: &[(String, ColumnProfile)],
+    usable_size: u32,
+    encoding: TextEncoding,
+) -> Vec<RecoveredRecord> {
+    let end = (usable_size as usize).min(data.len());
+    if skip >= end {
+        return Vec::new();
+    }
+
+    let scored = scan_region(data, skip, end, true, encoding)
+        .into_iter()
+        .map(|candidate| {
+            let best = profiles
+                .iter()
+                .filter(|(_, profile)| profile.fit(&candidate.record) > 0.0)
+                .map(|(name, profile)| (name, score(&candidate, Some(profile))))
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+            let (tree, confidence) = match best {
+                Some((name, confidence)) => (Some(name.clone()), confidence),
+                None => (None, score(&candidate, None)),
+            };
+            (confidence, candidate, tree)
+        })
+        .collect();
+
+    select_candidates(scored)
+        .into_iter()
+        .map(|(confidence, candidate, tree)| {
+            into_recovered(candidate, page_number, CarveSource::FreelistPage, tree, confidence)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{btree_page, table_leaf_cell, text, PAGE_SIZE};
+    use crate::parser::parse_page;
+
+    const ROWS: [(i64, &str, i64); 3] = [(1, "alpha", 30), (2, "bravo", 41), (3, "charlie", 52)];
+
+    fn cells() -> Vec<Vec<u8>> {
+        ROWS.iter().map(|&(rowid, name, age)| table_leaf_cell(rowid, &[text(name), Value::Integer(age)])).collect()
+    }
+
+    fn profile(page: &[u8]) -> ColumnProfile {
+        let page = parse_page(page, 2, PAGE_SIZE, PAGE_SIZE, TextEncoding::Utf8).unwrap();
+        ColumnProfile::from_records(page.cells.iter().filter_map(|c| c.payload())).unwrap()
+    }
+
+    /// Remove cell `index` from the pointer array, as deleting it does
+    fn remove_pointer(page: &mut [u8], index: usize) -> usize {
+        let count = u16::from_be_bytes([page[3], page[4]]) as usize;
+        let pointer = 8 + index * 2;
+        let offset = u16::from_be_bytes([page[pointer], page[pointer + 1]]) as usize;
+        page.copy_within(pointer + 2..8 + count * 2, pointer);
+        page[3..5].copy_from_slice(&(count as u16 - 1).to_be_bytes());
+        offset
+    }
+
+    fn carve(page: &[u8], profile: &ColumnProfile) -> Vec<RecoveredRecord> {
+        let page = parse_page(page, 2, PAGE_SIZE, PAGE_SIZE, TextEncoding::Utf8).unwrap();
+        carve_btree_page(&page, "t", Some(profile), PAGE_SIZE, TextEncoding::Utf8)
+    }
+
+    fn values(record: &RecoveredRecord) -> &[Value] {
+        &record.record.values
+    }
+
+    #[test]
+    fn test_carve_freeblock() {
+        // Deleting the middle row turns its cell into a freeblock, overwriting the
+        // payload size, rowid, record header size and first serial type
+        let mut page = btree_page(2, 0x0d, &cells(), None);
+        let live = profile(&page);
+        let offset = remove_pointer(&mut page, 1);
+        let size = cells()[1].len() as u16;
+        page[offset..offset + 4].copy_from_slice(&[0, 0, (size >> 8) as u8, size as u8]);
+        page[1..3].copy_from_slice(&(offset as u16).to_be_bytes());
+
+        let recovered = carve(&page, &live);
+        let record = recovered.iter().find(|r| r.source == CarveSource::Freeblock).unwrap();
+        assert_eq!(values(record), [text("bravo"), Value::Integer(41)]);
+        assert!(record.header_reconstructed);
+        assert_eq!(record.rowid, None);
+        assert_eq!(recovered.len(), 1);
+    }
+
+    #[test]
+    fn test_carve_unallocated_space() {
+        // Deleting the cell at the start of the content area moves the content start past it
+        let mut page = btree_page(2, 0x0d, &cells(), None);
+        let live = profile(&page);
+        let offset = remove_pointer(&mut page, 2);
+        page[5..7].copy_from_slice(&((offset + cells()[2].len()) as u16).to_be_bytes());
+
+        let recovered = carve(&page, &live);
+        assert_eq!(recovered.len(), 1);
+        assert_eq!(recovered[0].source, CarveSource::Unallocated);
+        assert_eq!(recovered[0].rowid, Some(3));
+        assert_eq!(values(&recovered[0]), [text("charlie"), Value::Integer(52)]);
+        assert!(!recovered[0].header_reconstructed);
+    }
+
+    #[test]
+    fn test_carve_freelist_leaf() {
+        // A freed leaf keeps the cells it held; records are attributed to the tree they fit
+        let page = btree_page(2, 0x0d, &cells(), None);
+        let profiles = vec![
+            ("other".to_string(), ColumnProfile { column_count: 3, classes: vec![Some(StorageClass::Blob); 3] }),
+            ("t".to_string(), profile(&page)),
+        ];
+
+        let recovered = carve_free_page(&page, 5, 0, &profiles, PAGE_SIZE, TextEncoding::Utf8);
+        let rowids: Vec<_> = recovered.iter().map(|r| r.rowid).collect();
+        assert_eq!(rowids, [Some(3), Some(2), Some(1)]);
+        assert!(recovered.iter().all(|r| r.source == CarveSource::FreelistPage && r.tree.as_deref() == Some("t")));
+        assert_eq!(values(&recovered[1]), [text("bravo"), Value::Integer(41)]);
+    }
+
+    #[test]
+    fn test_confidence_ordering() {
+        let page = btree_page(2, 0x0d, &cells(), None);
+        let live = profile(&page);
+        let mut candidates = scan_region(&page, 8, page.len(), true, TextEncoding::Utf8);
+        candidates.retain(|c| c.prefix_valid);
+        let intact = &candidates[0];
+
+        // An intact cell that fits the live rows beats one without anything to compare with
+        let fitting = score(intact, Some(&live));
+        let unprofiled = score(intact, None);
+        assert!(fitting > unprofiled);
+
+        // Losing the cell prefix, or inferring part of the header, lowers confidence
+        let without_prefix = Candidate { prefix_valid: false, ..intact.clone() };
+        let reconstructed = Candidate { header_reconstructed: true, ..without_prefix.clone() };
+        assert!(fitting > score(&without_prefix, Some(&live)));
+        assert!(score(&without_prefix, Some(&live)) > score(&reconstructed, Some(&live)));
+
+        // A record with the wrong shape for the tree scores well below one that fits
+        let mismatched = ColumnProfile { column_count: 3, classes: vec![Some(StorageClass::Blob); 3] };
+        assert!(score(intact, Some(&mismatched)) < score(&reconstructed, Some(&live)));
+    }
+}
@@ -0,0 +1,253 @@
+//! SQLite's ordering of index keys: record comparison with per-column collations and sort order.
+
+use std::cmp::Ordering;
+
+use serde::Serialize;
+
+use crate::error::{Result, SqliteVizError};
+use crate::model::{TextEncoding, Value};
+
+/// A built-in collating sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub enum Collation {
+    /// Byte-wise comparison of the text in the database encoding
+    #[default]
+    Binary,
+    /// Like BINARY, with ASCII letters folded to lower case
+    NoCase,
+    /// Like BINARY, ignoring trailing spaces
+    Rtrim,
+}
+
+impl Collation {
+    /// Look up a collation by name, ignoring case
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "BINARY" => Ok(Collation::Binary),
+            "NOCASE" => Ok(Collation::NoCase),
+            "RTRIM" => Ok(Collation::Rtrim),
+            _ => Err(SqliteVizError::SchemaError(format!("unsupported collating sequence {}", name))),
+        }
+    }
+
+    /// Compare two strings as stored in a database with the given text encoding.
+    /// Only BINARY works on the stored encoding; NOCASE and RTRIM compare the text as UTF-8.
+    pub fn compare(&self, a: &str, b: &str, encoding: TextEncoding) -> Ordering {
+        match self {
+            Collation::Binary => compare_encoded(a, b, encoding),
+            Collation::NoCase => a.bytes().map(|c| c.to_ascii_lowercase()).cmp(b.bytes().map(|c| c.to_ascii_lowercase())),
+            Collation::Rtrim => compare_rtrim(a.as_bytes(), b.as_bytes()),
+        }
+    }
+}
+
+/// memcmp() over the common length; if that is equal, the strings are equal when whatever
+/// is left of the longer one is all spaces, otherwise the longer one sorts last
+fn compare_rtrim(a: &[u8], b: &[u8]) -> Ordering {
+    let common = a.len().min(b.len());
+    a[..common].cmp(&b[..common]).then_with(|| {
+        if a[common..].iter().chain(&b[common..]).all(|&c| c == b' ') {
+            Ordering::Equal
+        } else {
+            a.len().cmp(&b.len())
+        }
+    })
+}
+
+/// memcmp() order of the encoded strings; for UTF-16LE this is not code point order
+fn compare_encoded(a: &str, b: &str, encoding: TextEncoding) -> Ordering {
+    match encoding {
+        TextEncoding::Utf8 => a.as_bytes().cmp(b.as_bytes()),
+        TextEncoding::Utf16Be => a.encode_utf16().cmp(b.encode_utf16()),
+        TextEncoding::Utf16Le => a
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .cmp(b.encode_utf16().flat_map(u16::to_le_bytes)),
+    }
+}
+
+/// Collation and sort order of one column of an index key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub struct KeyColumn {
+    pub collation: Collation,
+    pub descending: bool,
+}
+
+/// Compares records the way SQLite orders the entries of an index B-tree
+#[derive(Debug, Clone)]
+pub struct RecordComparator {
+    /// Key columns; columns past these (such as the trailing rowid) are BINARY and ascending
+    pub columns: Vec<KeyColumn>,
+    pub encoding: TextEncoding,
+}
+
+impl RecordComparator {
+    pub fn new(columns: Vec<KeyColumn>, encoding: TextEncoding) -> Self {
+        Self { columns, encoding }
+    }
+
+    /// Compare two records column by column; if one is a prefix of the other, the shorter sorts first
+    pub fn compare(&self, a: &[Value], b: &[Value]) -> Ordering {
+        self.compare_prefix(a, b).then(a.len().cmp(&b.len()))
+    }
+
+    /// Compare only the columns both records have, so a search key matches every
+    /// entry that starts with it
+    pub fn compare_prefix(&self, a: &[Value], b: &[Value]) -> Ordering {
+        for (i, (x, y)) in a.iter().zip(b).enumerate() {
+            let column = self.columns.get(i).copied().unwrap_or_default();
+            let ordering = compare_values(x, y, column.collation, self.encoding);
+            let ordering = if column.descending { ordering.reverse() } else { ordering };
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        Ordering::Equal
+    }
+
+    /// Number of leading columns on which two records are equal, NULLs comparing equal
+    pub fn equal_prefix(&self, a: &[Value], b: &[Value]) -> usize {
+        a.iter()
+            .zip(b)
+            .enumerate()
+            .take_while(|(i, (x, y))| {
+                let column = self.columns.get(*i).copied().unwrap_or_default();
+                compare_values(x, y, column.collation, self.encoding) == Ordering::Equal
+            })
+            .count()
+    }
+}
+
+/// Compare two values: NULL sorts before numbers, numbers before text and text before blobs.
+/// Integers and reals compare by numeric value, text by collation and blobs byte-wise.
+pub fn compare_values(a: &Value, b: &Value, collation: Collation, encoding: TextEncoding) -> Ordering {
+    match (a, b) {
+        (Value::Integer(x), Value::Integer(y)) => x.cmp(y),
+        (Value::Real(x), Value::Real(y)) => x.partial_cmp(y).unwrap_or(Ordering::Equal),
+        (Value::Integer(x), Value::Real(y)) => compare_int_real(*x, *y),
+        (Value::Real(x), Value::Integer(y)) => compare_int_real(*y, *x).reverse(),
+        (Value::Text(x) | Value::MalformedText(x), Value::Text(y) | Value::MalformedText(y)) => {
+            collation.compare(x, y, encoding)
+        }
+        (Value::Blob(x), Value::Blob(y)) => x.cmp(y),
+        _ => type_rank(a).cmp(&type_rank(b)),
+    }
+}
+
+fn type_rank(value: &Value) -> u8 {
+    match value {
+        Value::Null => 0,
+        Value::Integer(_) | Value::Real(_) => 1,
+        Value::Text(_) | Value::MalformedText(_) => 2,
+        Value::Blob(_) => 3,
+    }
+}
+
+/// Compare an integer with a real exactly, without rounding the integer to a double
+fn compare_int_real(i: i64, r: f64) -> Ordering {
+    if r.is_nan() || r < -9_223_372_036_854_775_808.0 {
+        return Ordering::Greater;
+    }
+    if r >= 9_223_372_036_854_775_808.0 {
+        return Ordering::Less;
+    }
+    let whole = r.trunc();
+    i.cmp(&(whole as i64)).then_with(|| 0.0.partial_cmp(&(r - whole)).unwrap_or(Ordering::Equal))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(s: &str) -> Value {
+        Value::Text(s.to_string())
+    }
+
+    fn compare(a: &Value, b: &Value) -> Ordering {
+        compare_values(a, b, Collation::Binary, TextEncoding::Utf8)
+    }
+
+    #[test]
+    fn test_type_order() {
+        // NULL < numbers < text < blobs, whatever the values
+        let ascending = [
+            Value::Null,
+            Value::Real(-1e300),
+            Value::Integer(0),
+            Value::Real(1e300),
+            text(""),
+            text("zzz"),
+            Value::Blob(Vec::new()),
+            Value::Blob(vec![0xff]),
+        ];
+        for (i, a) in ascending.iter().enumerate() {
+            for (j, b) in ascending.iter().enumerate() {
+                assert_eq!(compare(a, b), i.cmp(&j), "{:?} vs {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_integer_real_comparison_is_exact() {
+        assert_eq!(compare(&Value::Integer(3), &Value::Real(3.0)), Ordering::Equal);
+        assert_eq!(compare(&Value::Integer(3), &Value::Real(3.5)), Ordering::Less);
+        assert_eq!(compare(&Value::Integer(-3), &Value::Real(-3.5)), Ordering::Greater);
+        assert_eq!(compare(&Value::Real(-3.5), &Value::Integer(-3)), Ordering::Less);
+
+        // 2^53 + 1 rounds to 2^53 as a double, but is still larger
+        assert_eq!(compare(&Value::Integer((1 << 53) + 1), &Value::Real(9007199254740992.0)), Ordering::Greater);
+
+        // i64::MAX rounds up to 2^63, which no integer reaches; i64::MIN is exactly -2^63
+        let two_63 = 2f64.powi(63);
+        assert_eq!(compare(&Value::Integer(i64::MAX), &Value::Real(two_63)), Ordering::Less);
+        assert_eq!(compare(&Value::Integer(i64::MIN), &Value::Real(-two_63)), Ordering::Equal);
+        assert_eq!(compare(&Value::Integer(i64::MIN), &Value::Real(-1e19)), Ordering::Greater);
+        assert_eq!(compare(&Value::Integer(i64::MAX), &Value::Real(f64::INFINITY)), Ordering::Less);
+    }
+
+    #[test]
+    fn test_collations() {
+        let utf8 = TextEncoding::Utf8;
+        assert_eq!(Collation::NoCase.compare("ABC", "abc", utf8), Ordering::Equal);
+        assert_eq!(Collation::NoCase.compare("a", "B", utf8), Ordering::Less);
+        assert_eq!(Collation::Binary.compare("a", "B", utf8), Ordering::Greater);
+        // Only ASCII letters are folded
+        assert_ne!(Collation::NoCase.compare("\u{c9}", "\u{e9}", utf8), Ordering::Equal);
+
+        assert_eq!(Collation::Rtrim.compare("a  ", "a", utf8), Ordering::Equal);
+        assert_eq!(Collation::Rtrim.compare("a \u{1}", "a", utf8), Ordering::Greater);
+        assert_eq!(Collation::Rtrim.compare("a\u{1}", "a ", utf8), Ordering::Less);
+        assert_eq!(Collation::Rtrim.compare("a ", "a\u{1}", utf8), Ordering::Greater);
+
+        // BINARY compares the stored bytes: U+0101 is 01 01 in UTF-16LE, U+0200 is 00 02
+        assert_eq!(Collation::Binary.compare("\u{101}", "\u{200}", TextEncoding::Utf16Le), Ordering::Greater);
+        assert_eq!(Collation::Binary.compare("\u{101}", "\u{200}", TextEncoding::Utf16Be), Ordering::Less);
+    }
+
+    #[test]
+    fn test_descending_columns_and_prefixes() {
+        let comparator = RecordComparator::new(
+            vec![
+                KeyColumn { collation: Collation::Binary, descending: true },
+                KeyColumn { collation: Collation::NoCase, descending: false },
+            ],
+            TextEncoding::Utf8,
+        );
+        let a = [Value::Integer(2), text("x"), Value::Integer(7)];
+        let b = [Value::Integer(1), text("X"), Value::Integer(7)];
+        assert_eq!(comparator.compare(&a, &b), Ordering::Less);
+
+        let c = [Value::Integer(2), text("X"), Value::Integer(8)];
+        assert_eq!(comparator.compare(&a, &c), Ordering::Less);
+        assert_eq!(comparator.equal_prefix(&a, &c), 2);
+
+        // A search key matches every entry it is a prefix of, but sorts before them
+        let key = [Value::Integer(2)];
+        assert_eq!(comparator.compare_prefix(&key, &a), Ordering::Equal);
+        assert_eq!(comparator.compare(&key, &a), Ordering::Less);
+        assert_eq!(comparator.compare_prefix(&key, &b), Ordering::Less);
+
+        // NULLs compare equal to each other
+        assert_eq!(comparator.equal_prefix(&[Value::Null, Value::Null], &[Value::Null, Value::Null]), 2);
+    }
+}
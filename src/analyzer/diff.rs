@@ -0,0 +1,324 @@
+//! Logical (row-level) comparison of two databases.
+
+use std::cmp::Ordering;
+
+use crate::error::Result;
+use crate::model::{CellChange, CellChangeKind, ColumnChange, Page, RowChange, TableDiff, TableRow, Value};
+
+/// Compare the columns of two versions of a row
+fn column_changes(old: &[Value], new: &[Value]) -> Vec<ColumnChange> {
+    (0..old.len().max(new.len()))
+        .filter_map(|column| {
+            let (old, new) = (old.get(column), new.get(column));
+            (old != new).then(|| ColumnChange { column, old: old.cloned(), new: new.cloned() })
+        })
+        .collect()
+}
+
+/// Next readable row of a scan; rows that cannot be read are recorded in `errors` and passed over
+fn next_row(rows: &mut impl Iterator<Item = Result<TableRow>>, side: &str, errors: &mut Vec<String>) -> Option<TableRow> {
+    loop {
+        match rows.next()? {
+            Ok(row) => return Some(row),
+            Err(e) => errors.push(format!("{}: {}", side, e)),
+        }
+    }
+}
+
+/// Diff the rows of one table by merging two rowid-ordered scans.
+/// Either side may be None when the table exists in only one database.
+/// Pages and records that cannot be read are listed in `errors`, and the merge carries on past them.
+pub fn diff_table_rows<I, J>(
+    name: &str,
+    old_root: Option<u32>,
+    new_root: Option<u32>,
+    mut old_rows: I,
+    mut new_rows: J,
+) -> TableDiff
+where
+    I: Iterator<Item = Result<TableRow>>,
+    J: Iterator<Item = Result<TableRow>>,
+{
+    let mut changes = Vec::new();
+    let mut errors = Vec::new();
+
+    let mut old = next_row(&mut old_rows, "old", &mut errors);
+    let mut new = next_row(&mut new_rows, "new", &mut errors);
+    loop {
+        let order = match (&old, &new) {
+            (None, None) => break,
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(o), Some(n)) => o.rowid.cmp(&n.rowid),
+        };
+        match order {
+            Ordering::Less => {
+                let row = old.take().unwrap();
+                changes.push(RowChange::Deleted { rowid: row.rowid, values: row.record.values });
+                old = next_row(&mut old_rows, "old", &mut errors);
+            }
+            Ordering::Greater => {
+                let row = new.take().unwrap();
+                changes.push(RowChange::Inserted { rowid: row.rowid, values: row.record.values });
+                new = next_row(&mut new_rows, "new", &mut errors);
+            }
+            Ordering::Equal => {
+                let (o, n) = (old.take().unwrap(), new.take().unwrap());
+                let columns = column_changes(&o.record.values, &n.record.values);
+                if !columns.is_empty() {
+                    changes.push(RowChange::Changed { rowid: o.rowid, columns });
+                }
+                old = next_row(&mut old_rows, "old", &mut errors);
+                new = next_row(&mut new_rows, "new", &mut errors);
+            }
+        }
+    }
+
+    TableDiff {
+        name: name.to_string(),
+        old_root,
+        new_root,
+        skipped: None,
+        changes,
+        errors,
+    }
+}
+
+/// Number of bytes that differ between two versions of a page
+pub fn count_changed_bytes(old: &[u8], new: &[u8]) -> usize {
+    let common = old.iter().zip(new).filter(|(a, b)| a != b).count();
+    common + old.len().abs_diff(new.len())
+}
+
+/// A cell as it sits on one version of a page
+struct PlacedCell<'a> {
+    index: usize,
+    offset: u16,
+    rowid: Option<i64>,
+    bytes: &'a [u8],
+}
+
+fn placed_cells(page: &Page) -> Vec<PlacedCell<'_>> {
+    page.cells
+        .iter()
+        .enumerate()
+        .map(|(index, cell)| {
+            let start = (cell.cell_offset() as usize).min(page.raw_data.len());
+            let end = (start + cell.cell_size()).min(page.raw_data.len());
+            PlacedCell { index, offset: cell.cell_offset(), rowid: cell.rowid(), bytes: &page.raw_data[start..end] }
+        })
+        .collect()
+}
+
+/// Compare the cells of two versions of a B-tree page of the same type.
+/// Returns whether the page header or cell pointer array changed, and the cells that changed:
+/// cells with identical bytes are matched first (moved if the offset differs), then, if
+/// `match_rowids` is set, table cells with the same rowid (rewritten); whatever is left was
+/// added or removed. Rowids only identify the same row if the page stayed in the same tree.
+pub fn diff_page_cells(old: &Page, new: &Page, match_rowids: bool) -> (bool, Vec<CellChange>) {
+    let header_offset = if new.page_number == 1 { 100 } else { 0 };
+    let header_end = |page: &Page| header_offset + page.page_type.header_size() + page.cell_pointers.len() * 2;
+    let header_len = header_end(old).max(header_end(new));
+    let header_changed = old.raw_data.get(header_offset..header_len) != new.raw_data.get(header_offset..header_len);
+
+    let mut old_cells: Vec<Option<PlacedCell>> = placed_cells(old).into_iter().map(Some).collect();
+    let mut new_cells: Vec<Option<PlacedCell>> = placed_cells(new).into_iter().map(Some).collect();
+    let mut changes = Vec::new();
+
+    let change = |kind, old: Option<&PlacedCell>, new: Option<&PlacedCell>| CellChange {
+        kind,
+        rowid: new.or(old).and_then(|c| c.rowid),
+        old_index: old.map(|c| c.index),
+        new_index: new.map(|c| c.index),
+        old_offset: old.map(|c| c.offset),
+        new_offset: new.map(|c| c.offset),
+        size: new.or(old).map_or(0, |c| c.bytes.len()),
+    };
+
+    // Identical bytes: unchanged in place, otherwise moved. Prefer same-offset matches.
+    for same_offset in [true, false] {
+        for slot in new_cells.iter_mut() {
+            let Some(n) = slot else { continue };
+            let found = old_cells.iter_mut().find(|o| {
+                o.as_ref().is_some_and(|o| o.bytes == n.bytes && (!same_offset || o.offset == n.offset))
+            });
+            if let Some(found) = found {
+                let o = found.take().unwrap();
+                if o.offset != n.offset {
+                    changes.push(change(CellChangeKind::Moved, Some(&o), Some(n)));
+                }
+                *slot = None;
+            }
+        }
+    }
+
+    // Same rowid, different bytes
+    for slot in new_cells.iter_mut().filter(|_| match_rowids) {
+        let Some(n) = slot else { continue };
+        let Some(rowid) = n.rowid else { continue };
+        if let Some(found) = old_cells.iter_mut().find(|o| o.as_ref().is_some_and(|o| o.rowid == Some(rowid))) {
+            let o = found.take().unwrap();
+            changes.push(change(CellChangeKind::Rewritten, Some(&o), Some(n)));
+            *slot = None;
+        }
+    }
+
+    for o in old_cells.iter().flatten() {
+        changes.push(change(CellChangeKind::Removed, Some(o), None));
+    }
+    for n in new_cells.iter().flatten() {
+        changes.push(change(CellChangeKind::Added, None, Some(n)));
+    }
+    changes.sort_by_key(|c| (c.new_index.or(c.old_index), c.new_index.is_none()));
+
+    (header_changed, changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::SqliteVizError;
+    use crate::fixtures::{btree_page, record, table_leaf_cell, text, PAGE_SIZE};
+    use crate::model::TextEncoding;
+    use crate::parser::{parse_page, parse_record};
+
+    fn rows(rows: &[(i64, &[Value])]) -> impl Iterator<Item = Result<TableRow>> {
+        rows.iter()
+            .map(|&(rowid, values)| {
+                Ok(TableRow { rowid, page_number: 2, record: parse_record(&record(values), TextEncoding::Utf8)? })
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    fn diff(old: &[(i64, &[Value])], new: &[(i64, &[Value])]) -> TableDiff {
+        diff_table_rows("t", Some(2), Some(2), rows(old), rows(new))
+    }
+
+    #[test]
+    fn test_insert_and_delete() {
+        let a = [text("a")];
+        let b = [text("b")];
+        let c = [text("c")];
+        let diff = diff(&[(1, &a), (2, &b)], &[(1, &a), (3, &c)]);
+
+        assert_eq!((diff.inserted(), diff.deleted(), diff.changed()), (1, 1, 0));
+        assert!(matches!(&diff.changes[0], RowChange::Deleted { rowid: 2, values } if values[..] == b));
+        assert!(matches!(&diff.changes[1], RowChange::Inserted { rowid: 3, values } if values[..] == c));
+    }
+
+    #[test]
+    fn test_changed_columns() {
+        let diff = diff(
+            &[(1, &[text("a"), Value::Integer(1), Value::Null])],
+            &[(1, &[text("a"), Value::Integer(2), text("x")])],
+        );
+
+        assert_eq!(diff.changes.len(), 1);
+        let RowChange::Changed { rowid: 1, columns } = &diff.changes[0] else { panic!("{:?}", diff.changes) };
+        let changed: Vec<_> = columns.iter().map(|c| (c.column, c.old.clone(), c.new.clone())).collect();
+        assert_eq!(
+            changed,
+            [(1, Some(Value::Integer(1)), Some(Value::Integer(2))), (2, Some(Value::Null), Some(text("x")))]
+        );
+    }
+
+    #[test]
+    fn test_unequal_tails() {
+        // Rows left over on either side once the other scan is exhausted
+        let v = [Value::Integer(0)];
+        let deleted = diff(&[(1, &v), (2, &v), (3, &v)], &[(1, &v)]);
+        assert_eq!(deleted.changes.iter().map(|c| c.rowid()).collect::<Vec<_>>(), [2, 3]);
+        assert_eq!(deleted.deleted(), 2);
+
+        let inserted = diff(&[], &[(1, &v), (2, &v)]);
+        assert_eq!(inserted.inserted(), 2);
+
+        // A row with a column added by ALTER TABLE differs only in that column
+        let widened = diff(&[(1, &v)], &[(1, &[Value::Integer(0), text("new")])]);
+        let RowChange::Changed { columns, .. } = &widened.changes[0] else { panic!("{:?}", widened.changes) };
+        assert_eq!((columns[0].column, columns[0].old.clone()), (1, None));
+    }
+
+    #[test]
+    fn test_unreadable_rows_do_not_stop_the_merge() {
+        let v = [Value::Integer(0)];
+        let w = [Value::Integer(1)];
+        let old = rows(&[(1, &v), (2, &v), (4, &v)]);
+        // The new scan fails on one page partway through and carries on after it
+        let new = rows(&[(1, &v)])
+            .chain(std::iter::once(Err(SqliteVizError::BTreeError("page 3 is corrupt".to_string()))))
+            .chain(rows(&[(4, &w), (5, &v)]));
+
+        let diff = diff_table_rows("t", Some(2), Some(2), old, new);
+        assert_eq!(diff.errors, ["new: B-tree error: page 3 is corrupt"]);
+        let changes: Vec<_> = diff.changes.iter().map(|c| (c.rowid(), matches!(c, RowChange::Changed { .. }))).collect();
+        assert_eq!(changes, [(2, false), (4, true), (5, false)]);
+        assert_eq!((diff.deleted(), diff.changed(), diff.inserted()), (1, 1, 1));
+        assert!(!diff.is_unchanged());
+    }
+
+    fn leaf(cells: &[(i64, &str)]) -> Page {
+        let cells: Vec<_> = cells.iter().map(|&(rowid, value)| table_leaf_cell(rowid, &[text(value)])).collect();
+        parse_page(&btree_page(2, 0x0d, &cells, None), 2, PAGE_SIZE, PAGE_SIZE, TextEncoding::Utf8).unwrap()
+    }
+
+    /// Kind, rowid, old index and new index of a change
+    type Summary = (CellChangeKind, Option<i64>, Option<usize>, Option<usize>);
+
+    fn summary(changes: &[CellChange]) -> Vec<Summary> {
+        changes.iter().map(|c| (c.kind, c.rowid, c.old_index, c.new_index)).collect()
+    }
+
+    #[test]
+    fn test_identical_pages() {
+        let page = leaf(&[(1, "a"), (2, "b")]);
+        let (header_changed, changes) = diff_page_cells(&page, &page, true);
+        assert!(!header_changed);
+        assert!(changes.is_empty());
+    }
+
+    #[test]
+    fn test_added_and_moved_cells() {
+        // Inserting rowid 2 in the middle moves rowid 3 down the page
+        let old = leaf(&[(1, "a"), (3, "c")]);
+        let new = leaf(&[(1, "a"), (2, "b"), (3, "c")]);
+        let (header_changed, changes) = diff_page_cells(&old, &new, true);
+
+        assert!(header_changed);
+        assert_eq!(
+            summary(&changes),
+            [
+                (CellChangeKind::Added, Some(2), None, Some(1)),
+                (CellChangeKind::Moved, Some(3), Some(1), Some(2)),
+            ]
+        );
+        assert_ne!(changes[1].old_offset, changes[1].new_offset);
+    }
+
+    #[test]
+    fn test_rewritten_and_removed_cells() {
+        let old = leaf(&[(1, "a"), (2, "b"), (3, "c")]);
+        let new = leaf(&[(1, "a"), (2, "longer")]);
+
+        let (_, changes) = diff_page_cells(&old, &new, true);
+        assert_eq!(
+            summary(&changes),
+            [
+                (CellChangeKind::Rewritten, Some(2), Some(1), Some(1)),
+                (CellChangeKind::Removed, Some(3), Some(2), None),
+            ]
+        );
+
+        // Without rowid matching, the rewritten cell is a removal and an addition
+        let (_, changes) = diff_page_cells(&old, &new, false);
+        assert_eq!(
+            summary(&changes),
+            [
+                (CellChangeKind::Added, Some(2), None, Some(1)),
+                (CellChangeKind::Removed, Some(2), Some(1), None),
+                (CellChangeKind::Removed, Some(3), Some(2), None),
+            ]
+        );
+    }
+}
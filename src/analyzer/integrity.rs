@@ -0,0 +1,525 @@
+//! B-tree integrity checking, similar to `PRAGMA integrity_check`.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::analyzer::{btree_type, parse_schema_record};
+use crate::error::Result;
+use crate::model::{BTreeType, Cell, Page, Schema, SchemaEntry};
+
+/// A single integrity violation
+#[derive(Debug, Clone, Serialize)]
+pub struct IntegrityIssue {
+    /// Name of the B-tree the problem was found in
+    pub tree: String,
+    /// Page the problem was found on
+    pub page: u32,
+    /// Byte offset within the page, when the problem is at a specific location
+    pub offset: Option<usize>,
+    /// Description of the problem
+    pub message: String,
+}
+
+/// Result of checking every B-tree in the database
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct IntegrityReport {
+    /// Number of B-trees walked
+    pub trees_checked: usize,
+    /// Number of distinct pages visited (B-tree and overflow pages)
+    pub pages_checked: usize,
+    /// Violations found, in walk order
+    pub issues: Vec<IntegrityIssue>,
+}
+
+impl IntegrityReport {
+    /// True if no violations were found
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// A page waiting to be checked, with the constraints inherited from its parent
+struct PendingPage {
+    page_number: u32,
+    depth: usize,
+    /// Rowids in this subtree must be greater than this (table trees only)
+    lower_bound: Option<i64>,
+    /// Rowids in this subtree must be at most this (table trees only)
+    upper_bound: Option<i64>,
+}
+
+/// Walks B-trees and records violations
+struct IntegrityChecker<F, G> {
+    read_page: F,
+    read_page_raw: G,
+    page_count: u32,
+    usable_size: u32,
+    /// Which tree each visited page belongs to
+    owners: HashMap<u32, String>,
+    /// Entries read from the sqlite_schema leaves the walk reached
+    schema_entries: Vec<SchemaEntry>,
+    report: IntegrityReport,
+}
+
+impl<F, G> IntegrityChecker<F, G>
+where
+    F: FnMut(u32) -> Result<Page>,
+    G: FnMut(u32) -> Result<Vec<u8>>,
+{
+    fn issue(&mut self, tree: &str, page: u32, offset: Option<usize>, message: String) {
+        self.report.issues.push(IntegrityIssue {
+            tree: tree.to_string(),
+            page,
+            offset,
+            message,
+        });
+    }
+
+    /// Check that a page reference is in range and not already used.
+    /// Returns false if the page must not be followed.
+    fn claim_page(&mut self, tree: &str, page_number: u32, from_page: u32, offset: Option<usize>) -> bool {
+        if page_number < 1 || page_number > self.page_count {
+            self.issue(
+                tree,
+                from_page,
+                offset,
+                format!("page number {} out of range 1..={}", page_number, self.page_count),
+            );
+            return false;
+        }
+        if let Some(owner) = self.owners.get(&page_number).cloned() {
+            self.issue(
+                tree,
+                from_page,
+                offset,
+                format!("page {} is referenced twice (already used by {})", page_number, owner),
+            );
+            return false;
+        }
+        self.owners.insert(page_number, tree.to_string());
+        true
+    }
+
+    fn check_tree(&mut self, name: &str, root_page: u32, tree_type: BTreeType) {
+        self.report.trees_checked += 1;
+        if !self.claim_page(name, root_page, root_page, None) {
+            return;
+        }
+
+        let mut leaf_depth = None;
+        let mut stack = vec![PendingPage {
+            page_number: root_page,
+            depth: 0,
+            lower_bound: None,
+            upper_bound: None,
+        }];
+
+        while let Some(pending) = stack.pop() {
+            let page_number = pending.page_number;
+            let page = match (self.read_page)(page_number) {
+                Ok(page) => page,
+                Err(e) => {
+                    self.issue(name, page_number, None, format!("could not parse page: {}", e));
+                    continue;
+                }
+            };
+
+            let type_matches = match tree_type {
+                BTreeType::Table => page.page_type.is_table(),
+                BTreeType::Index => page.page_type.is_index(),
+            };
+            if !type_matches {
+                self.issue(
+                    name,
+                    page_number,
+                    None,
+                    format!("{:?} page found in a {:?} B-tree", page.page_type, tree_type),
+                );
+                continue;
+            }
+
+            self.check_cell_layout(name, &page);
+
+            if page.page_type.is_leaf() {
+                match leaf_depth {
+                    None => leaf_depth = Some(pending.depth),
+                    Some(depth) if depth != pending.depth => self.issue(
+                        name,
+                        page_number,
+                        None,
+                        format!("leaf at depth {}, but other leaves are at depth {}", pending.depth, depth),
+                    ),
+                    Some(_) => {}
+                }
+            }
+
+            if tree_type == BTreeType::Table {
+                self.check_rowid_order(name, &page, pending.lower_bound, pending.upper_bound);
+            }
+
+            if root_page == 1 && page.page_type.is_leaf() {
+                self.schema_entries.extend(page.cells.iter().filter_map(|cell| match cell {
+                    Cell::TableLeaf(leaf) => leaf.payload.as_ref().and_then(parse_schema_record),
+                    _ => None,
+                }));
+            }
+
+            self.check_overflow_chains(name, &page);
+
+            // Queue children, each bounded by the keys on either side of its pointer
+            let mut lower_bound = pending.lower_bound;
+            let mut children = Vec::new();
+            for cell in &page.cells {
+                if let Some(child) = cell.left_child() {
+                    let upper_bound = cell.rowid().or(pending.upper_bound);
+                    children.push((child, Some(cell.cell_offset() as usize), lower_bound, upper_bound));
+                    lower_bound = cell.rowid().or(lower_bound);
+                }
+            }
+            if let Some(right_ptr) = page.header.as_ref().and_then(|h| h.right_most_pointer) {
+                let header_offset = if page_number == 1 { 100 } else { 0 };
+                children.push((right_ptr, Some(header_offset + 8), lower_bound, pending.upper_bound));
+            }
+
+            // Push in reverse so children are checked left to right
+            for (child, offset, lower_bound, upper_bound) in children.into_iter().rev() {
+                if self.claim_page(name, child, page_number, offset) {
+                    stack.push(PendingPage {
+                        page_number: child,
+                        depth: pending.depth + 1,
+                        lower_bound,
+                        upper_bound,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Check cell pointers land in the cell content area, cells do not overlap and free space adds up
+    fn check_cell_layout(&mut self, name: &str, page: &Page) {
+        let Some(header) = &page.header else { return };
+        let header_offset = if page.page_number == 1 { 100 } else { 0 };
+        let pointer_array_end = header_offset + page.page_type.header_size() + page.cell_pointers.len() * 2;
+        let usable_size = self.usable_size as usize;
+        let content_start = match header.cell_content_start {
+            0 => 65536,
+            start => start as usize,
+        };
+
+        if content_start < pointer_array_end || content_start > usable_size {
+            self.issue(
+                name,
+                page.page_number,
+                Some(header_offset + 5),
+                format!("cell content area starts at {}, outside {}..={}", content_start, pointer_array_end, usable_size),
+            );
+        }
+
+        if let Some(free) = &page.free_space_breakdown {
+            if let Some(error) = &free.freeblock_error {
+                self.issue(name, page.page_number, Some(header_offset + 1), error.clone());
+            } else if free.unaccounted != 0 {
+                self.issue(
+                    name,
+                    page.page_number,
+                    None,
+                    format!(
+                        "free space does not add up: cells and free space differ from the cell content area by {} bytes",
+                        free.unaccounted
+                    ),
+                );
+            }
+        }
+
+        for (i, &pointer) in page.cell_pointers.iter().enumerate() {
+            let pointer = pointer as usize;
+            let pointer_offset = Some(header_offset + page.page_type.header_size() + i * 2);
+            if pointer < pointer_array_end {
+                self.issue(
+                    name,
+                    page.page_number,
+                    pointer_offset,
+                    format!("cell pointer {} ({}) points into the page header or cell pointer array", i, pointer),
+                );
+            } else if pointer >= usable_size {
+                self.issue(
+                    name,
+                    page.page_number,
+                    pointer_offset,
+                    format!("cell pointer {} ({}) points past the usable page size {}", i, pointer, usable_size),
+                );
+            } else if !page.cells.iter().any(|c| c.cell_offset() as usize == pointer) {
+                self.issue(name, page.page_number, Some(pointer), format!("cell {} could not be parsed", i));
+            }
+        }
+
+        let mut extents: Vec<(usize, usize)> = page
+            .cells
+            .iter()
+            .map(|c| (c.cell_offset() as usize, c.cell_offset() as usize + c.cell_size()))
+            .collect();
+        extents.sort();
+
+        for &(start, end) in &extents {
+            if start >= pointer_array_end && end > usable_size {
+                self.issue(
+                    name,
+                    page.page_number,
+                    Some(start),
+                    format!("cell at {}..{} extends past the usable page size {}", start, end, usable_size),
+                );
+            }
+        }
+        for pair in extents.windows(2) {
+            let ((start_a, end_a), (start_b, end_b)) = (pair[0], pair[1]);
+            if start_b < end_a {
+                self.issue(
+                    name,
+                    page.page_number,
+                    Some(start_b),
+                    format!("cell at {}..{} overlaps cell at {}..{}", start_b, end_b, start_a, end_a),
+                );
+            }
+        }
+    }
+
+    /// Check rowids on a table page are ascending and within the bounds set by the parent
+    fn check_rowid_order(&mut self, name: &str, page: &Page, lower_bound: Option<i64>, upper_bound: Option<i64>) {
+        let mut previous: Option<i64> = None;
+        for cell in &page.cells {
+            let Some(rowid) = cell.rowid() else { continue };
+            let offset = Some(cell.cell_offset() as usize);
+
+            if let Some(prev) = previous {
+                // Leaf rowids are unique; interior keys may repeat after deletes
+                let out_of_order = if page.page_type.is_leaf() { rowid <= prev } else { rowid < prev };
+                if out_of_order {
+                    self.issue(name, page.page_number, offset, format!("rowid {} follows rowid {}", rowid, prev));
+                }
+            }
+            if lower_bound.is_some_and(|lower| rowid <= lower) {
+                self.issue(
+                    name,
+                    page.page_number,
+                    offset,
+                    format!("rowid {} is not greater than parent key {}", rowid, lower_bound.unwrap()),
+                );
+            }
+            if upper_bound.is_some_and(|upper| rowid > upper) {
+                self.issue(
+                    name,
+                    page.page_number,
+                    offset,
+                    format!("rowid {} is greater than parent key {}", rowid, upper_bound.unwrap()),
+                );
+            }
+            previous = Some(rowid);
+        }
+    }
+
+    /// Check every overflow chain on a page has the expected length and valid pages
+    fn check_overflow_chains(&mut self, name: &str, page: &Page) {
+        let content_per_page = self.usable_size as usize - 4;
+        for cell in &page.cells {
+            let (Some(first), Some(payload_size), Some(local_size)) =
+                (cell.overflow_page(), cell.payload_size(), cell.local_payload_size())
+            else {
+                continue;
+            };
+
+            let overflow_bytes = (payload_size as usize).saturating_sub(local_size);
+            let expected_pages = overflow_bytes.div_ceil(content_per_page);
+            let offset = Some(cell.cell_offset() as usize);
+
+            let mut current = first;
+            let mut from_page = page.page_number;
+            let mut pages = 0;
+            loop {
+                if !self.claim_page(name, current, from_page, offset) {
+                    break;
+                }
+                pages += 1;
+                let next = match (self.read_page_raw)(current) {
+                    Ok(data) if data.len() >= 4 => u32::from_be_bytes([data[0], data[1], data[2], data[3]]),
+                    _ => {
+                        self.issue(name, current, None, "could not read overflow page".to_string());
+                        break;
+                    }
+                };
+                if next == 0 || pages >= expected_pages {
+                    if next != 0 || pages != expected_pages {
+                        self.issue(
+                            name,
+                            page.page_number,
+                            offset,
+                            format!(
+                                "overflow chain from page {} has {} pages, payload needs {}",
+                                first,
+                                if next != 0 { format!("more than {}", pages) } else { pages.to_string() },
+                                expected_pages
+                            ),
+                        );
+                    }
+                    break;
+                }
+                from_page = current;
+                current = next;
+            }
+        }
+    }
+}
+
+/// Check the schema B-tree and every table and index B-tree listed in it.
+/// The schema is read from the sqlite_schema pages the walk can reach, so damage to it
+/// is reported as issues on sqlite_schema and the roots that could be read are still checked.
+pub fn check_integrity<F, G>(
+    page_count: u32,
+    usable_size: u32,
+    read_page: F,
+    read_page_raw: G,
+) -> IntegrityReport
+where
+    F: FnMut(u32) -> Result<Page>,
+    G: FnMut(u32) -> Result<Vec<u8>>,
+{
+    let mut checker = IntegrityChecker {
+        read_page,
+        read_page_raw,
+        page_count,
+        usable_size,
+        owners: HashMap::new(),
+        schema_entries: Vec::new(),
+        report: IntegrityReport::default(),
+    };
+
+    checker.check_tree("sqlite_schema", 1, BTreeType::Table);
+    let schema = Schema { entries: std::mem::take(&mut checker.schema_entries) };
+    for entry in schema.tables() {
+        if entry.root_page != 0 {
+            checker.check_tree(&entry.name, entry.root_page, btree_type(entry));
+        }
+    }
+    for entry in schema.indexes() {
+        if entry.root_page != 0 {
+            checker.check_tree(&entry.name, entry.root_page, BTreeType::Index);
+        }
+    }
+
+    checker.report.pages_checked = checker.owners.len();
+    checker.report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{btree_page, schema_cell, table_interior_cell, table_leaf_cell, Pages, PAGE_SIZE};
+    use crate::model::Value;
+
+    /// Page 1 listing table `t` rooted at page 2, followed by `pages`
+    fn database(pages: Vec<Vec<u8>>) -> Pages {
+        let schema = btree_page(1, 0x0d, &[schema_cell(1, "table", "t", "t", 2, "CREATE TABLE t(x)")], None);
+        Pages([vec![schema], pages].concat())
+    }
+
+    fn leaf(page_number: u32, rowids: &[i64]) -> Vec<u8> {
+        let cells: Vec<_> = rowids.iter().map(|&r| table_leaf_cell(r, &[Value::Integer(r)])).collect();
+        btree_page(page_number, 0x0d, &cells, None)
+    }
+
+    fn check(pages: &Pages) -> IntegrityReport {
+        check_integrity(pages.count(), PAGE_SIZE, |n| pages.page(n), |n| pages.raw(n))
+    }
+
+    fn assert_issue(report: &IntegrityReport, page: u32, message: &str) {
+        assert!(
+            report.issues.iter().any(|i| i.tree == "t" && i.page == page && i.message.contains(message)),
+            "no {:?} issue on page {} in {:?}",
+            message,
+            page,
+            report.issues
+        );
+    }
+
+    #[test]
+    fn test_valid_tree() {
+        let pages = database(vec![
+            btree_page(2, 0x05, &[table_interior_cell(3, 2)], Some(4)),
+            leaf(3, &[1, 2]),
+            leaf(4, &[3, 4]),
+        ]);
+        let report = check(&pages);
+        assert!(report.is_ok(), "{:?}", report.issues);
+        assert_eq!(report.trees_checked, 2);
+        assert_eq!(report.pages_checked, 4);
+    }
+
+    #[test]
+    fn test_child_out_of_range() {
+        let pages = database(vec![btree_page(2, 0x05, &[table_interior_cell(3, 1)], Some(9)), leaf(3, &[1])]);
+        assert_issue(&check(&pages), 2, "page number 9 out of range");
+    }
+
+    #[test]
+    fn test_page_reached_twice() {
+        let pages = database(vec![btree_page(2, 0x05, &[table_interior_cell(3, 1)], Some(3)), leaf(3, &[1])]);
+        assert_issue(&check(&pages), 2, "page 3 is referenced twice");
+    }
+
+    #[test]
+    fn test_uneven_leaf_depth() {
+        // Page 3 is a leaf directly under the root, page 5 is one level further down
+        let pages = database(vec![
+            btree_page(2, 0x05, &[table_interior_cell(3, 1)], Some(4)),
+            leaf(3, &[1]),
+            btree_page(4, 0x05, &[], Some(5)),
+            leaf(5, &[2]),
+        ]);
+        assert_issue(&check(&pages), 5, "leaf at depth 2, but other leaves are at depth 1");
+    }
+
+    #[test]
+    fn test_rowid_outside_parent_keys() {
+        // The left child may only hold rowids up to 10, the right child only above it
+        let pages = database(vec![
+            btree_page(2, 0x05, &[table_interior_cell(3, 10)], Some(4)),
+            leaf(3, &[5, 20]),
+            leaf(4, &[10, 11]),
+        ]);
+        let report = check(&pages);
+        assert_issue(&report, 3, "rowid 20 is greater than parent key 10");
+        assert_issue(&report, 4, "rowid 10 is not greater than parent key 10");
+    }
+
+    #[test]
+    fn test_cell_pointer_into_header() {
+        let mut page = leaf(2, &[1, 2]);
+        page[10..12].copy_from_slice(&4u16.to_be_bytes());
+        let pages = database(vec![page]);
+        assert_issue(&check(&pages), 2, "points into the page header");
+    }
+
+    #[test]
+    fn test_overlapping_cells() {
+        // Both cell pointers lead to the same cell
+        let mut page = leaf(2, &[1, 2]);
+        page.copy_within(8..10, 10);
+        let pages = database(vec![page]);
+        assert_issue(&check(&pages), 2, "overlaps cell");
+    }
+
+    #[test]
+    fn test_damaged_schema_still_checks_readable_roots() {
+        // sqlite_schema spans pages 2 and 3; page 3 is not a B-tree page
+        let pages = Pages(vec![
+            btree_page(1, 0x05, &[table_interior_cell(2, 1)], Some(3)),
+            btree_page(2, 0x0d, &[schema_cell(1, "table", "t", "t", 4, "CREATE TABLE t(x)")], None),
+            vec![0xff; PAGE_SIZE as usize],
+            leaf(4, &[1]),
+        ]);
+        let report = check(&pages);
+        assert!(report.issues.iter().any(|i| i.tree == "sqlite_schema" && i.page == 3));
+        assert_eq!(report.trees_checked, 2);
+        assert_eq!(report.issues.len(), 1, "{:?}", report.issues);
+    }
+}
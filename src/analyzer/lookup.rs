@@ -0,0 +1,387 @@
+//! Tracing the search path of a key lookup from the root of a B-tree to a leaf.
+
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use crate::analyzer::RecordComparator;
+use crate::error::{Result, SqliteVizError};
+use crate::model::{Page, PageType, Record, RowLayout, RowValue, Value};
+
+/// One page visited on the way down a B-tree
+#[derive(Debug, Clone, Serialize)]
+pub struct LookupStep {
+    pub page_number: u32,
+    pub page_type: PageType,
+    pub cell_count: usize,
+    /// Cell whose key bounds the search key; None if the right-most pointer was followed,
+    /// or on a leaf that does not hold the key
+    pub cell_index: Option<usize>,
+    /// Page descended into (None on the leaf)
+    pub child_page: Option<u32>,
+}
+
+/// How a lookup ended on the leaf page
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "result")]
+pub enum LookupResult {
+    Found {
+        cell_index: usize,
+        /// Offset of the cell within the leaf page
+        offset: u16,
+        record: Record,
+        /// The record's values as a row, with the rowid alias and defaults filled in
+        row: Vec<RowValue>,
+    },
+    NotFound {
+        /// Position on the leaf where the key would be inserted
+        insert_index: usize,
+    },
+}
+
+/// The path a rowid lookup takes through a table B-tree
+#[derive(Debug, Clone, Serialize)]
+pub struct RowidLookup {
+    pub tree: String,
+    pub rowid: i64,
+    /// Pages visited, root first and leaf last
+    pub path: Vec<LookupStep>,
+    pub result: LookupResult,
+}
+
+impl RowidLookup {
+    /// Page the lookup ended on
+    pub fn leaf_page(&self) -> Option<u32> {
+        self.path.last().map(|step| step.page_number)
+    }
+}
+
+/// Descend a table B-tree looking for `rowid`, as SQLite does: on interior pages, follow
+/// the left child of the first cell whose rowid is at least the search key, or the
+/// right-most pointer if there is none. `layout` turns the record found into a row.
+pub fn lookup_rowid<F>(tree: &str, root_page: u32, rowid: i64, layout: &RowLayout, mut read_page: F) -> Result<RowidLookup>
+where
+    F: FnMut(u32) -> Result<Page>,
+{
+    let mut path = Vec::new();
+    let mut visited = HashSet::new();
+    let mut page_number = root_page;
+
+    loop {
+        if !visited.insert(page_number) {
+            return Err(SqliteVizError::BTreeError(format!("page {} is reached more than once", page_number)));
+        }
+        let page = read_page(page_number)?;
+        let cell_count = page.cells.len();
+
+        match page.page_type {
+            PageType::InteriorTable => {
+                let cell_index = page.cells.iter().position(|c| c.rowid().is_some_and(|key| rowid <= key));
+                let child = match cell_index {
+                    Some(i) => page.cells[i].left_child(),
+                    None => page.header.as_ref().and_then(|h| h.right_most_pointer),
+                }
+                .ok_or_else(|| {
+                    SqliteVizError::BTreeError(format!("interior page {} has no child to descend into", page_number))
+                })?;
+                path.push(LookupStep {
+                    page_number,
+                    page_type: page.page_type,
+                    cell_count,
+                    cell_index,
+                    child_page: Some(child),
+                });
+                page_number = child;
+            }
+            PageType::LeafTable => {
+                let position = page.cells.iter().position(|c| c.rowid().is_some_and(|key| key >= rowid));
+                let result = match position {
+                    Some(i) if page.cells[i].rowid() == Some(rowid) => {
+                        let record = page.cells[i].payload().cloned().ok_or_else(|| {
+                            SqliteVizError::BTreeError(format!(
+                                "record for rowid {} on page {} could not be decoded",
+                                rowid, page_number
+                            ))
+                        })?;
+                        LookupResult::Found {
+                            cell_index: i,
+                            offset: page.cells[i].cell_offset(),
+                            row: layout.materialize(Some(rowid), &record.values),
+                            record,
+                        }
+                    }
+                    _ => LookupResult::NotFound { insert_index: position.unwrap_or(cell_count) },
+                };
+                path.push(LookupStep {
+                    page_number,
+                    page_type: page.page_type,
+                    cell_count,
+                    cell_index: match result {
+                        LookupResult::Found { cell_index, .. } => Some(cell_index),
+                        LookupResult::NotFound { .. } => None,
+                    },
+                    child_page: None,
+                });
+                return Ok(RowidLookup { tree: tree.to_string(), rowid, path, result });
+            }
+            other => {
+                return Err(SqliteVizError::BTreeError(format!(
+                    "page {} is a {:?} page, not a table B-tree page",
+                    page_number, other
+                )));
+            }
+        }
+    }
+}
+
+/// An index entry whose leading columns equal the search key
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexMatch {
+    pub page_number: u32,
+    pub cell_index: usize,
+    pub record: Record,
+}
+
+/// The path a key search takes through an index B-tree, and the entries it matches
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexLookup {
+    pub tree: String,
+    /// Search key: values for the leading columns of the index
+    pub key: Vec<Value>,
+    /// Pages visited looking for the first match, root first and leaf last
+    pub path: Vec<LookupStep>,
+    /// Matching entries in index order; interior cells are entries too
+    pub matches: Vec<IndexMatch>,
+}
+
+impl IndexLookup {
+    /// Page the search ended on
+    pub fn leaf_page(&self) -> Option<u32> {
+        self.path.last().map(|step| step.page_number)
+    }
+}
+
+/// Search an index B-tree for the entries that start with `key`. The path is the descent
+/// to the first entry not less than the key: on interior pages, the left child of the
+/// first cell not less than the key, or the right-most pointer if there is none.
+pub fn lookup_index_key<F>(
+    tree: &str,
+    root_page: u32,
+    key: &[Value],
+    comparator: &RecordComparator,
+    mut read_page: F,
+) -> Result<IndexLookup>
+where
+    F: FnMut(u32) -> Result<Page>,
+{
+    let mut path = Vec::new();
+    let mut visited = HashSet::new();
+    let mut page_number = root_page;
+    loop {
+        if !visited.insert(page_number) {
+            return Err(SqliteVizError::BTreeError(format!("page {} is reached more than once", page_number)));
+        }
+        let page = read_page(page_number)?;
+        if !page.page_type.is_index() {
+            return Err(SqliteVizError::BTreeError(format!(
+                "page {} is a {:?} page, not an index B-tree page",
+                page_number, page.page_type
+            )));
+        }
+        let mut first = None;
+        for i in 0..page.cells.len() {
+            let ordering = comparator.compare_prefix(&cell_record(&page, i)?.values, key);
+            if ordering != Ordering::Less {
+                first = Some((i, ordering));
+                break;
+            }
+        }
+
+        let child = if page.page_type.is_interior() {
+            let child = match first {
+                Some((i, _)) => page.cells[i].left_child(),
+                None => page.header.as_ref().and_then(|h| h.right_most_pointer),
+            }
+            .ok_or_else(|| {
+                SqliteVizError::BTreeError(format!("interior page {} has no child to descend into", page_number))
+            })?;
+            Some(child)
+        } else {
+            None
+        };
+        path.push(LookupStep {
+            page_number,
+            page_type: page.page_type,
+            cell_count: page.cells.len(),
+            cell_index: match (child, first) {
+                (Some(_), Some((i, _))) => Some(i),
+                (None, Some((i, Ordering::Equal))) => Some(i),
+                _ => None,
+            },
+            child_page: child,
+        });
+        match child {
+            Some(child) => page_number = child,
+            None => break,
+        }
+    }
+
+    let mut matches = Vec::new();
+    collect_matches(root_page, key, comparator, &mut read_page, &mut HashSet::new(), &mut matches)?;
+    Ok(IndexLookup { tree: tree.to_string(), key: key.to_vec(), path, matches })
+}
+
+/// Walk the subtree at `page_number` in key order, adding the entries that match and
+/// skipping subtrees that cannot hold any. Returns true once past the last match.
+fn collect_matches<F>(
+    page_number: u32,
+    key: &[Value],
+    comparator: &RecordComparator,
+    read_page: &mut F,
+    visited: &mut HashSet<u32>,
+    matches: &mut Vec<IndexMatch>,
+) -> Result<bool>
+where
+    F: FnMut(u32) -> Result<Page>,
+{
+    if !visited.insert(page_number) {
+        return Err(SqliteVizError::BTreeError(format!("page {} is reached more than once", page_number)));
+    }
+    let page = read_page(page_number)?;
+    for (i, cell) in page.cells.iter().enumerate() {
+        let record = cell_record(&page, i)?;
+        let ordering = comparator.compare_prefix(&record.values, key);
+        // Everything left of an entry sorts before it
+        if ordering != Ordering::Less {
+            if let Some(child) = cell.left_child() {
+                if collect_matches(child, key, comparator, read_page, visited, matches)? {
+                    return Ok(true);
+                }
+            }
+        }
+        match ordering {
+            Ordering::Less => {}
+            Ordering::Equal => matches.push(IndexMatch {
+                page_number,
+                cell_index: i,
+                record: record.clone(),
+            }),
+            Ordering::Greater => return Ok(true),
+        }
+    }
+    match page.header.as_ref().and_then(|h| h.right_most_pointer) {
+        Some(child) if page.page_type.is_interior() => collect_matches(child, key, comparator, read_page, visited, matches),
+        _ => Ok(false),
+    }
+}
+
+fn cell_record(page: &Page, index: usize) -> Result<&Record> {
+    page.cells[index].payload().ok_or_else(|| {
+        SqliteVizError::BTreeError(format!("cell {} on page {} could not be decoded", index, page.page_number))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::{Collation, KeyColumn};
+    use crate::fixtures::{btree_page, index_interior_cell, index_leaf_cell, table_interior_cell, table_leaf_cell, Pages};
+    use crate::model::TextEncoding;
+
+    /// Root page 1 over leaves 2 (rowids 5, 10), 3 (15, 20) and 4 (25, 30)
+    fn table_tree() -> Pages {
+        let leaf = |page, rowids: [i64; 2]| {
+            let cells: Vec<_> = rowids.iter().map(|&rowid| table_leaf_cell(rowid, &[Value::Integer(rowid * 100)])).collect();
+            btree_page(page, 0x0d, &cells, None)
+        };
+        Pages(vec![
+            btree_page(1, 0x05, &[table_interior_cell(2, 10), table_interior_cell(3, 20)], Some(4)),
+            leaf(2, [5, 10]),
+            leaf(3, [15, 20]),
+            leaf(4, [25, 30]),
+        ])
+    }
+
+    fn lookup(pages: &Pages, rowid: i64) -> Result<RowidLookup> {
+        lookup_rowid("t", 1, rowid, &RowLayout::default(), |n| pages.page(n))
+    }
+
+    /// Page, cell and child of each step
+    fn steps(path: &[LookupStep]) -> Vec<(u32, Option<usize>, Option<u32>)> {
+        path.iter().map(|step| (step.page_number, step.cell_index, step.child_page)).collect()
+    }
+
+    #[test]
+    fn test_key_equal_to_separator_goes_left() {
+        let pages = table_tree();
+        let found = lookup(&pages, 10).unwrap();
+        assert_eq!(steps(&found.path), [(1, Some(0), Some(2)), (2, Some(1), None)]);
+        let LookupResult::Found { cell_index: 1, record, .. } = &found.result else { panic!("{:?}", found.result) };
+        assert_eq!(record.values, [Value::Integer(1000)]);
+
+        let found = lookup(&pages, 20).unwrap();
+        assert_eq!(steps(&found.path), [(1, Some(1), Some(3)), (3, Some(1), None)]);
+    }
+
+    #[test]
+    fn test_key_above_separators_follows_right_most_pointer() {
+        let found = lookup(&table_tree(), 30).unwrap();
+        assert_eq!(steps(&found.path), [(1, None, Some(4)), (4, Some(1), None)]);
+        assert_eq!(found.leaf_page(), Some(4));
+    }
+
+    #[test]
+    fn test_missing_key_reports_insert_position() {
+        let pages = table_tree();
+        let insert_index = |rowid| match lookup(&pages, rowid).unwrap().result {
+            LookupResult::NotFound { insert_index } => insert_index,
+            found => panic!("{:?}", found),
+        };
+        assert_eq!(insert_index(12), 0);
+        assert_eq!(insert_index(17), 1);
+        assert_eq!(insert_index(99), 2);
+
+        let missing = lookup(&pages, 17).unwrap();
+        assert_eq!(steps(&missing.path), [(1, Some(1), Some(3)), (3, None, None)]);
+    }
+
+    #[test]
+    fn test_revisited_page_is_rejected() {
+        // The first child pointer of the root leads back to the root
+        let pages = Pages(vec![
+            btree_page(1, 0x05, &[table_interior_cell(1, 10)], Some(2)),
+            btree_page(2, 0x0d, &[table_leaf_cell(20, &[Value::Null])], None),
+        ]);
+        assert!(matches!(lookup(&pages, 5), Err(SqliteVizError::BTreeError(_))));
+        assert!(lookup(&pages, 20).is_ok());
+    }
+
+    #[test]
+    fn test_index_matches_span_interior_entry() {
+        // Index on (a DESC), entries (a, rowid): 9 5 | 5 | 5 3 | 2 | 1
+        let entry = |a: i64, rowid: i64| [Value::Integer(a), Value::Integer(rowid)];
+        let pages = Pages(vec![
+            btree_page(1, 0x02, &[index_interior_cell(2, &entry(5, 3)), index_interior_cell(3, &entry(2, 7))], Some(4)),
+            btree_page(2, 0x0a, &[index_leaf_cell(&entry(9, 1)), index_leaf_cell(&entry(5, 2))], None),
+            btree_page(3, 0x0a, &[index_leaf_cell(&entry(5, 4)), index_leaf_cell(&entry(3, 5))], None),
+            btree_page(4, 0x0a, &[index_leaf_cell(&entry(1, 8))], None),
+        ]);
+        let comparator =
+            RecordComparator::new(vec![KeyColumn { collation: Collation::Binary, descending: true }], TextEncoding::Utf8);
+
+        let lookup = lookup_index_key("i", 1, &[Value::Integer(5)], &comparator, |n| pages.page(n)).unwrap();
+        // The first match is in the left child of the interior entry that also matches
+        assert_eq!(steps(&lookup.path), [(1, Some(0), Some(2)), (2, Some(1), None)]);
+        let matches: Vec<_> = lookup.matches.iter().map(|m| (m.page_number, m.cell_index, m.record.values[1].clone())).collect();
+        assert_eq!(
+            matches,
+            [(2, 1, Value::Integer(2)), (1, 0, Value::Integer(3)), (3, 0, Value::Integer(4))]
+        );
+
+        // 4 sorts between 5 and 3 in descending order, on page 3, which has no match
+        let lookup = lookup_index_key("i", 1, &[Value::Integer(4)], &comparator, |n| pages.page(n)).unwrap();
+        assert_eq!(steps(&lookup.path), [(1, Some(1), Some(3)), (3, None, None)]);
+        assert!(lookup.matches.is_empty());
+    }
+}
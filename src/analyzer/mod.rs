@@ -1,5 +1,23 @@
 pub mod schema;
 pub mod btree;
+pub mod wal;
+pub mod integrity;
+pub mod pages;
+pub mod carve;
+pub mod rows;
+pub mod diff;
+pub mod lookup;
+pub mod compare;
+pub mod stats;
 
 pub use schema::*;
 pub use btree::*;
+pub use wal::*;
+pub use integrity::*;
+pub use pages::*;
+pub use carve::*;
+pub use rows::*;
+pub use diff::*;
+pub use lookup::*;
+pub use compare::*;
+pub use stats::*;
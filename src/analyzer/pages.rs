@@ -0,0 +1,162 @@
+//! Page accounting: which structure owns each page of the file.
+
+use std::collections::HashSet;
+
+use crate::error::Result;
+use crate::model::{Page, PageMap, PageOwner};
+
+/// Claim every page of a B-tree, and the overflow chains of its cells, in `map`.
+/// References outside the file are skipped, and a referenced page that cannot be parsed
+/// as a B-tree page is claimed as unreadable and not descended into. A page reached twice is claimed twice but not walked again,
+/// so corrupt trees terminate.
+pub fn claim_btree_pages<F, G>(
+    map: &mut PageMap,
+    name: &str,
+    root_page: u32,
+    usable_size: u32,
+    mut read_page: F,
+    mut read_page_raw: G,
+) where
+    F: FnMut(u32) -> Result<Page>,
+    G: FnMut(u32) -> Result<Vec<u8>>,
+{
+    let page_count = map.page_count;
+    let in_range = |page: u32| page >= 1 && page <= page_count;
+    let content_per_page = usable_size as usize - 4;
+
+    let mut visited = HashSet::new();
+    let mut stack = vec![root_page];
+
+    while let Some(page_num) = stack.pop() {
+        if !in_range(page_num) {
+            continue;
+        }
+        // Pointer-map, freelist and lock-byte pages parse too, but not as B-tree pages
+        let page = match read_page(page_num) {
+            Ok(page) if page.page_type.is_btree() => page,
+            _ => {
+                map.claim(page_num, PageOwner::Unreadable { tree: name.to_string() });
+                continue;
+            }
+        };
+        map.claim(page_num, PageOwner::BTree { tree: name.to_string(), page_type: page.page_type });
+        if !visited.insert(page_num) {
+            continue;
+        }
+
+        for (cell_index, cell) in page.cells.iter().enumerate() {
+            if let Some(child) = cell.left_child() {
+                stack.push(child);
+            }
+
+            let (Some(first), Some(payload_size), Some(local_size)) =
+                (cell.overflow_page(), cell.payload_size(), cell.local_payload_size())
+            else {
+                continue;
+            };
+
+            // Stop after the pages the payload needs, even if the chain goes on
+            let expected_pages = (payload_size as usize).saturating_sub(local_size).div_ceil(content_per_page);
+            let mut current = first;
+            for _ in 0..expected_pages {
+                if !in_range(current) {
+                    break;
+                }
+                map.claim(current, PageOwner::Overflow { tree: name.to_string(), page: page_num, cell_index });
+                if !visited.insert(current) {
+                    break;
+                }
+                match read_page_raw(current) {
+                    Ok(data) if data.len() >= 4 => {
+                        current = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+                    }
+                    _ => break,
+                }
+            }
+        }
+
+        if let Some(right_ptr) = page.header.as_ref().and_then(|h| h.right_most_pointer) {
+            stack.push(right_ptr);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{btree_page, table_interior_cell, table_leaf_cell, Pages, PAGE_SIZE};
+    use crate::model::{PageType, Value};
+    use crate::parser::parse_raw_page;
+
+    fn claim(map: &mut PageMap, pages: &Pages, root_page: u32) {
+        claim_btree_pages(map, "t", root_page, PAGE_SIZE, |n| pages.page(n), |n| pages.raw(n));
+    }
+
+    #[test]
+    fn test_claims_tree_and_leaves_orphans() {
+        // Page 4 is not referenced by the tree
+        let pages = Pages(vec![
+            btree_page(1, 0x05, &[table_interior_cell(2, 1)], Some(3)),
+            btree_page(2, 0x0d, &[table_leaf_cell(1, &[Value::Integer(1)])], None),
+            btree_page(3, 0x0d, &[table_leaf_cell(2, &[Value::Integer(2)])], None),
+            vec![0; PAGE_SIZE as usize],
+        ]);
+        let mut map = PageMap::new(pages.count());
+        claim(&mut map, &pages, 1);
+
+        assert_eq!(map.owner(1), Some(&PageOwner::BTree { tree: "t".to_string(), page_type: PageType::InteriorTable }));
+        assert_eq!(map.owner(3), Some(&PageOwner::BTree { tree: "t".to_string(), page_type: PageType::LeafTable }));
+        assert_eq!(map.duplicates().count(), 0);
+        assert_eq!(map.orphans(), [4]);
+    }
+
+    #[test]
+    fn test_page_claimed_twice() {
+        // Both child pointers lead to page 2, which is claimed twice but walked once
+        let pages = Pages(vec![
+            btree_page(1, 0x05, &[table_interior_cell(2, 1)], Some(2)),
+            btree_page(2, 0x0d, &[table_leaf_cell(1, &[Value::Integer(1)])], None),
+        ]);
+        let mut map = PageMap::new(pages.count());
+        claim(&mut map, &pages, 1);
+
+        let duplicates: Vec<_> = map.duplicates().collect();
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].0, 2);
+        assert_eq!(duplicates[0].1.len(), 2);
+    }
+
+    #[test]
+    fn test_unparsable_page_is_not_orphaned() {
+        let pages = Pages(vec![
+            btree_page(1, 0x05, &[table_interior_cell(2, 1)], Some(3)),
+            btree_page(2, 0x0d, &[table_leaf_cell(1, &[Value::Integer(1)])], None),
+            vec![0xff; PAGE_SIZE as usize],
+        ]);
+        let mut map = PageMap::new(pages.count());
+        claim(&mut map, &pages, 1);
+
+        assert_eq!(map.owner(3), Some(&PageOwner::Unreadable { tree: "t".to_string() }));
+        assert!(map.orphans().is_empty());
+    }
+
+    #[test]
+    fn test_child_pointer_to_non_btree_page_is_unreadable() {
+        // Page 3 reads as a pointer-map page, as Database::parse_page returns it
+        let pages = Pages(vec![
+            btree_page(1, 0x05, &[table_interior_cell(2, 1)], Some(3)),
+            btree_page(2, 0x0d, &[table_leaf_cell(1, &[Value::Integer(1)])], None),
+            btree_page(3, 0x05, &[table_interior_cell(2, 1)], Some(2)),
+        ]);
+        let mut map = PageMap::new(pages.count());
+        let read_page = |n| match n {
+            3 => Ok(parse_raw_page(&pages.raw(3)?, 3, PageType::PointerMap, PAGE_SIZE)),
+            _ => pages.page(n),
+        };
+        claim_btree_pages(&mut map, "t", 1, PAGE_SIZE, read_page, |n| pages.raw(n));
+
+        assert_eq!(map.owner(3), Some(&PageOwner::Unreadable { tree: "t".to_string() }));
+        // Its child pointers are not followed, so page 2 is claimed only once
+        assert_eq!(map.duplicates().count(), 0);
+    }
+}
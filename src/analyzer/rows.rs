@@ -0,0 +1,218 @@
+//! Reading table rows in rowid order, and index entries in key order.
+
+use std::collections::HashSet;
+
+use crate::error::{Result, SqliteVizError};
+use crate::model::{Cell, Page, PageType, Record, TableRow};
+
+/// Iterator over the rows of a table B-tree in rowid order.
+/// Pages are read one at a time as the scan reaches them. A page or record that
+/// cannot be read is returned as an error, and the scan carries on past it.
+pub struct TableScan<F> {
+    read_page: F,
+    /// Pages still to visit, next on top
+    stack: Vec<u32>,
+    visited: HashSet<u32>,
+    /// Rows of the current leaf not yet returned
+    rows: std::vec::IntoIter<Result<TableRow>>,
+}
+
+/// Scan a table B-tree from its root page
+pub fn scan_table<F>(root_page: u32, read_page: F) -> TableScan<F>
+where
+    F: FnMut(u32) -> Result<Page>,
+{
+    TableScan {
+        read_page,
+        stack: vec![root_page],
+        visited: HashSet::new(),
+        rows: Vec::new().into_iter(),
+    }
+}
+
+impl<F> TableScan<F>
+where
+    F: FnMut(u32) -> Result<Page>,
+{
+    /// Read the next page off the stack, queueing children or collecting leaf rows
+    fn visit(&mut self, page_number: u32) -> Result<()> {
+        if !self.visited.insert(page_number) {
+            return Err(SqliteVizError::BTreeError(format!("page {} is reached more than once", page_number)));
+        }
+        let page = (self.read_page)(page_number)?;
+
+        match page.page_type {
+            PageType::LeafTable => {
+                let rows: Vec<_> = page
+                    .cells
+                    .into_iter()
+                    .filter_map(|cell| match cell {
+                        Cell::TableLeaf(cell) => Some(cell),
+                        _ => None,
+                    })
+                    .map(|cell| match cell.payload {
+                        Some(record) => Ok(TableRow { rowid: cell.rowid, page_number, record }),
+                        None => Err(SqliteVizError::BTreeError(format!(
+                            "record for rowid {} on page {} could not be decoded",
+                            cell.rowid, page_number
+                        ))),
+                    })
+                    .collect();
+                self.rows = rows.into_iter();
+            }
+            PageType::InteriorTable => {
+                // Push in reverse so the leftmost child is visited first
+                self.stack.extend(page.header.as_ref().and_then(|h| h.right_most_pointer));
+                self.stack.extend(page.cells.iter().rev().filter_map(|c| c.left_child()));
+            }
+            other => {
+                return Err(SqliteVizError::BTreeError(format!(
+                    "page {} is a {:?} page, not a table B-tree page",
+                    page_number, other
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<F> Iterator for TableScan<F>
+where
+    F: FnMut(u32) -> Result<Page>,
+{
+    type Item = Result<TableRow>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(row) = self.rows.next() {
+                return Some(row);
+            }
+            let page_number = self.stack.pop()?;
+            if let Err(e) = self.visit(page_number) {
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+/// Work left in an index scan: a page to read, or an interior cell's entry to return
+enum IndexStep {
+    Page(u32),
+    Entry(Result<Record>),
+}
+
+/// Iterator over the entries of an index B-tree in key order, interior cells included.
+/// Pages are read one at a time as the scan reaches them. A page or record that cannot
+/// be read is returned as an error, and the scan carries on past it.
+pub struct IndexScan<F> {
+    read_page: F,
+    /// Steps still to take, next on top
+    stack: Vec<IndexStep>,
+    visited: HashSet<u32>,
+}
+
+/// Scan an index B-tree (or a WITHOUT ROWID table) from its root page
+pub fn scan_index<F>(root_page: u32, read_page: F) -> IndexScan<F>
+where
+    F: FnMut(u32) -> Result<Page>,
+{
+    IndexScan {
+        read_page,
+        stack: vec![IndexStep::Page(root_page)],
+        visited: HashSet::new(),
+    }
+}
+
+impl<F> IndexScan<F>
+where
+    F: FnMut(u32) -> Result<Page>,
+{
+    /// Read a page, queueing its children and entries in key order
+    fn visit(&mut self, page_number: u32) -> Result<()> {
+        if !self.visited.insert(page_number) {
+            return Err(SqliteVizError::BTreeError(format!("page {} is reached more than once", page_number)));
+        }
+        let page = (self.read_page)(page_number)?;
+        if !page.page_type.is_index() {
+            return Err(SqliteVizError::BTreeError(format!(
+                "page {} is a {:?} page, not an index B-tree page",
+                page_number, page.page_type
+            )));
+        }
+
+        // Push in reverse: each cell's left child comes before the cell's own entry,
+        // and the right-most child comes last
+        if page.page_type.is_interior() {
+            self.stack.extend(page.header.as_ref().and_then(|h| h.right_most_pointer).map(IndexStep::Page));
+        }
+        for (i, cell) in page.cells.iter().enumerate().rev() {
+            let entry = cell.payload().cloned().ok_or_else(|| {
+                SqliteVizError::BTreeError(format!("cell {} on page {} could not be decoded", i, page_number))
+            });
+            self.stack.push(IndexStep::Entry(entry));
+            if let Some(child) = cell.left_child() {
+                self.stack.push(IndexStep::Page(child));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<F> Iterator for IndexScan<F>
+where
+    F: FnMut(u32) -> Result<Page>,
+{
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.stack.pop()? {
+                IndexStep::Entry(entry) => return Some(entry),
+                IndexStep::Page(page_number) => {
+                    if let Err(e) = self.visit(page_number) {
+                        return Some(Err(e));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{btree_page, index_interior_cell, index_leaf_cell, Pages};
+    use crate::model::Value;
+
+    fn entry(key: i64) -> Vec<Value> {
+        vec![Value::Integer(key), Value::Integer(key + 100)]
+    }
+
+    #[test]
+    fn test_index_scan_visits_interior_entries_between_children() {
+        // Interior entries are real index entries: each comes after its left child's subtree
+        let pages = Pages(vec![
+            btree_page(1, 0x02, &[index_interior_cell(2, &entry(3)), index_interior_cell(3, &entry(6))], Some(4)),
+            btree_page(2, 0x0a, &[index_leaf_cell(&entry(1)), index_leaf_cell(&entry(2))], None),
+            btree_page(3, 0x0a, &[index_leaf_cell(&entry(4)), index_leaf_cell(&entry(5))], None),
+            btree_page(4, 0x0a, &[index_leaf_cell(&entry(7))], None),
+        ]);
+
+        let keys: Vec<_> = scan_index(1, |n| pages.page(n)).map(|e| e.unwrap().values[0].clone()).collect();
+        assert_eq!(keys, (1..=7).map(Value::Integer).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_index_scan_continues_past_unreadable_child() {
+        let pages = Pages(vec![
+            btree_page(1, 0x02, &[index_interior_cell(2, &entry(2))], Some(9)),
+            btree_page(2, 0x0a, &[index_leaf_cell(&entry(1))], None),
+        ]);
+
+        let entries: Vec<_> = scan_index(1, |n| pages.page(n)).collect();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].as_ref().unwrap().values[0], Value::Integer(1));
+        assert_eq!(entries[1].as_ref().unwrap().values[0], Value::Integer(2));
+        assert!(entries[2].is_err());
+    }
+}
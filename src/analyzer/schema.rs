@@ -1,10 +1,18 @@
+use std::collections::HashSet;
+
 use crate::error::{Result, SqliteVizError};
-use crate::model::{Schema, SchemaEntry, ObjectType, Page, Cell, Value, PageType};
+use crate::model::{Schema, SchemaEntry, ObjectType, Page, Cell, Value, PageType, TableDefinition, IndexColumn, BTreeType, RowLayout, ValueSource, Affinity};
+use crate::parser::{default_value, parse_create_index, parse_create_table};
 
-/// Parse the sqlite_schema table from page 1 and build the schema
-pub fn parse_schema(page1: &Page) -> Result<Schema> {
-    let mut schema = Schema::new();
+/// CREATE statement of sqlite_schema itself, which is not stored in the file
+pub const SQLITE_SCHEMA_SQL: &str = "CREATE TABLE sqlite_schema(type text, name text, tbl_name text, rootpage integer, sql text)";
 
+/// Parse the sqlite_schema table rooted at page 1 and build the schema.
+/// Interior pages are followed, so a schema spanning many pages is read completely.
+pub fn parse_schema<F>(page1: &Page, read_page: F) -> Result<Schema>
+where
+    F: FnMut(u32) -> Result<Page>,
+{
     // sqlite_schema is always a table B-tree starting at page 1
     if page1.page_type != PageType::LeafTable && page1.page_type != PageType::InteriorTable {
         return Err(SqliteVizError::SchemaError(
@@ -12,26 +20,13 @@ pub fn parse_schema(page1: &Page) -> Result<Schema> {
         ));
     }
 
-    // For now, we only handle the case where sqlite_schema fits in page 1 (leaf)
-    // A more complete implementation would traverse interior nodes
-    if page1.page_type == PageType::LeafTable {
-        for cell in &page1.cells {
-            if let Cell::TableLeaf(leaf_cell) = cell {
-                if let Some(record) = &leaf_cell.payload {
-                    if let Some(entry) = parse_schema_record(record) {
-                        schema.entries.push(entry);
-                    }
-                }
-            }
-        }
-    }
-
-    Ok(schema)
+    let entries = collect_schema_entries(page1, read_page)?;
+    Ok(Schema { entries })
 }
 
 /// Parse a single schema record from sqlite_schema
 /// Columns: type, name, tbl_name, rootpage, sql
-fn parse_schema_record(record: &crate::model::Record) -> Option<SchemaEntry> {
+pub fn parse_schema_record(record: &crate::model::Record) -> Option<SchemaEntry> {
     if record.values.len() < 5 {
         return None;
     }
@@ -77,21 +72,33 @@ fn parse_schema_record(record: &crate::model::Record) -> Option<SchemaEntry> {
     })
 }
 
-/// Recursively collect schema entries from a potentially multi-page sqlite_schema
+/// Collect schema entries from a potentially multi-page sqlite_schema, in rowid order
 pub fn collect_schema_entries<F>(
     root_page: &Page,
     mut read_page: F,
-    usable_size: u32,
 ) -> Result<Vec<SchemaEntry>>
 where
     F: FnMut(u32) -> Result<Page>,
 {
     let mut entries = Vec::new();
+    let mut visited = HashSet::from([root_page.page_number]);
+    collect_from_page(root_page, &mut read_page, &mut visited, &mut entries)?;
+    Ok(entries)
+}
 
-    match root_page.page_type {
+fn collect_from_page<F>(
+    page: &Page,
+    read_page: &mut F,
+    visited: &mut HashSet<u32>,
+    entries: &mut Vec<SchemaEntry>,
+) -> Result<()>
+where
+    F: FnMut(u32) -> Result<Page>,
+{
+    match page.page_type {
         PageType::LeafTable => {
             // Leaf page - extract records directly
-            for cell in &root_page.cells {
+            for cell in &page.cells {
                 if let Cell::TableLeaf(leaf_cell) = cell {
                     if let Some(record) = &leaf_cell.payload {
                         if let Some(entry) = parse_schema_record(record) {
@@ -102,29 +109,345 @@ where
             }
         }
         PageType::InteriorTable => {
-            // Interior page - traverse children
-            for cell in &root_page.cells {
-                if let Cell::TableInterior(interior_cell) = cell {
-                    let child_page = read_page(interior_cell.left_child_page)?;
-                    let child_entries = collect_schema_entries(&child_page, &mut read_page, usable_size)?;
-                    entries.extend(child_entries);
-                }
-            }
-            // Don't forget the rightmost pointer
-            if let Some(header) = &root_page.header {
-                if let Some(right_page) = header.right_most_pointer {
-                    let child_page = read_page(right_page)?;
-                    let child_entries = collect_schema_entries(&child_page, &mut read_page, usable_size)?;
-                    entries.extend(child_entries);
+            // Interior page - traverse children left to right, then the rightmost pointer
+            let children = page.cells.iter()
+                .filter_map(|cell| cell.left_child())
+                .chain(page.header.as_ref().and_then(|h| h.right_most_pointer));
+
+            for child in children {
+                if !visited.insert(child) {
+                    return Err(SqliteVizError::SchemaError(format!(
+                        "sqlite_schema page {} is reached more than once",
+                        child
+                    )));
                 }
+                let child_page = read_page(child)?;
+                collect_from_page(&child_page, read_page, visited, entries)?;
             }
         }
         _ => {
-            return Err(SqliteVizError::SchemaError(
-                "Unexpected page type in sqlite_schema".to_string()
-            ));
+            return Err(SqliteVizError::SchemaError(format!(
+                "Unexpected page type {:?} in sqlite_schema (page {})",
+                page.page_type, page.page_number
+            )));
         }
     }
 
-    Ok(entries)
+    Ok(())
+}
+
+/// Parse the CREATE TABLE statement of a table
+pub fn table_definition(schema: &Schema, table: &str) -> Result<TableDefinition> {
+    if table == "sqlite_schema" || table == "sqlite_master" {
+        return parse_create_table(SQLITE_SCHEMA_SQL);
+    }
+    let entry = schema
+        .get_table(table)
+        .ok_or_else(|| SqliteVizError::SchemaError(format!("no table named {}", table)))?;
+    let sql = entry
+        .sql
+        .as_deref()
+        .ok_or_else(|| SqliteVizError::SchemaError(format!("table {} has no CREATE statement", table)))?;
+    parse_create_table(sql)
+}
+
+/// Whether a table is declared WITHOUT ROWID, and so stored in an index B-tree keyed by its PRIMARY KEY
+pub fn is_without_rowid(entry: &SchemaEntry) -> bool {
+    entry.object_type == ObjectType::Table
+        && entry.sql.as_deref().and_then(|sql| parse_create_table(sql).ok()).is_some_and(|table| table.without_rowid)
+}
+
+/// Kind of B-tree a table or index is stored in
+pub fn btree_type(entry: &SchemaEntry) -> BTreeType {
+    if entry.object_type == ObjectType::Index || is_without_rowid(entry) {
+        BTreeType::Index
+    } else {
+        BTreeType::Table
+    }
+}
+
+/// Columns of a WITHOUT ROWID table in the order its records store them: the PRIMARY KEY
+/// columns first, then the other stored columns in declaration order
+pub fn without_rowid_record_columns(table: &TableDefinition) -> Vec<String> {
+    let key = table.primary_key.iter().map(|k| k.name.clone());
+    let rest = table
+        .columns
+        .iter()
+        .filter(|c| c.is_stored() && !table.primary_key.iter().any(|k| k.name.eq_ignore_ascii_case(&c.name)))
+        .map(|c| c.name.clone());
+    key.chain(rest).collect()
+}
+
+/// Key columns of an index B-tree: for an index, from its CREATE INDEX statement or, for an
+/// automatic index, from the PRIMARY KEY or UNIQUE constraint of its table that created it;
+/// for a WITHOUT ROWID table, its PRIMARY KEY
+pub fn index_key(schema: &Schema, index: &str) -> Result<Vec<IndexColumn>> {
+    if let Some(table) = schema.get_table(index).filter(|entry| is_without_rowid(entry)) {
+        return Ok(table_definition(schema, &table.name)?.primary_key);
+    }
+    let entry = schema
+        .get_index(index)
+        .ok_or_else(|| SqliteVizError::SchemaError(format!("no index or WITHOUT ROWID table named {}", index)))?;
+    if let Some(sql) = &entry.sql {
+        return Ok(parse_create_index(sql)?.columns);
+    }
+
+    let number = index
+        .strip_prefix("sqlite_autoindex_")
+        .and_then(|rest| rest.rsplit_once('_'))
+        .and_then(|(_, n)| n.parse::<usize>().ok())
+        .filter(|&n| n >= 1)
+        .ok_or_else(|| SqliteVizError::SchemaError(format!("index {} has no CREATE statement", index)))?;
+    table_definition(schema, &entry.table_name)?
+        .autoindexes
+        .get(number - 1)
+        .cloned()
+        .ok_or_else(|| SqliteVizError::SchemaError(format!("no constraint of {} matches {}", entry.table_name, index)))
+}
+
+/// Key columns stored in the records of an index B-tree: `index_key`, followed for an index
+/// on a WITHOUT ROWID table by the table's PRIMARY KEY columns that are not already in the
+/// index, in their PRIMARY KEY order
+pub fn index_record_key(schema: &Schema, index: &str) -> Result<Vec<IndexColumn>> {
+    let mut key = index_key(schema, index)?;
+    let table = schema.get_index(index).and_then(|entry| table_definition(schema, &entry.table_name).ok());
+    if let Some(table) = table.filter(|t| t.without_rowid) {
+        let trailing: Vec<_> = table
+            .primary_key
+            .into_iter()
+            .filter(|k| !key.iter().any(|c| !c.expression && c.name.eq_ignore_ascii_case(&k.name)))
+            .collect();
+        key.extend(trailing);
+    }
+    Ok(key)
+}
+
+/// Names of the values in the records of a B-tree, in record order: the stored columns of a
+/// table, or the key columns of an index followed by the rowid, or for an index on a
+/// WITHOUT ROWID table, by the PRIMARY KEY columns not already in the key.
+/// Empty if the tree is unknown or its CREATE statement cannot be parsed.
+pub fn record_column_names(schema: &Schema, tree: &str) -> Vec<String> {
+    let names = match schema.get_index(tree) {
+        Some(entry) => index_record_key(schema, tree).map(|key| {
+            let without_rowid = schema.get_table(&entry.table_name).is_some_and(is_without_rowid);
+            let rowid = (!without_rowid).then(|| "rowid".to_string());
+            key.into_iter().map(|column| column.name).chain(rowid).collect()
+        }),
+        None => table_definition(schema, tree).map(|table| {
+            if table.without_rowid {
+                without_rowid_record_columns(&table)
+            } else {
+                table.columns.into_iter().filter(|c| c.is_stored()).map(|column| column.name).collect()
+            }
+        }),
+    };
+    names.unwrap_or_default()
+}
+
+/// Affinity of each value in the records of an index or WITHOUT ROWID table, in record
+/// order: that of the table column it holds, INTEGER for the rowid, and BLOB (no
+/// conversion) for expressions. SQLite applies these to a key before searching.
+pub fn key_affinities(schema: &Schema, tree: &str) -> Vec<Affinity> {
+    let table_name = schema.get_index(tree).map_or(tree, |entry| entry.table_name.as_str());
+    let table = table_definition(schema, table_name).ok();
+    record_column_names(schema, tree)
+        .iter()
+        .map(|name| match table.as_ref().and_then(|t| t.column(name)) {
+            Some(column) => column.affinity,
+            None if name == "rowid" => Affinity::Integer,
+            None => Affinity::Blob,
+        })
+        .collect()
+}
+
+/// How the records of a B-tree read as rows: the column names, and for a table its rowid
+/// alias, column affinities and the defaults of columns added by ALTER TABLE
+pub fn row_layout(schema: &Schema, tree: &str) -> RowLayout {
+    let names = record_column_names(schema, tree);
+    let table = match table_definition(schema, tree) {
+        Ok(table) if schema.get_index(tree).is_none() => table,
+        _ => return RowLayout::with_names(names),
+    };
+    let columns: Vec<_> = names.iter().filter_map(|name| table.column(name)).collect();
+    if columns.len() != names.len() {
+        return RowLayout::with_names(names);
+    }
+    RowLayout {
+        defaults: columns
+            .iter()
+            .map(|c| c.default.as_deref().and_then(|sql| default_value(sql, c.affinity)).unwrap_or(Value::Null))
+            .collect(),
+        affinities: columns.iter().map(|c| c.affinity).collect(),
+        // VIRTUAL generated columns are not in the record, so positions can differ from declarations
+        rowid_alias: table
+            .rowid_alias
+            .and_then(|i| names.iter().position(|name| name.eq_ignore_ascii_case(&table.columns[i].name))),
+        names,
+    }
+}
+
+/// `name: value` previews of a row's values, marking those not read from the record
+pub fn labeled_row(layout: &RowLayout, rowid: Option<i64>, values: &[Value], max_len: usize) -> Vec<String> {
+    layout
+        .materialize(rowid, values)
+        .iter()
+        .enumerate()
+        .map(|(i, v)| match v.source {
+            ValueSource::Stored => format!("{}: {}", column_label(&layout.names, i), v.value.preview(max_len)),
+            source => format!("{}: {} ({})", column_label(&layout.names, i), v.value.preview(max_len), source.label()),
+        })
+        .collect()
+}
+
+/// Label of a record value: its column name, or its position if the name is unknown
+pub fn column_label(names: &[String], index: usize) -> String {
+    names.get(index).cloned().unwrap_or_else(|| format!("[{}]", index))
+}
+
+/// `name: value` previews of a record's values
+pub fn labeled_values(names: &[String], values: &[Value], max_len: usize) -> Vec<String> {
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| format!("{}: {}", column_label(names, i), value.preview(max_len)))
+        .collect()
+}
+
+/// A key as SQL-like text, e.g. `uid DESC, grp`
+pub fn key_description(key: &[IndexColumn]) -> String {
+    key.iter()
+        .map(|column| {
+            let mut text = column.name.clone();
+            if let Some(collation) = &column.collation {
+                text.push_str(&format!(" COLLATE {}", collation));
+            }
+            if column.descending {
+                text.push_str(" DESC");
+            }
+            text
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{btree_page, schema_cell, table_interior_cell, Pages};
+
+    fn table_row(rowid: i64, name: &str, root_page: u32) -> Vec<u8> {
+        schema_cell(rowid, "table", name, name, root_page, &format!("CREATE TABLE {}(x)", name))
+    }
+
+    #[test]
+    fn test_parse_schema_follows_interior_pages() {
+        // Page 1 is an interior page over two leaves
+        let pages = Pages(vec![
+            btree_page(1, 0x05, &[table_interior_cell(2, 2)], Some(3)),
+            btree_page(2, 0x0d, &[table_row(1, "a", 4), table_row(2, "b", 5)], None),
+            btree_page(3, 0x0d, &[table_row(3, "c", 6)], None),
+        ]);
+
+        let schema = parse_schema(&pages.page(1).unwrap(), |n| pages.page(n)).unwrap();
+        let names: Vec<_> = schema.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["a", "b", "c"]);
+        assert_eq!(schema.entries[2].root_page, 6);
+    }
+
+    #[test]
+    fn test_parse_schema_rejects_cycle() {
+        // Page 2 points back to page 1
+        let pages = Pages(vec![
+            btree_page(1, 0x05, &[table_interior_cell(2, 1)], Some(3)),
+            btree_page(2, 0x05, &[table_interior_cell(1, 1)], Some(3)),
+            btree_page(3, 0x0d, &[table_row(1, "a", 4)], None),
+        ]);
+
+        let result = parse_schema(&pages.page(1).unwrap(), |n| pages.page(n));
+        assert!(matches!(result, Err(SqliteVizError::SchemaError(_))));
+    }
+
+    fn schema(entries: &[(ObjectType, &str, &str, &str)]) -> Schema {
+        Schema {
+            entries: entries
+                .iter()
+                .enumerate()
+                .map(|(i, &(object_type, name, table_name, sql))| SchemaEntry {
+                    object_type,
+                    name: name.to_string(),
+                    table_name: table_name.to_string(),
+                    root_page: i as u32 + 2,
+                    sql: Some(sql.to_string()),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_key_affinities() {
+        let schema = schema(&[
+            (ObjectType::Table, "t", "t", "CREATE TABLE t(a INTEGER, b TEXT)"),
+            (ObjectType::Index, "i", "t", "CREATE INDEX i ON t(b, a, lower(b))"),
+        ]);
+        assert_eq!(
+            key_affinities(&schema, "i"),
+            [Affinity::Text, Affinity::Integer, Affinity::Blob, Affinity::Integer]
+        );
+    }
+
+    #[test]
+    fn test_virtual_columns_are_not_in_records() {
+        let schema = schema(&[(
+            ObjectType::Table,
+            "g",
+            "g",
+            "CREATE TABLE g(x TEXT, id INTEGER PRIMARY KEY, v INT GENERATED ALWAYS AS (id*2) VIRTUAL, \
+             b TEXT, s AS (id+1) STORED, w AS (b || x))",
+        )]);
+        assert_eq!(record_column_names(&schema, "g"), ["x", "id", "b", "s"]);
+
+        let layout = row_layout(&schema, "g");
+        assert_eq!(layout.rowid_alias, Some(1));
+        assert_eq!(layout.affinities, [Affinity::Text, Affinity::Integer, Affinity::Text, Affinity::Blob]);
+    }
+
+    fn without_rowid_schema() -> Schema {
+        schema(&[
+            (ObjectType::Table, "w", "w", "CREATE TABLE w(a TEXT, b INT, c TEXT, d, PRIMARY KEY(c, a DESC)) WITHOUT ROWID"),
+            (ObjectType::Index, "wb", "w", "CREATE INDEX wb ON w(b, a COLLATE NOCASE)"),
+        ])
+    }
+
+    #[test]
+    fn test_without_rowid_records_start_with_primary_key() {
+        let schema = without_rowid_schema();
+        let table = table_definition(&schema, "w").unwrap();
+        assert_eq!(without_rowid_record_columns(&table), ["c", "a", "b", "d"]);
+        assert_eq!(record_column_names(&schema, "w"), ["c", "a", "b", "d"]);
+
+        let key = index_key(&schema, "w").unwrap();
+        let names: Vec<_> = key.iter().map(|k| k.name.as_str()).collect();
+        assert_eq!(names, ["c", "a"]);
+        assert!(!key[0].descending && key[1].descending);
+    }
+
+    #[test]
+    fn test_index_on_without_rowid_table_ends_with_primary_key() {
+        let without_rowid = without_rowid_schema();
+
+        // `a` is already in the index, so only `c` is appended; there is no rowid
+        let key = index_record_key(&without_rowid, "wb").unwrap();
+        let names: Vec<_> = key.iter().map(|k| k.name.as_str()).collect();
+        assert_eq!(names, ["b", "a", "c"]);
+        assert_eq!(key[1].collation.as_deref(), Some("NOCASE"));
+        assert_eq!(record_column_names(&without_rowid, "wb"), ["b", "a", "c"]);
+
+        // An index on a rowid table stores the rowid instead
+        let schema = schema(&[
+            (ObjectType::Table, "t", "t", "CREATE TABLE t(a, b)"),
+            (ObjectType::Index, "tb", "t", "CREATE INDEX tb ON t(b)"),
+        ]);
+        assert_eq!(index_record_key(&schema, "tb").unwrap().len(), 1);
+        assert_eq!(record_column_names(&schema, "tb"), ["b", "rowid"]);
+    }
 }
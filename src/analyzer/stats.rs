@@ -0,0 +1,255 @@
+//! Reading the planner statistics ANALYZE stores in sqlite_stat1 and sqlite_stat4.
+
+use crate::analyzer::{scan_index, scan_table, RecordComparator};
+use crate::error::{Result, SqliteVizError};
+use crate::model::{Page, Schema, Stat1Entry, Stat4Sample, Statistics, TextEncoding, Value};
+use crate::parser::parse_record;
+
+/// Read sqlite_stat1 and sqlite_stat4, if the database has them. Rows that cannot be read
+/// or decoded are skipped and listed in `errors`.
+pub fn read_statistics<F>(schema: &Schema, encoding: TextEncoding, mut read_page: F) -> Statistics
+where
+    F: FnMut(u32) -> Result<Page>,
+{
+    let mut statistics = Statistics::default();
+    if let Some(entry) = schema.get_table("sqlite_stat1").filter(|e| e.root_page != 0) {
+        for row in scan_table(entry.root_page, &mut read_page) {
+            match row.and_then(|row| parse_stat1_row(&row.record.values)) {
+                Ok(stat) => statistics.stat1.push(stat),
+                Err(e) => statistics.errors.push(format!("sqlite_stat1: {}", e)),
+            }
+        }
+    }
+    if let Some(entry) = schema.get_table("sqlite_stat4").filter(|e| e.root_page != 0) {
+        for row in scan_table(entry.root_page, &mut read_page) {
+            match row.and_then(|row| parse_stat4_row(&row.record.values, encoding)) {
+                Ok(sample) => statistics.stat4.push(sample),
+                Err(e) => statistics.errors.push(format!("sqlite_stat4: {}", e)),
+            }
+        }
+    }
+    statistics
+}
+
+/// Gather the sqlite_stat1 estimates ANALYZE would store for an index B-tree: the entry
+/// count and, for each prefix of the first `key_columns` columns, the average number of
+/// entries per distinct prefix, rounded up. Prefixes are equal when the comparator's
+/// collations say so, and NULLs are equal to each other. None for an empty index.
+pub fn compute_index_stat1<F>(
+    table: &str,
+    index: &str,
+    root_page: u32,
+    key_columns: usize,
+    comparator: &RecordComparator,
+    read_page: F,
+) -> Result<Option<Stat1Entry>>
+where
+    F: FnMut(u32) -> Result<Page>,
+{
+    let mut row_count = 0u64;
+    let mut distinct = vec![0u64; key_columns];
+    let mut previous: Option<Vec<Value>> = None;
+    for entry in scan_index(root_page, read_page) {
+        let values = entry?.values;
+        // A new distinct value for every prefix longer than the part equal to the previous entry
+        let equal = previous.as_ref().map_or(0, |p| comparator.equal_prefix(p, &values).min(key_columns));
+        for count in &mut distinct[equal..] {
+            *count += 1;
+        }
+        row_count += 1;
+        previous = Some(values);
+    }
+    if row_count == 0 {
+        return Ok(None);
+    }
+
+    let rows_per_key = distinct
+        .iter()
+        .map(|&keys| {
+            let rows = row_count.div_ceil(keys);
+            // As in SQLite's analyze.c: keys that are almost all distinct count as unique
+            if rows == 2 && row_count * 10 <= keys * 11 {
+                1
+            } else {
+                rows
+            }
+        })
+        .collect();
+    Ok(Some(Stat1Entry {
+        table: table.to_string(),
+        index: Some(index.to_string()),
+        row_count,
+        rows_per_key,
+        options: Vec::new(),
+    }))
+}
+
+/// Gather the sqlite_stat1 row ANALYZE would store for a table without indexes: its row
+/// count. None for an empty table.
+pub fn compute_table_stat1<F>(table: &str, root_page: u32, read_page: F) -> Result<Option<Stat1Entry>>
+where
+    F: FnMut(u32) -> Result<Page>,
+{
+    let mut row_count = 0u64;
+    for row in scan_table(root_page, read_page) {
+        row?;
+        row_count += 1;
+    }
+    Ok((row_count > 0).then(|| Stat1Entry {
+        table: table.to_string(),
+        index: None,
+        row_count,
+        rows_per_key: Vec::new(),
+        options: Vec::new(),
+    }))
+}
+
+/// Decode a sqlite_stat1 row (tbl, idx, stat). The stat text is the entry count, the
+/// average entries per distinct key prefix, then optional keywords.
+fn parse_stat1_row(values: &[Value]) -> Result<Stat1Entry> {
+    let table = text_column(values, 0, "tbl")?;
+    let index = match values.get(1) {
+        None | Some(Value::Null) => None,
+        Some(_) => Some(text_column(values, 1, "idx")?),
+    };
+    let stat = text_column(values, 2, "stat")?;
+
+    let mut words = stat.split_whitespace().peekable();
+    let mut numbers = Vec::new();
+    while let Some(n) = words.peek().and_then(|w| w.parse::<u64>().ok()) {
+        numbers.push(n);
+        words.next();
+    }
+    let options = words.map(str::to_string).collect();
+    let Some((&row_count, rows_per_key)) = numbers.split_first() else {
+        return Err(SqliteVizError::SchemaError(format!("stat {:?} of {} has no row count", stat, table)));
+    };
+    Ok(Stat1Entry { table, index, row_count, rows_per_key: rows_per_key.to_vec(), options })
+}
+
+/// Decode a sqlite_stat4 row (tbl, idx, neq, nlt, ndlt, sample); the sample is an index
+/// record, decoded like any other
+fn parse_stat4_row(values: &[Value], encoding: TextEncoding) -> Result<Stat4Sample> {
+    let sample = match values.get(5) {
+        Some(Value::Blob(data)) => parse_record(data, encoding)?,
+        _ => return Err(SqliteVizError::SchemaError("sample is not a blob".to_string())),
+    };
+    Ok(Stat4Sample {
+        table: text_column(values, 0, "tbl")?,
+        index: text_column(values, 1, "idx")?,
+        equal: number_list(&text_column(values, 2, "neq")?)?,
+        less: number_list(&text_column(values, 3, "nlt")?)?,
+        distinct_less: number_list(&text_column(values, 4, "ndlt")?)?,
+        sample,
+    })
+}
+
+fn text_column(values: &[Value], index: usize, name: &str) -> Result<String> {
+    match values.get(index) {
+        Some(Value::Text(s)) => Ok(s.clone()),
+        _ => Err(SqliteVizError::SchemaError(format!("{} is not text", name))),
+    }
+}
+
+/// Space-separated integers, as in the neq, nlt and ndlt columns
+fn number_list(text: &str) -> Result<Vec<u64>> {
+    text.split_whitespace()
+        .map(|word| {
+            word.parse()
+                .map_err(|_| SqliteVizError::SchemaError(format!("{:?} is not a list of integers", text)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{btree_page, index_leaf_cell, record, text, Pages};
+
+    fn stat1_row(index: Value, stat: &str) -> Vec<Value> {
+        vec![text("t"), index, text(stat)]
+    }
+
+    #[test]
+    fn test_parse_stat1_row() {
+        let stat = parse_stat1_row(&stat1_row(text("t_ab"), "200 16 3")).unwrap();
+        assert_eq!(stat.index.as_deref(), Some("t_ab"));
+        assert_eq!(stat.row_count, 200);
+        assert_eq!(stat.rows_per_key, [16, 3]);
+        assert!(stat.options.is_empty());
+
+        // A table without indexes has a NULL idx and only its row count
+        let stat = parse_stat1_row(&stat1_row(Value::Null, "42")).unwrap();
+        assert_eq!(stat.index, None);
+        assert_eq!(stat.row_count, 42);
+        assert!(stat.rows_per_key.is_empty());
+
+        assert!(parse_stat1_row(&stat1_row(Value::Null, "unordered")).is_err());
+        assert!(parse_stat1_row(&stat1_row(Value::Integer(1), "1")).is_err());
+    }
+
+    #[test]
+    fn test_parse_stat1_options() {
+        let stat = parse_stat1_row(&stat1_row(text("t_a"), "100 10 unordered sz=24")).unwrap();
+        assert_eq!(stat.rows_per_key, [10]);
+        assert_eq!(stat.options, ["unordered", "sz=24"]);
+        assert_eq!(stat.stat_text(), "100 10 unordered sz=24");
+
+        // Numbers after the first keyword are options too
+        let stat = parse_stat1_row(&stat1_row(text("t_a"), "100 noskipscan 5")).unwrap();
+        assert!(stat.rows_per_key.is_empty());
+        assert_eq!(stat.options, ["noskipscan", "5"]);
+    }
+
+    #[test]
+    fn test_parse_stat4_row() {
+        let sample = record(&[text("abc"), Value::Integer(7)]);
+        let row = [text("t"), text("t_ab"), text("3 1"), text("10 12"), text("4 11"), Value::Blob(sample)];
+
+        let stat = parse_stat4_row(&row, TextEncoding::Utf8).unwrap();
+        assert_eq!((stat.table.as_str(), stat.index.as_str()), ("t", "t_ab"));
+        assert_eq!(stat.equal, [3, 1]);
+        assert_eq!(stat.less, [10, 12]);
+        assert_eq!(stat.distinct_less, [4, 11]);
+        assert_eq!(stat.sample.values, [text("abc"), Value::Integer(7)]);
+
+        let mut not_blob = row.clone();
+        not_blob[5] = text("abc");
+        assert!(parse_stat4_row(&not_blob, TextEncoding::Utf8).is_err());
+        let mut not_numbers = row;
+        not_numbers[2] = text("3 x");
+        assert!(parse_stat4_row(&not_numbers, TextEncoding::Utf8).is_err());
+    }
+
+    fn index_stat1(keys: &[(i64, i64)], key_columns: usize) -> Stat1Entry {
+        let cells: Vec<_> = keys
+            .iter()
+            .enumerate()
+            .map(|(rowid, &(a, b))| index_leaf_cell(&[Value::Integer(a), Value::Integer(b), Value::Integer(rowid as i64)]))
+            .collect();
+        let pages = Pages(vec![btree_page(1, 0x0a, &cells, None)]);
+        let comparator = RecordComparator::new(Vec::new(), TextEncoding::Utf8);
+        compute_index_stat1("t", "t_ab", 1, key_columns, &comparator, |n| pages.page(n)).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_index_stat1_rounds_up() {
+        // 5 entries: 2 distinct values of a (5/2 -> 3), 4 distinct (a, b) pairs (5/4 -> 2)
+        let stat = index_stat1(&[(1, 1), (1, 1), (1, 2), (2, 1), (2, 2)], 2);
+        assert_eq!(stat.row_count, 5);
+        assert_eq!(stat.rows_per_key, [3, 2]);
+        assert_eq!(stat.stat_text(), "5 3 2");
+    }
+
+    #[test]
+    fn test_index_stat1_nearly_unique_keys_count_as_unique() {
+        // 11 entries over 10 keys would round up to 2, but 11*10 <= 10*11
+        let mut keys: Vec<_> = (0..10).map(|a| (a, 0)).collect();
+        keys.insert(5, (5, 0));
+        assert_eq!(index_stat1(&keys, 1).rows_per_key, [1]);
+
+        // 12 entries over 10 keys are not nearly unique
+        keys.insert(8, (7, 0));
+        assert_eq!(index_stat1(&keys, 1).rows_per_key, [2]);
+    }
+}
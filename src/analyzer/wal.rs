@@ -0,0 +1,126 @@
+//! Database snapshots reconstructed from WAL frames.
+
+use std::collections::HashMap;
+
+use crate::error::{Result, SqliteVizError};
+use crate::model::{WalFile, WalSnapshot};
+
+/// Build the snapshot SQLite would see after replaying the WAL up to `commit_frame`.
+/// With `None`, the last valid commit frame is used.
+pub fn wal_snapshot(wal: &WalFile, commit_frame: Option<usize>) -> Result<WalSnapshot> {
+    let last_valid = wal.last_valid_commit_frame();
+
+    let commit_frame = match commit_frame {
+        Some(frame) => frame,
+        None => last_valid.ok_or_else(|| {
+            SqliteVizError::WalError(format!("{} has no valid commit frame", wal.file_name))
+        })?,
+    };
+
+    let frame = wal.frames.get(commit_frame).ok_or_else(|| {
+        SqliteVizError::WalError(format!(
+            "frame {} does not exist ({} frames)",
+            commit_frame,
+            wal.frames.len()
+        ))
+    })?;
+    if !frame.header.is_commit_frame() {
+        return Err(SqliteVizError::WalError(format!("frame {} is not a commit frame", commit_frame)));
+    }
+    if last_valid.is_none_or(|last| commit_frame > last) {
+        return Err(SqliteVizError::WalError(format!(
+            "frame {} is past the first invalid frame and would not be replayed",
+            commit_frame
+        )));
+    }
+
+    // Later frames overwrite earlier versions of the same page
+    let mut page_frames = HashMap::new();
+    for frame in &wal.frames[..=commit_frame] {
+        page_frames.insert(frame.header.page_number, frame.frame_index);
+    }
+
+    Ok(WalSnapshot {
+        commit_frame,
+        db_size: frame.header.db_size_after_commit,
+        page_frames,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{WalFrame, WalFrameHeader, WalHeader};
+
+    /// WAL whose frames are (page number, db size after commit, checksum valid)
+    fn wal(frames: &[(u32, u32, bool)]) -> WalFile {
+        WalFile {
+            header: WalHeader {
+                magic: 0x377f0682,
+                format_version: 3007000,
+                page_size: 512,
+                checkpoint_sequence: 0,
+                salt1: 0,
+                salt2: 0,
+                checksum1: 0,
+                checksum2: 0,
+            },
+            frames: frames
+                .iter()
+                .enumerate()
+                .map(|(frame_index, &(page_number, db_size_after_commit, checksum_valid))| WalFrame {
+                    frame_index,
+                    header: WalFrameHeader {
+                        page_number,
+                        db_size_after_commit,
+                        salt1: 0,
+                        salt2: 0,
+                        checksum1: 0,
+                        checksum2: 0,
+                    },
+                    page: None,
+                    raw_page_data: Vec::new(),
+                    checksum_valid,
+                })
+                .collect(),
+            file_name: "test-wal".to_string(),
+            header_checksum_valid: true,
+        }
+    }
+
+    #[test]
+    fn test_snapshot_at_commit_frame() {
+        // Two transactions: frames 0-1 commit at 3 pages, frames 2-3 rewrite page 2 and grow to 4
+        let wal = wal(&[(2, 0, true), (1, 3, true), (2, 0, true), (4, 4, true)]);
+
+        let snapshot = wal_snapshot(&wal, Some(1)).unwrap();
+        assert_eq!(snapshot.commit_frame, 1);
+        assert_eq!(snapshot.db_size, 3);
+        assert_eq!(snapshot.page_frames, HashMap::from([(2, 0), (1, 1)]));
+
+        let snapshot = wal_snapshot(&wal, Some(3)).unwrap();
+        assert_eq!(snapshot.db_size, 4);
+        assert_eq!(snapshot.page_frames, HashMap::from([(2, 2), (1, 1), (4, 3)]));
+    }
+
+    #[test]
+    fn test_snapshot_rejects_non_commit_frame() {
+        let wal = wal(&[(2, 0, true), (1, 3, true)]);
+        assert!(wal_snapshot(&wal, Some(0)).is_err());
+        assert!(wal_snapshot(&wal, Some(5)).is_err());
+    }
+
+    #[test]
+    fn test_snapshot_defaults_to_last_valid_commit() {
+        // The second transaction's commit frame fails its checksum, so it is not replayed
+        let wal = wal(&[(1, 2, true), (2, 0, true), (2, 3, false)]);
+
+        let snapshot = wal_snapshot(&wal, None).unwrap();
+        assert_eq!(snapshot.commit_frame, 0);
+        assert_eq!(snapshot.db_size, 2);
+        assert!(wal_snapshot(&wal, Some(2)).is_err());
+
+        // Without any valid commit there is nothing to read
+        assert!(wal_snapshot(&self::wal(&[(1, 0, true)]), None).is_err());
+    }
+}
@@ -5,12 +5,14 @@ use std::fs::File;
 use std::io::Write;
 use std::path::Path;
 
+use crate::analyzer::{btree_type, is_without_rowid, key_description, row_layout, table_definition};
 use crate::error::Result;
 use crate::model::{
-    BTree, BTreeNode, BTreeType, Cell, DatabaseHeader, Page, PageType, Record, SerialType, Value,
-    WalFile, WalFrame, WalHeader,
+    truncate_chars, BTree, BTreeNode, BTreeType, Cell, DatabaseHeader, Freelist, JournalHeader,
+    JournalRecord, Page, PageType, PtrmapPage, Record, RollbackJournal, RowLayout, SerialType, Value, ValueSource, WalFile, WalFrame, WalHeader, WalIndex, WalIndexHeader,
+    READ_MARK_NOT_USED,
 };
-use crate::parser::is_wal_file;
+use crate::parser::{is_journal_file, is_wal_file, is_wal_index_file};
 use crate::Database;
 
 /// Detected file type
@@ -19,6 +21,10 @@ pub enum FileType {
     SqliteDb,
     /// WAL (Write-Ahead Log) file
     WalFile,
+    /// WAL-index (-shm) file
+    WalIndex,
+    /// Rollback journal (-journal) file
+    RollbackJournal,
     /// Unknown file format
     Unknown,
 }
@@ -29,6 +35,10 @@ pub fn detect_file_type(data: &[u8]) -> FileType {
         FileType::SqliteDb
     } else if is_wal_file(data) {
         FileType::WalFile
+    } else if is_wal_index_file(data) {
+        FileType::WalIndex
+    } else if is_journal_file(data) {
+        FileType::RollbackJournal
     } else {
         FileType::Unknown
     }
@@ -64,6 +74,10 @@ pub fn dump_to_string(db: &Database, options: &DumpOptions) -> Result<String> {
 
     // Database header info
     dump_header(&mut out, &db.header, db.page_count());
+    if let Some(snapshot) = &db.wal_snapshot {
+        writeln!(out, "WAL snapshot:           commit frame {} ({} pages from WAL)",
+            snapshot.commit_frame, snapshot.page_frames.len()).unwrap();
+    }
 
     // If specific pages requested, just dump those
     if let Some(page_numbers) = &options.pages {
@@ -98,7 +112,16 @@ pub fn dump_to_string(db: &Database, options: &DumpOptions) -> Result<String> {
     writeln!(out).unwrap();
 
     for entry in schema.tables() {
-        writeln!(out, "TABLE: {} (root page {})", entry.name, entry.root_page).unwrap();
+        match table_definition(&schema, &entry.name) {
+            Ok(table) if table.without_rowid => writeln!(
+                out,
+                "TABLE: {} (root page {}, WITHOUT ROWID, key: {})",
+                entry.name,
+                entry.root_page,
+                key_description(&table.primary_key)
+            ).unwrap(),
+            _ => writeln!(out, "TABLE: {} (root page {})", entry.name, entry.root_page).unwrap(),
+        }
         if let Some(sql) = &entry.sql {
             writeln!(out, "  SQL: {}", sql.replace('\n', "\n       ")).unwrap();
         }
@@ -111,6 +134,37 @@ pub fn dump_to_string(db: &Database, options: &DumpOptions) -> Result<String> {
         }
     }
 
+    // Freelist (only in a full dump)
+    if options.btrees.is_none() {
+        writeln!(out).unwrap();
+        writeln!(out, "================================================================================").unwrap();
+        writeln!(out, "FREELIST").unwrap();
+        writeln!(out, "================================================================================").unwrap();
+        writeln!(out).unwrap();
+
+        match db.parse_freelist() {
+            Ok(freelist) => dump_freelist(&mut out, &freelist, db.header.freelist_page_count),
+            Err(e) => writeln!(out, "ERROR: Could not walk freelist: {}", e).unwrap(),
+        }
+
+        if db.header.is_auto_vacuum() {
+            writeln!(out).unwrap();
+            writeln!(out, "================================================================================").unwrap();
+            writeln!(out, "POINTER MAP").unwrap();
+            writeln!(out, "================================================================================").unwrap();
+
+            match db.parse_ptrmap() {
+                Ok(ptrmap) => {
+                    for ptrmap_page in &ptrmap {
+                        writeln!(out).unwrap();
+                        dump_ptrmap_page(&mut out, ptrmap_page);
+                    }
+                }
+                Err(e) => writeln!(out, "ERROR: Could not parse pointer map: {}", e).unwrap(),
+            }
+        }
+    }
+
     // Determine which B-trees to dump
     let mut btrees_to_dump: Vec<(String, u32, BTreeType)> = Vec::new();
 
@@ -118,7 +172,7 @@ pub fn dump_to_string(db: &Database, options: &DumpOptions) -> Result<String> {
     let include_all = options.btrees.is_none();
     let filter_names = options.btrees.as_ref();
 
-    if include_all || filter_names.map_or(false, |f| f.iter().any(|n| n == "sqlite_schema")) {
+    if include_all || filter_names.is_some_and(|f| f.iter().any(|n| n == "sqlite_schema")) {
         btrees_to_dump.push(("sqlite_schema".to_string(), 1, BTreeType::Table));
     }
 
@@ -126,8 +180,8 @@ pub fn dump_to_string(db: &Database, options: &DumpOptions) -> Result<String> {
         if entry.name.starts_with("sqlite_") || entry.root_page == 0 {
             continue;
         }
-        if include_all || filter_names.map_or(false, |f| f.contains(&entry.name)) {
-            btrees_to_dump.push((entry.name.clone(), entry.root_page, BTreeType::Table));
+        if include_all || filter_names.is_some_and(|f| f.contains(&entry.name)) {
+            btrees_to_dump.push((entry.name.clone(), entry.root_page, btree_type(entry)));
         }
     }
 
@@ -135,7 +189,7 @@ pub fn dump_to_string(db: &Database, options: &DumpOptions) -> Result<String> {
         if entry.root_page == 0 {
             continue;
         }
-        if include_all || filter_names.map_or(false, |f| f.contains(&entry.name)) {
+        if include_all || filter_names.is_some_and(|f| f.contains(&entry.name)) {
             btrees_to_dump.push((entry.name.clone(), entry.root_page, BTreeType::Index));
         }
     }
@@ -144,12 +198,16 @@ pub fn dump_to_string(db: &Database, options: &DumpOptions) -> Result<String> {
     for (name, root_page, tree_type) in btrees_to_dump {
         writeln!(out).unwrap();
         writeln!(out, "================================================================================").unwrap();
-        writeln!(out, "B-TREE: {} ({:?})", name, tree_type).unwrap();
+        match schema.get_table(&name).filter(|entry| is_without_rowid(entry)) {
+            Some(_) => writeln!(out, "B-TREE: {} ({:?}, WITHOUT ROWID table)", name, tree_type).unwrap(),
+            None => writeln!(out, "B-TREE: {} ({:?})", name, tree_type).unwrap(),
+        }
         writeln!(out, "================================================================================").unwrap();
 
         match db.build_btree(&name, root_page, tree_type) {
             Ok(btree) => {
-                dump_btree(&mut out, db, &btree, options.no_hex)?;
+                let layout = row_layout(&schema, &name);
+                dump_btree(&mut out, db, &btree, &layout, options.no_hex)?;
             }
             Err(e) => {
                 writeln!(out, "ERROR: Could not build B-tree: {}", e).unwrap();
@@ -185,7 +243,43 @@ fn dump_header(out: &mut String, header: &DatabaseHeader, page_count: u32) {
     writeln!(out, "Version valid for:      {}", header.version_valid_for).unwrap();
 }
 
-fn dump_btree(out: &mut String, db: &Database, btree: &BTree, no_hex: bool) -> Result<()> {
+fn dump_freelist(out: &mut String, freelist: &Freelist, header_count: u32) {
+    writeln!(out, "First trunk page:       {}", freelist.first_trunk).unwrap();
+    writeln!(out, "Trunk pages:            {}", freelist.trunks.len()).unwrap();
+    writeln!(out, "Leaf pages:             {}", freelist.leaf_pages().count()).unwrap();
+    writeln!(out, "Total pages:            {} (header: {})", freelist.total_pages(), header_count).unwrap();
+    if freelist.total_pages() != header_count as usize {
+        writeln!(out, "WARNING: freelist chain does not match header page count").unwrap();
+    }
+
+    for trunk in &freelist.trunks {
+        writeln!(out).unwrap();
+        writeln!(out, "  TRUNK PAGE {}", trunk.page_number).unwrap();
+        match trunk.next_trunk {
+            Some(next) => writeln!(out, "    Next trunk:           {}", next).unwrap(),
+            None => writeln!(out, "    Next trunk:           (end of chain)").unwrap(),
+        }
+        writeln!(out, "    Leaf count:           {}", trunk.leaf_pages.len()).unwrap();
+        if !trunk.leaf_pages.is_empty() {
+            writeln!(out, "    Leaf pages:           {:?}", trunk.leaf_pages).unwrap();
+        }
+    }
+}
+
+fn dump_ptrmap_page(out: &mut String, ptrmap_page: &PtrmapPage) {
+    writeln!(out, "PTRMAP PAGE {} ({} entries)", ptrmap_page.page_number, ptrmap_page.entries.len()).unwrap();
+    for entry in &ptrmap_page.entries {
+        writeln!(
+            out,
+            "    page {:>6}: {:<10} parent {}",
+            entry.page_number,
+            format!("{:?}", entry.entry_type),
+            entry.parent_page
+        ).unwrap();
+    }
+}
+
+fn dump_btree(out: &mut String, db: &Database, btree: &BTree, layout: &RowLayout, no_hex: bool) -> Result<()> {
     writeln!(out).unwrap();
     writeln!(out, "Root page:     {}", btree.root_page).unwrap();
     writeln!(out, "Tree depth:    {}", btree.depth).unwrap();
@@ -204,7 +298,7 @@ fn dump_btree(out: &mut String, db: &Database, btree: &BTree, no_hex: bool) -> R
 
         let page = db.parse_page(node.page_number)?;
         let raw_data = if no_hex { None } else { db.read_page_raw(node.page_number).ok() };
-        dump_page_with_node(out, &page, node, raw_data);
+        dump_page_with_node(out, &page, node, layout, raw_data);
     }
 
     Ok(())
@@ -231,14 +325,14 @@ fn dump_tree_structure(out: &mut String, btree: &BTree) {
     }
 }
 
-fn dump_page_with_node(out: &mut String, page: &Page, node: &BTreeNode, raw_data: Option<&[u8]>) {
+fn dump_page_with_node(out: &mut String, page: &Page, node: &BTreeNode, layout: &RowLayout, raw_data: Option<&[u8]>) {
     writeln!(out, "PAGE {} (depth {}, {:?})", page.page_number, node.depth, page.page_type).unwrap();
 
     if let Some(parent) = node.parent {
         writeln!(out, "  Parent page: {}", parent).unwrap();
     }
 
-    dump_page_common(out, page, raw_data);
+    dump_page_common(out, page, layout, raw_data);
 
     // Overflow info
     if !node.overflow_chains.is_empty() {
@@ -256,11 +350,12 @@ fn dump_page_with_node(out: &mut String, page: &Page, node: &BTreeNode, raw_data
 
 fn dump_page(out: &mut String, page: &Page, raw_data: Option<&[u8]>) {
     writeln!(out, "PAGE {} ({:?})", page.page_number, page.page_type).unwrap();
-    dump_page_common(out, page, raw_data);
+    dump_page_common(out, page, &RowLayout::default(), raw_data);
 }
 
-/// Dump common page content (shared between DB pages and WAL frames)
-pub fn dump_page_common(out: &mut String, page: &Page, raw_data: Option<&[u8]>) {
+/// Dump common page content (shared between DB pages and WAL frames).
+/// `layout` labels record values with column names when the owning B-tree is known.
+pub fn dump_page_common(out: &mut String, page: &Page, layout: &RowLayout, raw_data: Option<&[u8]>) {
     // Header info
     if let Some(header) = &page.header {
         writeln!(out, "  Header:").unwrap();
@@ -281,6 +376,30 @@ pub fn dump_page_common(out: &mut String, page: &Page, raw_data: Option<&[u8]>)
     }
 
     writeln!(out, "  Free space:             {} bytes", page.free_space).unwrap();
+    if let Some(free) = &page.free_space_breakdown {
+        writeln!(out, "    Unallocated:          {} bytes", free.unallocated).unwrap();
+        writeln!(out, "    Freeblocks:           {} bytes in {} blocks", free.freeblocks, page.freeblocks.len()).unwrap();
+        writeln!(out, "    Fragmented:           {} bytes", free.fragmented).unwrap();
+        if let Some(error) = &free.freeblock_error {
+            writeln!(out, "    WARNING: {}", error).unwrap();
+        }
+        if free.unaccounted != 0 {
+            writeln!(
+                out,
+                "    WARNING: cells and free space differ from the cell content area by {} bytes",
+                free.unaccounted
+            ).unwrap();
+        }
+    }
+    for block in &page.freeblocks {
+        writeln!(
+            out,
+            "    Freeblock @ {}: {} bytes, next {}",
+            block.offset,
+            block.size,
+            block.next.map_or("none".to_string(), |n| n.to_string())
+        ).unwrap();
+    }
 
     // Cell pointers
     if !page.cell_pointers.is_empty() {
@@ -292,7 +411,7 @@ pub fn dump_page_common(out: &mut String, page: &Page, raw_data: Option<&[u8]>)
     writeln!(out, "  Cells ({}):", page.cells.len()).unwrap();
 
     for (i, cell) in page.cells.iter().enumerate() {
-        dump_cell(out, i, cell);
+        dump_cell(out, i, cell, layout);
     }
 
     // Hex dump
@@ -303,7 +422,7 @@ pub fn dump_page_common(out: &mut String, page: &Page, raw_data: Option<&[u8]>)
     }
 }
 
-fn dump_cell(out: &mut String, index: usize, cell: &Cell) {
+fn dump_cell(out: &mut String, index: usize, cell: &Cell, layout: &RowLayout) {
     match cell {
         Cell::TableLeaf(c) => {
             writeln!(out, "    [{}] TableLeafCell @ offset {}, {} bytes", index, c.cell_offset, c.cell_size).unwrap();
@@ -313,7 +432,7 @@ fn dump_cell(out: &mut String, index: usize, cell: &Cell) {
                 writeln!(out, "        overflow page: {}", overflow).unwrap();
             }
             if let Some(record) = &c.payload {
-                dump_record(out, record, "        ");
+                dump_record(out, record, layout, Some(c.rowid), "        ");
             }
         }
         Cell::TableInterior(c) => {
@@ -328,7 +447,7 @@ fn dump_cell(out: &mut String, index: usize, cell: &Cell) {
                 writeln!(out, "        overflow page: {}", overflow).unwrap();
             }
             if let Some(record) = &c.payload {
-                dump_record(out, record, "        ");
+                dump_record(out, record, layout, None, "        ");
             }
         }
         Cell::IndexInterior(c) => {
@@ -339,20 +458,40 @@ fn dump_cell(out: &mut String, index: usize, cell: &Cell) {
                 writeln!(out, "        overflow page: {}", overflow).unwrap();
             }
             if let Some(record) = &c.payload {
-                dump_record(out, record, "        ");
+                dump_record(out, record, layout, None, "        ");
             }
         }
     }
 }
 
-fn dump_record(out: &mut String, record: &Record, indent: &str) {
+/// Dump a record's values with their serial types. Values a row reads from elsewhere are
+/// marked: the rowid of an INTEGER PRIMARY KEY, and defaults of columns the record predates.
+fn dump_record(out: &mut String, record: &Record, layout: &RowLayout, rowid: Option<i64>, indent: &str) {
     writeln!(out, "{}record header size: {}", indent, record.header_size).unwrap();
     writeln!(out, "{}columns ({}):", indent, record.values.len()).unwrap();
 
-    for (i, (serial_type, value)) in record.column_types.iter().zip(record.values.iter()).enumerate() {
-        let type_str = format_serial_type(serial_type);
-        let value_str = format_value(value);
-        writeln!(out, "{}  [{}] {} = {}", indent, i, type_str, value_str).unwrap();
+    let row = layout.materialize(rowid, &record.values);
+    for (i, value) in row.iter().enumerate() {
+        let label = match layout.names.get(i) {
+            Some(name) => format!("[{}] {}:", i, name),
+            None => format!("[{}]", i),
+        };
+        match (record.column_types.get(i), record.values.get(i)) {
+            (Some(serial_type), Some(stored)) => {
+                let type_str = format_serial_type(serial_type);
+                let value_str = format_value(stored);
+                match value.source {
+                    ValueSource::RowidAlias => writeln!(
+                        out,
+                        "{}  {} {} = {} (rowid alias: {})",
+                        indent, label, type_str, value_str, format_value(&value.value)
+                    )
+                    .unwrap(),
+                    _ => writeln!(out, "{}  {} {} = {}", indent, label, type_str, value_str).unwrap(),
+                }
+            }
+            _ => writeln!(out, "{}  {} not stored, default = {}", indent, label, format_value(&value.value)).unwrap(),
+        }
     }
 }
 
@@ -379,15 +518,8 @@ fn format_value(value: &Value) -> String {
         Value::Null => "NULL".to_string(),
         Value::Integer(i) => i.to_string(),
         Value::Real(f) => format!("{}", f),
-        Value::Text(s) => {
-            // Truncate long text and escape special characters
-            let display = if s.len() > 100 {
-                format!("{}...", &s[..100])
-            } else {
-                s.clone()
-            };
-            format!("\"{}\"", display.replace('\n', "\\n").replace('\r', "\\r").replace('\t', "\\t"))
-        }
+        Value::Text(s) => format_text(s),
+        Value::MalformedText(s) => format!("{} [malformed text]", format_text(s)),
         Value::Blob(b) => {
             if b.len() <= 32 {
                 format!("x'{}'", hex_encode(b))
@@ -398,6 +530,16 @@ fn format_value(value: &Value) -> String {
     }
 }
 
+fn format_text(s: &str) -> String {
+    // Truncate long text and escape special characters
+    let display = if s.chars().count() > 100 {
+        format!("{}...", truncate_chars(s, 100))
+    } else {
+        s.to_string()
+    };
+    format!("\"{}\"", display.replace('\n', "\\n").replace('\r', "\\r").replace('\t', "\\t"))
+}
+
 fn hex_encode(bytes: &[u8]) -> String {
     bytes.iter().map(|b| format!("{:02x}", b)).collect()
 }
@@ -442,16 +584,21 @@ pub fn dump_hex(out: &mut String, data: &[u8], indent: &str) {
 // WAL dump functions
 // =============================================================================
 
-/// Dump WAL file to a file
-pub fn dump_wal_to_file(wal: &WalFile, output_path: &Path, options: &DumpOptions) -> Result<()> {
-    let content = dump_wal_to_string(wal, options)?;
+/// Dump WAL file to a file, with the state of its WAL-index if available
+pub fn dump_wal_to_file(
+    wal: &WalFile,
+    wal_index: Option<&WalIndex>,
+    output_path: &Path,
+    options: &DumpOptions,
+) -> Result<()> {
+    let content = dump_wal_to_string(wal, wal_index, options)?;
     let mut file = File::create(output_path)?;
     file.write_all(content.as_bytes())?;
     Ok(())
 }
 
 /// Dump WAL file information to a string
-pub fn dump_wal_to_string(wal: &WalFile, options: &DumpOptions) -> Result<String> {
+pub fn dump_wal_to_string(wal: &WalFile, wal_index: Option<&WalIndex>, options: &DumpOptions) -> Result<String> {
     let mut out = String::new();
 
     // Header
@@ -462,10 +609,35 @@ pub fn dump_wal_to_string(wal: &WalFile, options: &DumpOptions) -> Result<String
 
     // WAL header info
     dump_wal_header(&mut out, &wal.header);
+    writeln!(
+        out,
+        "Header checksum:        {}",
+        if wal.header_checksum_valid { "valid" } else { "INVALID" }
+    )
+    .unwrap();
 
     // Frame count summary
     writeln!(out).unwrap();
     writeln!(out, "Frame count:            {}", wal.frames.len()).unwrap();
+    match wal.first_invalid_frame() {
+        Some(frame) => writeln!(out, "First invalid frame:    {}", frame).unwrap(),
+        None => writeln!(out, "First invalid frame:    (none)").unwrap(),
+    }
+    match wal.last_valid_commit_frame() {
+        Some(frame) => writeln!(out, "Replayed frames:        0..={} (last valid commit)", frame).unwrap(),
+        None => writeln!(out, "Replayed frames:        (none - no valid commit frame)").unwrap(),
+    }
+
+    if let Some(index) = wal_index {
+        writeln!(out).unwrap();
+        writeln!(out, "================================================================================").unwrap();
+        writeln!(out, "WAL-INDEX ({})", index.file_name).unwrap();
+        writeln!(out, "================================================================================").unwrap();
+        writeln!(out).unwrap();
+        dump_wal_index_state(&mut out, index);
+        writeln!(out).unwrap();
+        dump_wal_index_consistency(&mut out, index, wal);
+    }
 
     // If specific pages requested, filter frames by DB page number
     let frames_to_dump: Vec<&WalFrame> = if let Some(page_numbers) = &options.pages {
@@ -523,14 +695,15 @@ fn dump_wal_frame(out: &mut String, frame: &WalFrame, no_hex: bool) {
     writeln!(out, "--------------------------------------------------------------------------------").unwrap();
     writeln!(
         out,
-        "FRAME {} (DB page {}){}",
+        "FRAME {} (DB page {}){}{}",
         frame.frame_index,
         frame.header.page_number,
         if frame.header.is_commit_frame() {
             " [COMMIT]"
         } else {
             ""
-        }
+        },
+        if frame.checksum_valid { "" } else { " [BAD CHECKSUM]" }
     )
     .unwrap();
     writeln!(out, "--------------------------------------------------------------------------------").unwrap();
@@ -563,6 +736,12 @@ fn dump_wal_frame(out: &mut String, frame: &WalFrame, no_hex: bool) {
         frame.header.checksum2
     )
     .unwrap();
+    writeln!(
+        out,
+        "    Checksum:             {}",
+        if frame.checksum_valid { "valid" } else { "INVALID" }
+    )
+    .unwrap();
 
     // Page content
     writeln!(out).unwrap();
@@ -575,7 +754,7 @@ fn dump_wal_frame(out: &mut String, frame: &WalFrame, no_hex: bool) {
         } else {
             Some(frame.raw_page_data.as_slice())
         };
-        dump_page_common(out, page, raw_data);
+        dump_page_common(out, page, &RowLayout::default(), raw_data);
     } else {
         writeln!(
             out,
@@ -591,3 +770,286 @@ fn dump_wal_frame(out: &mut String, frame: &WalFrame, no_hex: bool) {
         }
     }
 }
+
+// =============================================================================
+// WAL-index dump functions
+// =============================================================================
+
+/// Dump WAL-index file to a file
+pub fn dump_wal_index_to_file(index: &WalIndex, output_path: &Path, options: &DumpOptions) -> Result<()> {
+    let content = dump_wal_index_to_string(index, options)?;
+    let mut file = File::create(output_path)?;
+    file.write_all(content.as_bytes())?;
+    Ok(())
+}
+
+/// Dump WAL-index file information to a string
+pub fn dump_wal_index_to_string(index: &WalIndex, options: &DumpOptions) -> Result<String> {
+    let mut out = String::new();
+
+    writeln!(out, "================================================================================").unwrap();
+    writeln!(out, "SQLite WAL-Index File Dump").unwrap();
+    writeln!(out, "================================================================================").unwrap();
+    writeln!(out).unwrap();
+
+    dump_wal_index_state(&mut out, index);
+
+    // Frame -> page mapping up to mxFrame, optionally filtered by DB page number
+    let max_frame = index.headers[0].max_frame;
+    writeln!(out).unwrap();
+    writeln!(out, "================================================================================").unwrap();
+    writeln!(out, "HASH TABLES").unwrap();
+    writeln!(out, "================================================================================").unwrap();
+
+    for table in &index.hash_tables {
+        writeln!(out).unwrap();
+        writeln!(
+            out,
+            "Block {} (frames {}..={}): {} hash slots used",
+            table.block,
+            table.first_frame,
+            table.first_frame as usize + table.page_numbers.len() - 1,
+            table.used_slots()
+        )
+        .unwrap();
+        for (i, &page_num) in table.page_numbers.iter().enumerate() {
+            let frame = table.first_frame + i as u32;
+            if frame > max_frame || page_num == 0 {
+                continue;
+            }
+            if options.pages.as_ref().is_some_and(|pages| !pages.contains(&page_num)) {
+                continue;
+            }
+            writeln!(out, "  Frame {:>6} -> page {}", frame, page_num).unwrap();
+        }
+    }
+
+    Ok(out)
+}
+
+fn dump_wal_index_header(out: &mut String, header: &WalIndexHeader) {
+    writeln!(out, "  Version:              {}", header.version).unwrap();
+    writeln!(out, "  Change counter:       {}", header.change_counter).unwrap();
+    writeln!(out, "  Initialized:          {}", header.is_initialized).unwrap();
+    writeln!(
+        out,
+        "  Checksum byte order:  {}",
+        if header.big_endian_checksum { "big-endian" } else { "little-endian" }
+    )
+    .unwrap();
+    writeln!(out, "  Page size:            {} bytes", header.page_size).unwrap();
+    writeln!(out, "  mxFrame:              {}", header.max_frame).unwrap();
+    writeln!(out, "  Database pages:       {}", header.page_count).unwrap();
+    writeln!(
+        out,
+        "  Last frame checksum:  0x{:08x} 0x{:08x}",
+        header.frame_checksum.0, header.frame_checksum.1
+    )
+    .unwrap();
+    writeln!(out, "  Salt-1:               0x{:08x}", header.salt1).unwrap();
+    writeln!(out, "  Salt-2:               0x{:08x}", header.salt2).unwrap();
+    writeln!(
+        out,
+        "  Header checksum:      0x{:08x} 0x{:08x} ({})",
+        header.checksum1,
+        header.checksum2,
+        if header.checksum_valid { "valid" } else { "INVALID" }
+    )
+    .unwrap();
+}
+
+/// Dump the header copies and checkpoint info
+fn dump_wal_index_state(out: &mut String, index: &WalIndex) {
+    writeln!(
+        out,
+        "File byte order:        {}",
+        if index.big_endian { "big-endian" } else { "little-endian" }
+    )
+    .unwrap();
+    writeln!(out, "Hash-table blocks:      {}", index.hash_tables.len()).unwrap();
+    writeln!(
+        out,
+        "Header copies:          {}",
+        if index.headers_match() { "identical" } else { "DIFFER (writer interrupted or in progress)" }
+    )
+    .unwrap();
+
+    // Only show the second copy when it differs from the first
+    let copies = if index.headers_match() { 1 } else { 2 };
+    for (i, header) in index.headers.iter().take(copies).enumerate() {
+        writeln!(out).unwrap();
+        writeln!(out, "WAL-INDEX HEADER (copy {})", i + 1).unwrap();
+        writeln!(out, "--------------------------------------------------------------------------------").unwrap();
+        dump_wal_index_header(out, header);
+    }
+
+    let checkpoint = &index.checkpoint;
+    let max_frame = index.headers[0].max_frame;
+    writeln!(out).unwrap();
+    writeln!(out, "CHECKPOINT INFO").unwrap();
+    writeln!(out, "--------------------------------------------------------------------------------").unwrap();
+    writeln!(out, "  nBackfill:            {} of {} frames", checkpoint.backfill, max_frame).unwrap();
+    writeln!(out, "  nBackfillAttempted:   {}", checkpoint.backfill_attempted).unwrap();
+    for (i, &mark) in checkpoint.read_marks.iter().enumerate() {
+        let state = if mark == READ_MARK_NOT_USED {
+            "unused".to_string()
+        } else if i == 0 {
+            // Readers holding slot 0 ignore the WAL entirely
+            format!("{} (reads database file only)", mark)
+        } else if mark < max_frame {
+            format!("{} ({} frames behind mxFrame)", mark, max_frame - mark)
+        } else {
+            mark.to_string()
+        };
+        writeln!(out, "  Read mark {}:          {}", i, state).unwrap();
+    }
+}
+
+/// Compare the WAL-index against the WAL it describes
+fn dump_wal_index_consistency(out: &mut String, index: &WalIndex, wal: &WalFile) {
+    let header = &index.headers[0];
+    writeln!(out, "CONSISTENCY WITH WAL").unwrap();
+    writeln!(out, "--------------------------------------------------------------------------------").unwrap();
+
+    let salts_match = header.salt1 == wal.header.salt1 && header.salt2 == wal.header.salt2;
+    writeln!(
+        out,
+        "  Salts:                {}",
+        if salts_match { "match WAL header" } else { "DIFFER from WAL header (index describes an older WAL)" }
+    )
+    .unwrap();
+    writeln!(out, "  mxFrame:              {} (WAL has {} frames)", header.max_frame, wal.frames.len()).unwrap();
+
+    // The index numbers frames from 1, this dump from 0
+    let mut mismatches = 0;
+    for frame in wal.frames.iter().take(header.max_frame as usize) {
+        let indexed = index.page_for_frame(frame.frame_index as u32 + 1);
+        if indexed != Some(frame.header.page_number) {
+            mismatches += 1;
+            writeln!(
+                out,
+                "  Frame {}: WAL writes page {}, index says {:?}",
+                frame.frame_index, frame.header.page_number, indexed
+            )
+            .unwrap();
+        }
+    }
+    if mismatches == 0 {
+        writeln!(out, "  Frame -> page map:    matches WAL").unwrap();
+    }
+}
+
+// =============================================================================
+// Rollback journal dump functions
+// =============================================================================
+
+/// Dump rollback journal to a file
+pub fn dump_journal_to_file(journal: &RollbackJournal, output_path: &Path, options: &DumpOptions) -> Result<()> {
+    let content = dump_journal_to_string(journal, options)?;
+    let mut file = File::create(output_path)?;
+    file.write_all(content.as_bytes())?;
+    Ok(())
+}
+
+/// Dump rollback journal information to a string
+pub fn dump_journal_to_string(journal: &RollbackJournal, options: &DumpOptions) -> Result<String> {
+    let mut out = String::new();
+
+    writeln!(out, "================================================================================").unwrap();
+    writeln!(out, "SQLite Rollback Journal Dump").unwrap();
+    writeln!(out, "================================================================================").unwrap();
+    writeln!(out).unwrap();
+
+    let restored = journal.restored_pages();
+    writeln!(out, "File:                   {}", journal.file_name).unwrap();
+    writeln!(out, "Segments:               {}", journal.segments.len()).unwrap();
+    writeln!(out, "Records:                {}", journal.records().count()).unwrap();
+    writeln!(
+        out,
+        "Hot:                    {}",
+        if journal.is_hot() { "yes (would be rolled back)" } else { "no (no records to play back)" }
+    )
+    .unwrap();
+    if let Some(record) = journal.records().find(|r| !r.checksum_valid) {
+        writeln!(out, "First bad checksum:     record at offset {} (playback stops here)", record.offset).unwrap();
+    }
+    if let Some(segment) = journal.segments.first() {
+        writeln!(
+            out,
+            "Rollback restores:      {} pages, truncates database to {} pages",
+            restored.len(),
+            segment.header.initial_page_count
+        )
+        .unwrap();
+        writeln!(out, "Restored pages:         {:?}", restored).unwrap();
+    }
+
+    for (i, segment) in journal.segments.iter().enumerate() {
+        writeln!(out).unwrap();
+        writeln!(out, "================================================================================").unwrap();
+        writeln!(out, "SEGMENT {} (offset {})", i, segment.header.offset).unwrap();
+        writeln!(out, "================================================================================").unwrap();
+        writeln!(out).unwrap();
+        dump_journal_header(&mut out, &segment.header);
+
+        for record in &segment.records {
+            if options.pages.as_ref().is_some_and(|pages| !pages.contains(&record.page_number)) {
+                continue;
+            }
+            writeln!(out).unwrap();
+            dump_journal_record(&mut out, record, restored.contains(&record.page_number), options.no_hex);
+        }
+    }
+
+    Ok(out)
+}
+
+fn dump_journal_header(out: &mut String, header: &JournalHeader) {
+    writeln!(out, "JOURNAL HEADER").unwrap();
+    writeln!(out, "--------------------------------------------------------------------------------").unwrap();
+    if header.record_count == u32::MAX {
+        writeln!(out, "Record count:           0xffffffff (not synced, records run to end of file)").unwrap();
+    } else {
+        writeln!(out, "Record count:           {}", header.record_count).unwrap();
+    }
+    writeln!(out, "Nonce:                  0x{:08x}", header.nonce).unwrap();
+    writeln!(out, "Initial page count:     {}", header.initial_page_count).unwrap();
+    writeln!(out, "Sector size:            {} bytes", header.sector_size).unwrap();
+    writeln!(out, "Page size:              {} bytes", header.page_size).unwrap();
+}
+
+fn dump_journal_record(out: &mut String, record: &JournalRecord, restored: bool, no_hex: bool) {
+    writeln!(out, "--------------------------------------------------------------------------------").unwrap();
+    writeln!(
+        out,
+        "RECORD at offset {} (DB page {}){}{}",
+        record.offset,
+        record.page_number,
+        if restored { " [RESTORED]" } else { "" },
+        if record.checksum_valid { "" } else { " [BAD CHECKSUM]" }
+    )
+    .unwrap();
+    writeln!(out, "--------------------------------------------------------------------------------").unwrap();
+    writeln!(
+        out,
+        "  Checksum:               0x{:08x} ({})",
+        record.checksum,
+        if record.checksum_valid { "valid" } else { "INVALID" }
+    )
+    .unwrap();
+
+    writeln!(out).unwrap();
+    writeln!(out, "  Original Page Content:").unwrap();
+
+    if let Some(page) = &record.page {
+        let raw_data = if no_hex { None } else { Some(record.raw_page_data.as_slice()) };
+        dump_page_common(out, page, &RowLayout::default(), raw_data);
+    } else {
+        writeln!(out, "    (Could not parse page content - may be overflow, freelist, or corrupted)").unwrap();
+        if !no_hex {
+            writeln!(out).unwrap();
+            writeln!(out, "  Hex dump:").unwrap();
+            dump_hex(out, &record.raw_page_data, "    ");
+        }
+    }
+}
@@ -11,6 +11,12 @@ pub enum SqliteVizError {
     #[error("Invalid WAL magic header: {0:#x}")]
     InvalidWalMagic(u32),
 
+    #[error("Invalid WAL-index header")]
+    InvalidWalIndex,
+
+    #[error("Invalid rollback journal header")]
+    InvalidJournal,
+
     #[error("Invalid page type: {0:#x}")]
     InvalidPageType(u8),
 
@@ -29,12 +35,27 @@ pub enum SqliteVizError {
     #[error("Page out of bounds: page {page} requested, but database has {total} pages")]
     PageOutOfBounds { page: u32, total: u32 },
 
+    #[error("Page sizes differ ({old} and {new} bytes), pages cannot be compared")]
+    PageSizeMismatch { old: u32, new: u32 },
+
     #[error("Unexpected end of data while parsing {context}")]
     UnexpectedEof { context: &'static str },
 
     #[error("Schema parse error: {0}")]
     SchemaError(String),
 
+    #[error("Freelist error: {0}")]
+    FreelistError(String),
+
+    #[error("Freeblock error: {0}")]
+    FreeblockError(String),
+
+    #[error("B-tree error: {0}")]
+    BTreeError(String),
+
+    #[error("WAL error: {0}")]
+    WalError(String),
+
     #[error("JSON serialization error: {0}")]
     JsonError(#[from] serde_json::Error),
 }
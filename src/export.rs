@@ -0,0 +1,187 @@
+//! Exporting the rows of a table to CSV, JSON and NDJSON, straight from the B-tree.
+
+use std::io::Write;
+
+use crate::analyzer::{is_without_rowid, row_layout, table_definition};
+use crate::error::Result;
+use crate::model::Value;
+use crate::Database;
+
+/// Output format of `export`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// Comma-separated values with a header row
+    Csv,
+    /// A single JSON array of row objects
+    Json,
+    /// One JSON object per line
+    Ndjson,
+}
+
+/// What an export wrote, and what it had to skip
+#[derive(Debug, Default)]
+pub struct ExportSummary {
+    /// Rows written
+    pub rows: usize,
+    /// Pages, records and schema entries that could not be read
+    pub errors: Vec<String>,
+}
+
+/// A row as read from the B-tree: its rowid, if the table has rowids, and its record values
+type ExportRow = (Option<i64>, Vec<Value>);
+
+/// Write every row of a table, in rowid order, with the rowid as the first column.
+/// A WITHOUT ROWID table has no rowid: its rows come in PRIMARY KEY order instead.
+/// Rows read as SQLite returns them: an INTEGER PRIMARY KEY column holds the rowid, and
+/// columns added after a row was written hold their default.
+/// Unreadable pages and records are skipped and listed in the summary.
+pub fn export_table(db: &Database, table: &str, format: ExportFormat, out: &mut dyn Write) -> Result<ExportSummary> {
+    let schema = db.parse_schema()?;
+    let without_rowid = schema.get_table(table).is_some_and(is_without_rowid);
+    let root_page = match schema.get_table(table).filter(|_| without_rowid) {
+        Some(entry) => entry.root_page,
+        None => db.table_root(&schema, table)?,
+    };
+    // A WITHOUT ROWID table is stored as an index B-tree keyed by its PRIMARY KEY
+    let rows = || -> Box<dyn Iterator<Item = Result<ExportRow>> + '_> {
+        if without_rowid {
+            Box::new(db.scan_index(root_page).map(|entry| entry.map(|record| (None, record.values))))
+        } else {
+            Box::new(db.scan_table(root_page).map(|row| row.map(|row| (Some(row.rowid), row.record.values))))
+        }
+    };
+
+    let mut summary = ExportSummary::default();
+    let definition = table_definition(&schema, table);
+    if let Err(e) = &definition {
+        summary.errors.push(format!("could not read column names: {}", e));
+    }
+    let layout = row_layout(&schema, table);
+    let mut names = layout.names.clone();
+
+    // Records of a WITHOUT ROWID table store the PRIMARY KEY columns first; columns are
+    // written in declaration order all the same. `order` maps output columns to record values.
+    let mut order: Vec<usize> = match &definition {
+        Ok(definition) if without_rowid => definition
+            .columns
+            .iter()
+            .filter_map(|c| names.iter().position(|name| name.eq_ignore_ascii_case(&c.name)))
+            .collect(),
+        _ => (0..names.len()).collect(),
+    };
+
+    // Rows may have more values than the CREATE statement has columns in a damaged file.
+    // Any row can be the widest, so for CSV they are all read once before the header is
+    // written; JSON objects name their extra values row by row instead.
+    if format == ExportFormat::Csv {
+        let widest = rows().filter_map(|row| row.ok()).map(|(_, values)| values.len()).max().unwrap_or(0);
+        for i in names.len()..widest {
+            names.push(format!("column{}", i + 1));
+            order.push(i);
+        }
+    }
+    let rowid_name = (!without_rowid).then(|| {
+        ["rowid", "_rowid_", "oid"]
+            .into_iter()
+            .find(|candidate| !names.iter().any(|n| n.eq_ignore_ascii_case(candidate)))
+            .unwrap_or("rowid")
+    });
+
+    match format {
+        ExportFormat::Csv => {
+            let header: Vec<String> = rowid_name
+                .into_iter()
+                .chain(order.iter().map(|&i| names[i].as_str()))
+                .map(csv_field)
+                .collect();
+            writeln!(out, "{}", header.join(","))?;
+        }
+        ExportFormat::Json => write!(out, "[")?,
+        ExportFormat::Ndjson => {}
+    }
+
+    for row in rows() {
+        let (rowid, values) = match row {
+            Ok(row) => row,
+            Err(e) => {
+                summary.errors.push(e.to_string());
+                continue;
+            }
+        };
+        let values = layout.materialize(rowid, &values);
+        let value = |i: usize| values.get(i).map_or(&Value::Null, |v| &v.value);
+
+        match format {
+            ExportFormat::Csv => {
+                let fields: Vec<String> = rowid
+                    .map(|rowid| rowid.to_string())
+                    .into_iter()
+                    .chain(order.iter().map(|&i| csv_value(value(i))))
+                    .collect();
+                writeln!(out, "{}", fields.join(","))?;
+            }
+            ExportFormat::Json | ExportFormat::Ndjson => {
+                let mut members = Vec::new();
+                if let (Some(name), Some(rowid)) = (rowid_name, rowid) {
+                    members.push(format!("{}:{}", serde_json::to_string(name)?, rowid));
+                }
+                for &i in &order {
+                    members.push(format!("{}:{}", serde_json::to_string(&names[i])?, json_value(value(i))));
+                }
+                for i in names.len()..values.len() {
+                    members.push(format!("\"column{}\":{}", i + 1, json_value(value(i))));
+                }
+                let object = format!("{{{}}}", members.join(","));
+                if format == ExportFormat::Json {
+                    let separator = if summary.rows == 0 { "\n  " } else { ",\n  " };
+                    write!(out, "{}{}", separator, object)?;
+                } else {
+                    writeln!(out, "{}", object)?;
+                }
+            }
+        }
+        summary.rows += 1;
+    }
+
+    if format == ExportFormat::Json {
+        writeln!(out, "{}]", if summary.rows == 0 { "" } else { "\n" })?;
+    }
+    out.flush()?;
+    Ok(summary)
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// CSV representation of a value: NULL as an empty field, blobs as hex
+fn csv_value(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::Integer(i) => i.to_string(),
+        Value::Real(f) => format!("{:?}", f),
+        Value::Text(s) | Value::MalformedText(s) => csv_field(s),
+        Value::Blob(b) => hex_encode(b),
+    }
+}
+
+/// JSON representation of a value, with blobs as hex strings
+fn json_value(value: &Value) -> String {
+    let json = match value {
+        Value::Null => serde_json::Value::Null,
+        Value::Integer(i) => (*i).into(),
+        Value::Real(f) => (*f).into(),
+        Value::Text(s) | Value::MalformedText(s) => s.as_str().into(),
+        Value::Blob(b) => hex_encode(b).into(),
+    };
+    json.to_string()
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
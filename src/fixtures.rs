@@ -0,0 +1,150 @@
+//! Hand-built B-tree pages for unit tests
+
+use crate::error::{Result, SqliteVizError};
+use crate::model::{Page, TextEncoding, Value};
+use crate::parser::{encode_varint, parse_page};
+
+/// Page size of fixture databases
+pub const PAGE_SIZE: u32 = 512;
+
+/// Encode values as a record, using the smallest serial type for each
+pub fn record(values: &[Value]) -> Vec<u8> {
+    let mut types = Vec::new();
+    let mut body = Vec::new();
+    for value in values {
+        let serial_type = match value {
+            Value::Null => 0,
+            Value::Integer(0) => 8,
+            Value::Integer(1) => 9,
+            Value::Integer(i) => {
+                let (serial_type, len) = match *i {
+                    -0x80..=0x7f => (1, 1),
+                    -0x8000..=0x7fff => (2, 2),
+                    -0x80_0000..=0x7f_ffff => (3, 3),
+                    -0x8000_0000..=0x7fff_ffff => (4, 4),
+                    -0x8000_0000_0000..=0x7fff_ffff_ffff => (5, 6),
+                    _ => (6, 8),
+                };
+                body.extend_from_slice(&i.to_be_bytes()[8 - len..]);
+                serial_type
+            }
+            Value::Real(f) => {
+                body.extend_from_slice(&f.to_be_bytes());
+                7
+            }
+            Value::Text(s) | Value::MalformedText(s) => {
+                body.extend_from_slice(s.as_bytes());
+                13 + 2 * s.len() as u64
+            }
+            Value::Blob(b) => {
+                body.extend_from_slice(b);
+                12 + 2 * b.len() as u64
+            }
+        };
+        types.extend(encode_varint(serial_type));
+    }
+
+    // The header size counts its own varint
+    let mut header_size = types.len() + 1;
+    if encode_varint(header_size as u64).len() > 1 {
+        header_size += 1;
+    }
+    let mut data = encode_varint(header_size as u64);
+    data.extend(types);
+    data.extend(body);
+    data
+}
+
+/// Cell of a table leaf page
+pub fn table_leaf_cell(rowid: i64, values: &[Value]) -> Vec<u8> {
+    let payload = record(values);
+    let mut cell = encode_varint(payload.len() as u64);
+    cell.extend(encode_varint(rowid as u64));
+    cell.extend(payload);
+    cell
+}
+
+/// Cell of a table interior page
+pub fn table_interior_cell(left_child: u32, rowid: i64) -> Vec<u8> {
+    let mut cell = left_child.to_be_bytes().to_vec();
+    cell.extend(encode_varint(rowid as u64));
+    cell
+}
+
+/// Cell of an index leaf page
+pub fn index_leaf_cell(values: &[Value]) -> Vec<u8> {
+    let payload = record(values);
+    let mut cell = encode_varint(payload.len() as u64);
+    cell.extend(payload);
+    cell
+}
+
+/// Cell of an index interior page
+pub fn index_interior_cell(left_child: u32, values: &[Value]) -> Vec<u8> {
+    let mut cell = left_child.to_be_bytes().to_vec();
+    cell.extend(index_leaf_cell(values));
+    cell
+}
+
+/// Lay out a B-tree page: cells are packed at the end of the page in order,
+/// so the first cell has the highest offset. Page 1 leaves room for the database header.
+pub fn btree_page(page_number: u32, page_type: u8, cells: &[Vec<u8>], right_child: Option<u32>) -> Vec<u8> {
+    let mut data = vec![0u8; PAGE_SIZE as usize];
+    let header_offset = if page_number == 1 { 100 } else { 0 };
+    let header_size = if right_child.is_some() { 12 } else { 8 };
+
+    let mut content_start = PAGE_SIZE as usize;
+    let mut pointer = header_offset + header_size;
+    for cell in cells {
+        content_start -= cell.len();
+        data[content_start..content_start + cell.len()].copy_from_slice(cell);
+        data[pointer..pointer + 2].copy_from_slice(&(content_start as u16).to_be_bytes());
+        pointer += 2;
+    }
+
+    let header = &mut data[header_offset..];
+    header[0] = page_type;
+    header[3..5].copy_from_slice(&(cells.len() as u16).to_be_bytes());
+    header[5..7].copy_from_slice(&(content_start as u16).to_be_bytes());
+    if let Some(child) = right_child {
+        header[8..12].copy_from_slice(&child.to_be_bytes());
+    }
+    data
+}
+
+/// A database held as page images, page 1 first
+pub struct Pages(pub Vec<Vec<u8>>);
+
+impl Pages {
+    /// Raw image of a page
+    pub fn raw(&self, page_number: u32) -> Result<Vec<u8>> {
+        page_number
+            .checked_sub(1)
+            .and_then(|i| self.0.get(i as usize))
+            .cloned()
+            .ok_or(SqliteVizError::PageOutOfBounds { page: page_number, total: self.count() })
+    }
+
+    /// Parsed page
+    pub fn page(&self, page_number: u32) -> Result<Page> {
+        parse_page(&self.raw(page_number)?, page_number, PAGE_SIZE, PAGE_SIZE, TextEncoding::Utf8)
+    }
+
+    /// Number of pages
+    pub fn count(&self) -> u32 {
+        self.0.len() as u32
+    }
+}
+
+/// sqlite_schema row for a table or index
+pub fn schema_cell(rowid: i64, object_type: &str, name: &str, table_name: &str, root_page: u32, sql: &str) -> Vec<u8> {
+    table_leaf_cell(
+        rowid,
+        &[text(object_type), text(name), text(table_name), Value::Integer(root_page as i64), text(sql)],
+    )
+}
+
+/// Text value
+pub fn text(s: &str) -> Value {
+    Value::Text(s.to_string())
+}
@@ -4,22 +4,47 @@ pub mod model;
 pub mod analyzer;
 pub mod visualization;
 pub mod dump;
+pub mod export;
+pub mod server;
+pub mod tui;
 
+#[cfg(test)]
+mod fixtures;
+
+use std::collections::HashMap;
 use std::path::Path;
 use memmap2::Mmap;
 use std::fs::File;
 
 use crate::error::{Result, SqliteVizError};
-use crate::model::{DatabaseHeader, Page, Schema, BTree, BTreeType};
-use crate::parser::{parse_database_header, parse_page};
-use crate::analyzer::{parse_schema, build_btree};
-use crate::visualization::{VizData, VizDatabaseInfo, VizSchema, VizBTree, VizPage, generate_html};
+use crate::model::{
+    DatabaseHeader, Page, PageType, Schema, BTree, BTreeType, Freelist, PtrmapPage, WalFile, WalSnapshot,
+    PageMap, PageOwner, TableRow, TableDiff, DatabaseDiff, RowChange, PageDiff, PageDiffReport, Value, RowLayout, Statistics, Stat1Entry, StatReport, StatComparison, StatStatus,
+};
+use crate::parser::{
+    parse_database_header, parse_page, parse_raw_page, walk_freelist, is_ptrmap_page, ptrmap_pages,
+    parse_ptrmap_page, reassemble_overflow_payloads,
+};
+use crate::analyzer::{
+    parse_schema, build_btree, expand_overflow_chains, wal_snapshot, check_integrity, IntegrityReport,
+    claim_btree_pages, scan_table, scan_index, IndexScan, diff_table_rows, diff_page_cells, count_changed_bytes, TableScan, lookup_rowid, RowidLookup, LookupResult, lookup_index_key, IndexLookup, Collation, table_definition, index_key, index_record_key, key_affinities, record_column_names, labeled_values, column_label, row_layout, labeled_row, read_statistics, compute_index_stat1, compute_table_stat1, is_without_rowid, btree_type, key_description, KeyColumn, RecordComparator, carve_btree_page, carve_free_page, ColumnProfile, RecoveredRecord, CarveSource,
+};
+use crate::visualization::{
+    VizData, VizDatabaseInfo, VizSchema, VizBTree, VizPage, VizFreelist, VizPtrmapPage, VizPageOwnership,
+    generate_html,
+};
 
 /// Main database reader
 pub struct Database {
     mmap: Mmap,
     pub header: DatabaseHeader,
     file_name: String,
+    /// Pages whose type can only be known by walking the file (e.g. freelist pages)
+    special_pages: HashMap<u32, PageType>,
+    /// Page images from an applied WAL, taking precedence over the main file
+    wal_pages: HashMap<u32, Vec<u8>>,
+    /// The WAL commit the database is being read at, if any
+    pub wal_snapshot: Option<WalSnapshot>,
 }
 
 impl Database {
@@ -39,12 +64,66 @@ impl Database {
             .unwrap_or("database")
             .to_string();
 
-        Ok(Self { mmap, header, file_name })
+        let mut db = Self {
+            mmap,
+            header,
+            file_name,
+            special_pages: HashMap::new(),
+            wal_pages: HashMap::new(),
+            wal_snapshot: None,
+        };
+        db.label_special_pages();
+
+        Ok(db)
+    }
+
+    /// File name of the database, for display
+    pub fn file_name(&self) -> &str {
+        &self.file_name
+    }
+
+    /// Read the database as it was at a WAL commit frame, overlaying the frames
+    /// up to and including it on the main file. With `None`, the last valid
+    /// commit frame is used, which is the state SQLite itself would read.
+    pub fn apply_wal(&mut self, wal: &WalFile, commit_frame: Option<usize>) -> Result<()> {
+        if wal.header.page_size != self.header.page_size {
+            return Err(SqliteVizError::WalError(format!(
+                "WAL page size {} does not match database page size {}",
+                wal.header.page_size, self.header.page_size
+            )));
+        }
+
+        let snapshot = wal_snapshot(wal, commit_frame)?;
+        self.wal_pages = snapshot
+            .page_frames
+            .iter()
+            .map(|(&page_num, &frame_index)| (page_num, wal.frames[frame_index].raw_page_data.clone()))
+            .collect();
+
+        // Page 1 may have been rewritten, so the header (freelist, schema cookie...) can differ
+        if let Some(page1) = self.wal_pages.get(&1) {
+            self.header = parse_database_header(&page1[..100])?;
+        }
+        self.wal_snapshot = Some(snapshot);
+        self.label_special_pages();
+
+        Ok(())
+    }
+
+    /// Record pages whose type is only known by walking the file
+    fn label_special_pages(&mut self) {
+        self.special_pages.clear();
+        // Labelling is best-effort: a damaged freelist is reported by parse_freelist()
+        if let Ok(freelist) = self.parse_freelist() {
+            self.special_pages.extend(freelist.pages());
+        }
     }
 
     /// Get the number of pages in the database
     pub fn page_count(&self) -> u32 {
-        if self.header.database_size_pages > 0 {
+        if let Some(snapshot) = &self.wal_snapshot {
+            snapshot.db_size
+        } else if self.header.database_size_pages > 0 {
             self.header.database_size_pages
         } else {
             // Calculate from file size
@@ -61,6 +140,10 @@ impl Database {
             });
         }
 
+        if let Some(data) = self.wal_pages.get(&page_number) {
+            return Ok(data);
+        }
+
         let page_size = self.header.page_size as usize;
         let offset = (page_number as usize - 1) * page_size;
         let end = offset + page_size;
@@ -78,31 +161,503 @@ impl Database {
     /// Parse a page
     pub fn parse_page(&self, page_number: u32) -> Result<Page> {
         let page_data = self.read_page_raw(page_number)?;
-        parse_page(
+        if let Some(page_type) = self.special_page_type(page_number) {
+            return Ok(parse_raw_page(page_data, page_number, page_type, self.header.page_size));
+        }
+        let mut page = parse_page(
             page_data,
             page_number,
             self.header.page_size,
             self.header.usable_size(),
+            self.header.text_encoding,
+        )?;
+        reassemble_overflow_payloads(
+            &mut page,
+            self.header.usable_size(),
+            self.page_count(),
+            self.header.text_encoding,
+            |page_num| self.read_page_raw(page_num).map(|d| d.to_vec()),
+        );
+        Ok(page)
+    }
+
+    /// Get the type of a page that is not a B-tree page and cannot be identified from its content
+    fn special_page_type(&self, page_number: u32) -> Option<PageType> {
+        if page_number == self.header.lock_byte_page() {
+            return Some(PageType::LockByte);
+        }
+        if self.header.is_auto_vacuum()
+            && is_ptrmap_page(page_number, self.header.usable_size(), self.header.lock_byte_page())
+        {
+            return Some(PageType::PointerMap);
+        }
+        self.special_pages.get(&page_number).copied()
+    }
+
+    /// Parse all pointer-map pages (empty unless the database uses auto-vacuum)
+    pub fn parse_ptrmap(&self) -> Result<Vec<PtrmapPage>> {
+        if !self.header.is_auto_vacuum() {
+            return Ok(Vec::new());
+        }
+
+        let usable_size = self.header.usable_size();
+        let lock_byte_page = self.header.lock_byte_page();
+        ptrmap_pages(self.page_count(), usable_size, lock_byte_page)
+            .into_iter()
+            .map(|page_num| {
+                let page_data = self.read_page_raw(page_num)?;
+                parse_ptrmap_page(page_data, page_num, usable_size, self.page_count(), lock_byte_page)
+            })
+            .collect()
+    }
+
+    /// Walk the freelist trunk chain starting from the header
+    pub fn parse_freelist(&self) -> Result<Freelist> {
+        walk_freelist(
+            self.header.first_freelist_page,
+            self.header.freelist_page_count,
+            self.header.usable_size(),
+            self.page_count(),
+            |page_num| self.read_page_raw(page_num).map(|d| d.to_vec()),
         )
     }
 
     /// Parse the database schema
     pub fn parse_schema(&self) -> Result<Schema> {
         let page1 = self.parse_page(1)?;
-        parse_schema(&page1)
+        parse_schema(&page1, |page_num| self.parse_page(page_num))
     }
 
     /// Build a B-tree for a table or index
     pub fn build_btree(&self, name: &str, root_page: u32, tree_type: BTreeType) -> Result<BTree> {
-        build_btree(
+        let mut btree = build_btree(
             name.to_string(),
             root_page,
             tree_type,
             |page_num| self.parse_page(page_num),
             self.header.usable_size(),
+        )?;
+        expand_overflow_chains(
+            &mut btree,
+            |page_num| self.read_page_raw(page_num).map(|d| d.to_vec()),
+            self.header.usable_size(),
+        );
+        Ok(btree)
+    }
+
+    /// Walk every B-tree in the schema and report structural violations
+    pub fn check_integrity(&self) -> IntegrityReport {
+        check_integrity(
+            self.page_count(),
+            self.header.usable_size(),
+            |page_num| self.parse_page(page_num),
+            |page_num| self.read_page_raw(page_num).map(|d| d.to_vec()),
         )
     }
 
+    /// Assign every page of the file to the structures that use it.
+    /// Pages with more than one owner, or none, indicate corruption or leaks.
+    pub fn page_map(&self) -> Result<PageMap> {
+        let schema = self.parse_schema()?;
+        let page_count = self.page_count();
+        let usable_size = self.header.usable_size();
+        let mut map = PageMap::new(page_count);
+
+        let lock_byte_page = self.header.lock_byte_page();
+        if lock_byte_page <= page_count {
+            map.claim(lock_byte_page, PageOwner::LockByte);
+        }
+        if self.header.is_auto_vacuum() {
+            for page_num in ptrmap_pages(page_count, usable_size, lock_byte_page) {
+                map.claim(page_num, PageOwner::PointerMap);
+            }
+        }
+        // A damaged freelist is reported by parse_freelist(); its pages show up as orphans
+        if let Ok(freelist) = self.parse_freelist() {
+            for (page_num, page_type) in freelist.pages() {
+                let owner = if page_type == PageType::FreelistTrunk {
+                    PageOwner::FreelistTrunk
+                } else {
+                    PageOwner::FreelistLeaf
+                };
+                map.claim(page_num, owner);
+            }
+        }
+
+        let trees = std::iter::once(("sqlite_schema", 1))
+            .chain(schema.tables().chain(schema.indexes()).map(|e| (e.name.as_str(), e.root_page)))
+            .filter(|&(_, root_page)| root_page != 0);
+        for (name, root_page) in trees {
+            claim_btree_pages(
+                &mut map,
+                name,
+                root_page,
+                usable_size,
+                |page_num| self.parse_page(page_num),
+                |page_num| self.read_page_raw(page_num).map(|d| d.to_vec()),
+            );
+        }
+
+        Ok(map)
+    }
+
+    /// Iterate over the rows of a table B-tree in rowid order
+    pub fn scan_table(&self, root_page: u32) -> TableScan<impl FnMut(u32) -> Result<Page> + '_> {
+        scan_table(root_page, move |page_num| self.parse_page(page_num))
+    }
+
+    /// Iterate over the entries of an index B-tree, or the rows of a WITHOUT ROWID table, in key order
+    pub fn scan_index(&self, root_page: u32) -> IndexScan<impl FnMut(u32) -> Result<Page> + '_> {
+        scan_index(root_page, move |page_num| self.parse_page(page_num))
+    }
+
+    /// Compare the rows of every table with a newer version of the database.
+    /// Tables are matched by name; `tables` limits the comparison to the named tables.
+    /// Rows are matched by rowid, so WITHOUT ROWID tables are listed as skipped.
+    pub fn diff_rows(&self, new: &Database, tables: Option<&[String]>) -> Result<DatabaseDiff> {
+        let old_schema = self.parse_schema()?;
+        let new_schema = new.parse_schema()?;
+
+        let root_of = |schema: &Schema, name: &str| {
+            if name == "sqlite_schema" {
+                Some(1)
+            } else {
+                schema.get_table(name).map(|e| e.root_page)
+            }
+        };
+        let mut names = vec!["sqlite_schema".to_string()];
+        for entry in old_schema.tables().chain(new_schema.tables()) {
+            if !names.contains(&entry.name) {
+                names.push(entry.name.clone());
+            }
+        }
+
+        let mut diff = DatabaseDiff::default();
+        for name in names {
+            if tables.is_some_and(|t| !t.contains(&name)) {
+                continue;
+            }
+            let old_root = root_of(&old_schema, &name);
+            let new_root = root_of(&new_schema, &name);
+
+            let skipped = if old_root == Some(0) || new_root == Some(0) {
+                Some("virtual table has no B-tree")
+            } else if old_schema.get_table(&name).is_some_and(is_without_rowid)
+                || new_schema.get_table(&name).is_some_and(is_without_rowid)
+            {
+                Some("WITHOUT ROWID tables have no rowids to match rows on; compare their exports instead")
+            } else {
+                None
+            };
+            if let Some(reason) = skipped {
+                diff.tables.push(TableDiff {
+                    name,
+                    old_root,
+                    new_root,
+                    skipped: Some(reason.to_string()),
+                    changes: Vec::new(),
+                    errors: Vec::new(),
+                });
+                continue;
+            }
+
+            let old_rows: Box<dyn Iterator<Item = Result<TableRow>>> = match old_root {
+                Some(root) => Box::new(self.scan_table(root)),
+                None => Box::new(std::iter::empty()),
+            };
+            let new_rows: Box<dyn Iterator<Item = Result<TableRow>>> = match new_root {
+                Some(root) => Box::new(new.scan_table(root)),
+                None => Box::new(std::iter::empty()),
+            };
+            diff.tables.push(diff_table_rows(&name, old_root, new_root, old_rows, new_rows));
+        }
+
+        Ok(diff)
+    }
+
+    /// Compare the file page by page with a newer version of the database.
+    /// Each changed page is attributed to the structure that owns it, and for B-tree
+    /// pages the cells that were added, removed, moved or rewritten are listed.
+    pub fn diff_pages(&self, new: &Database) -> Result<PageDiffReport> {
+        if self.header.page_size != new.header.page_size {
+            return Err(SqliteVizError::PageSizeMismatch {
+                old: self.header.page_size,
+                new: new.header.page_size,
+            });
+        }
+
+        // A damaged schema in either file just leaves its pages unattributed
+        let old_map = self.page_map().unwrap_or_else(|_| PageMap::new(self.page_count()));
+        let new_map = new.page_map().unwrap_or_else(|_| PageMap::new(new.page_count()));
+        let old_count = self.page_count();
+        let new_count = new.page_count();
+
+        let mut pages = Vec::new();
+        for page_num in 1..=old_count.max(new_count) {
+            let old_data = if page_num <= old_count { Some(self.read_page_raw(page_num)?) } else { None };
+            let new_data = if page_num <= new_count { Some(new.read_page_raw(page_num)?) } else { None };
+            if old_data == new_data {
+                continue;
+            }
+
+            let old_page = old_data.and_then(|_| self.parse_page(page_num).ok());
+            let new_page = new_data.and_then(|_| new.parse_page(page_num).ok());
+            let old_owner = old_map.owner(page_num);
+            let new_owner = new_map.owner(page_num);
+            let owner = new_owner.or(old_owner);
+            let same_tree = old_owner.and_then(|o| o.tree()) == new_owner.and_then(|o| o.tree());
+            let owner_label = match (old_owner, new_owner) {
+                (Some(o), Some(n)) if !same_tree => format!("{} -> {}", o, n),
+                _ => owner.map_or("(unused)".to_string(), |o| o.to_string()),
+            };
+
+            let (header_changed, cell_changes) = match (&old_page, &new_page) {
+                (Some(o), Some(n)) if o.header.is_some() && n.header.is_some() && o.page_type == n.page_type => {
+                    diff_page_cells(o, n, same_tree)
+                }
+                _ => (false, Vec::new()),
+            };
+
+            pages.push(PageDiff {
+                page_number: page_num,
+                old_type: old_page.map(|p| p.page_type),
+                new_type: new_page.map(|p| p.page_type),
+                owner: owner_label,
+                tree: owner.and_then(|o| o.tree()).map(|t| t.to_string()),
+                bytes_changed: count_changed_bytes(old_data.unwrap_or_default(), new_data.unwrap_or_default()),
+                header_changed,
+                cell_changes,
+            });
+        }
+
+        Ok(PageDiffReport {
+            old_file: self.file_name.clone(),
+            new_file: new.file_name.clone(),
+            page_size: new.header.page_size,
+            old_page_count: old_count,
+            new_page_count: new_count,
+            pages,
+        })
+    }
+
+    /// Root page of a rowid table, by name. sqlite_schema is a table too.
+    pub fn table_root(&self, schema: &Schema, name: &str) -> Result<u32> {
+        if name == "sqlite_schema" || name == "sqlite_master" {
+            return Ok(1);
+        }
+        let entry = schema
+            .get_table(name)
+            .ok_or_else(|| SqliteVizError::SchemaError(format!("no table named {}", name)))?;
+        if entry.root_page == 0 {
+            return Err(SqliteVizError::SchemaError(format!("{} has no B-tree (virtual table?)", name)));
+        }
+        if is_without_rowid(entry) {
+            return Err(SqliteVizError::SchemaError(format!(
+                "{} is a WITHOUT ROWID table and has no rowids; search it by primary key with index-lookup",
+                name
+            )));
+        }
+        Ok(entry.root_page)
+    }
+
+    /// Trace the pages a lookup of `rowid` in a table visits, from the root down to the leaf
+    pub fn lookup_rowid(&self, table: &str, rowid: i64) -> Result<RowidLookup> {
+        let schema = self.parse_schema()?;
+        let root_page = self.table_root(&schema, table)?;
+        let layout = row_layout(&schema, table);
+        lookup_rowid(table, root_page, rowid, &layout, |page_num| self.parse_page(page_num))
+    }
+
+    /// Collation and sort order of each key column of an index or WITHOUT ROWID table.
+    /// Columns without a COLLATE clause use the collation declared on the table column.
+    pub fn index_key_columns(&self, schema: &Schema, index: &str) -> Result<Vec<KeyColumn>> {
+        let table_name = schema.get_index(index).map_or(index, |entry| entry.table_name.as_str());
+        let table = table_definition(schema, table_name).ok();
+
+        index_record_key(schema, index)?
+            .into_iter()
+            .map(|column| {
+                let declared = match (&table, column.expression) {
+                    (Some(table), false) => table.column(&column.name).and_then(|c| c.collation.clone()),
+                    _ => None,
+                };
+                let collation = match column.collation.or(declared) {
+                    Some(name) => Collation::from_name(&name)?,
+                    None => Collation::Binary,
+                };
+                Ok(KeyColumn { collation, descending: column.descending })
+            })
+            .collect()
+    }
+
+    /// Trace the search for the entries of an index or WITHOUT ROWID table that start with
+    /// `key`, and collect them. The key is first converted to the affinity of each column,
+    /// so `'42'` finds 42 in an INTEGER column as it would in SQL.
+    pub fn lookup_index_key(&self, index: &str, key: &[Value]) -> Result<IndexLookup> {
+        let schema = self.parse_schema()?;
+        let columns = self.index_key_columns(&schema, index)?;
+        let root_page = schema.get_index(index).or_else(|| schema.get_table(index)).map_or(0, |entry| entry.root_page);
+        if root_page == 0 {
+            return Err(SqliteVizError::SchemaError(format!("{} has no B-tree", index)));
+        }
+        let affinities = key_affinities(&schema, index);
+        let key: Vec<Value> = key
+            .iter()
+            .enumerate()
+            .map(|(i, value)| match affinities.get(i) {
+                Some(affinity) => affinity.apply(value.clone()),
+                None => value.clone(),
+            })
+            .collect();
+        let comparator = RecordComparator::new(columns, self.header.text_encoding);
+        lookup_index_key(index, root_page, &key, &comparator, |page_num| self.parse_page(page_num))
+    }
+
+    /// Planner statistics from sqlite_stat1 and sqlite_stat4; empty if ANALYZE has not been run
+    pub fn statistics(&self, schema: &Schema) -> Statistics {
+        read_statistics(schema, self.header.text_encoding, |page_num| self.parse_page(page_num))
+    }
+
+    /// Gather the sqlite_stat1 estimates ANALYZE would store for an index or WITHOUT ROWID table
+    pub fn compute_index_stat1(&self, schema: &Schema, table: &str, index: &str) -> Result<Option<Stat1Entry>> {
+        let key_columns = index_key(schema, index)?.len();
+        let comparator = RecordComparator::new(self.index_key_columns(schema, index)?, self.header.text_encoding);
+        let root_page = schema.get_index(index).or_else(|| schema.get_table(index)).map_or(0, |entry| entry.root_page);
+        compute_index_stat1(table, index, root_page, key_columns, &comparator, |page_num| self.parse_page(page_num))
+    }
+
+    /// Work out from the B-trees what ANALYZE would store in sqlite_stat1, and compare it
+    /// with what is stored. B-trees that cannot be read are listed as errors and not compared.
+    pub fn stat_report(&self) -> Result<StatReport> {
+        let schema = self.parse_schema()?;
+        let stored = self.statistics(&schema);
+        let mut report = StatReport { comparisons: Vec::new(), errors: stored.errors.clone() };
+
+        // (table, index, estimates) of every B-tree ANALYZE would look at
+        let mut computed = Vec::new();
+        let mut unreadable = Vec::new();
+        for entry in schema.tables() {
+            // ANALYZE skips sqlite_schema, the statistics tables and virtual tables
+            if entry.name.starts_with("sqlite_") || entry.root_page == 0 {
+                continue;
+            }
+            let mut indexes: Vec<&str> = Vec::new();
+            if is_without_rowid(entry) {
+                indexes.push(&entry.name);
+            }
+            indexes.extend(schema.indexes_for_table(&entry.name).filter(|i| i.root_page != 0).map(|i| i.name.as_str()));
+
+            if indexes.is_empty() {
+                match compute_table_stat1(&entry.name, entry.root_page, |page_num| self.parse_page(page_num)) {
+                    Ok(stat) => computed.push((entry.name.clone(), None, stat)),
+                    Err(e) => {
+                        report.errors.push(format!("{}: {}", entry.name, e));
+                        unreadable.push(entry.name.clone());
+                    }
+                }
+            }
+            for index in indexes {
+                match self.compute_index_stat1(&schema, &entry.name, index) {
+                    Ok(stat) => computed.push((entry.name.clone(), Some(index.to_string()), stat)),
+                    Err(e) => {
+                        report.errors.push(format!("{}: {}", index, e));
+                        unreadable.push(index.to_string());
+                    }
+                }
+            }
+        }
+
+        for (table, index, stat) in &computed {
+            let stored = stored.stat1.iter().find(|s| s.table == *table && s.index == *index);
+            let status = match (stat, stored) {
+                (None, None) => continue,
+                (Some(stat), Some(stored)) if stat.same_estimates(stored) => StatStatus::Current,
+                (Some(_), None) => StatStatus::Missing,
+                _ => StatStatus::Stale,
+            };
+            report.comparisons.push(StatComparison {
+                table: table.clone(),
+                index: index.clone(),
+                computed: stat.clone(),
+                stored: stored.cloned(),
+                status,
+            });
+        }
+
+        // Rows ANALYZE would now delete, such as those of dropped indexes
+        for stat in &stored.stat1 {
+            let known = computed.iter().any(|(table, index, _)| stat.table == *table && stat.index == *index);
+            let skipped = unreadable.iter().any(|tree| Some(tree) == stat.index.as_ref() || *tree == stat.table);
+            if !known && !skipped {
+                report.comparisons.push(StatComparison {
+                    table: stat.table.clone(),
+                    index: stat.index.clone(),
+                    computed: None,
+                    stored: Some(stat.clone()),
+                    status: StatStatus::Stale,
+                });
+            }
+        }
+        Ok(report)
+    }
+
+    /// Carve deleted records out of free space on B-tree leaf pages and freelist pages.
+    /// Records are returned in page and offset order.
+    pub fn recover_deleted_records(&self) -> Result<Vec<RecoveredRecord>> {
+        let map = self.page_map()?;
+        let usable_size = self.header.usable_size();
+        let encoding = self.header.text_encoding;
+
+        // Leaf pages of each tree, in tree discovery order
+        let mut leaves: Vec<(String, Vec<Page>)> = Vec::new();
+        for (&page_num, owners) in &map.claims {
+            let Some(tree) = owners.iter().find_map(|o| match o {
+                PageOwner::BTree { tree, page_type } if page_type.is_leaf() => Some(tree),
+                _ => None,
+            }) else {
+                continue;
+            };
+            let Ok(page) = self.parse_page(page_num) else { continue };
+            match leaves.iter_mut().find(|(name, _)| name == tree) {
+                Some((_, pages)) => pages.push(page),
+                None => leaves.push((tree.clone(), vec![page])),
+            }
+        }
+
+        let profiles: Vec<(String, ColumnProfile)> = leaves
+            .iter()
+            .filter_map(|(tree, pages)| {
+                let records = pages.iter().flat_map(|p| p.cells.iter().filter_map(|c| c.payload()));
+                ColumnProfile::from_records(records).map(|profile| (tree.clone(), profile))
+            })
+            .collect();
+
+        let mut recovered = Vec::new();
+        for (tree, pages) in &leaves {
+            let profile = profiles.iter().find(|(name, _)| name == tree).map(|(_, p)| p);
+            for page in pages {
+                recovered.extend(carve_btree_page(page, tree, profile, usable_size, encoding));
+            }
+        }
+
+        for (&page_num, owners) in &map.claims {
+            let data = self.read_page_raw(page_num)?;
+            // Skip the trunk header and leaf list, which overwrote the start of the page
+            let skip = if owners.contains(&PageOwner::FreelistTrunk) {
+                let leaf_count = u32::from_be_bytes([data[4], data[5], data[6], data[7]]) as usize;
+                8 + leaf_count.saturating_mul(4)
+            } else if owners.contains(&PageOwner::FreelistLeaf) {
+                0
+            } else {
+                continue;
+            };
+            recovered.extend(carve_free_page(data, page_num, skip, &profiles, usable_size, encoding));
+        }
+
+        recovered.sort_by_key(|r| (r.page_number, r.offset));
+        Ok(recovered)
+    }
+
     /// Generate visualization data for the entire database
     pub fn generate_viz_data(&self, filter_tables: Option<&[String]>, filter_indexes: Option<&[String]>) -> Result<VizData> {
         let schema = self.parse_schema()?;
@@ -111,14 +666,19 @@ impl Database {
         let mut btrees = Vec::new();
         let mut all_pages = Vec::new();
         let mut seen_pages = std::collections::HashSet::new();
+        // Row layout of each B-tree, and the B-tree each page belongs to
+        let mut tree_layouts = Vec::new();
+        let mut page_trees = HashMap::new();
 
         // sqlite_schema B-tree (always included)
         let schema_btree = self.build_btree("sqlite_schema", 1, BTreeType::Table)?;
         for node in &schema_btree.nodes {
             if seen_pages.insert(node.page_number) {
                 all_pages.push(self.parse_page(node.page_number)?);
+                page_trees.insert(node.page_number, tree_layouts.len());
             }
         }
+        tree_layouts.push(row_layout(&schema, "sqlite_schema"));
         btrees.push(schema_btree);
 
         // Tables
@@ -136,12 +696,14 @@ impl Database {
                 continue;
             }
 
-            let btree = self.build_btree(&entry.name, entry.root_page, BTreeType::Table)?;
+            let btree = self.build_btree(&entry.name, entry.root_page, btree_type(entry))?;
             for node in &btree.nodes {
                 if seen_pages.insert(node.page_number) {
                     all_pages.push(self.parse_page(node.page_number)?);
+                    page_trees.insert(node.page_number, tree_layouts.len());
                 }
             }
+            tree_layouts.push(row_layout(&schema, &entry.name));
             btrees.push(btree);
         }
 
@@ -161,16 +723,101 @@ impl Database {
             for node in &btree.nodes {
                 if seen_pages.insert(node.page_number) {
                     all_pages.push(self.parse_page(node.page_number)?);
+                    page_trees.insert(node.page_number, tree_layouts.len());
                 }
             }
+            tree_layouts.push(row_layout(&schema, &entry.name));
             btrees.push(btree);
         }
 
+        // Freelist pages
+        let freelist = self.parse_freelist().unwrap_or_default();
+        for (page_num, _) in freelist.pages() {
+            if seen_pages.insert(page_num) {
+                if let Ok(page) = self.parse_page(page_num) {
+                    all_pages.push(page);
+                }
+            }
+        }
+
+        // Pointer-map pages
+        let ptrmap = self.parse_ptrmap().unwrap_or_default();
+        for ptrmap_page in &ptrmap {
+            if seen_pages.insert(ptrmap_page.page_number) {
+                if let Ok(page) = self.parse_page(ptrmap_page.page_number) {
+                    all_pages.push(page);
+                }
+            }
+        }
+
+        // Orphaned pages are included so leaked content can be inspected
+        let page_map = self.page_map()?;
+        for page_num in page_map.orphans() {
+            if seen_pages.insert(page_num) {
+                if let Ok(page) = self.parse_page(page_num) {
+                    all_pages.push(page);
+                }
+            }
+        }
+
+        let mut database_info = VizDatabaseInfo::from_header(&self.header, self.file_name.clone());
+        database_info.page_count = self.page_count();
+        database_info.wal_commit_frame = self.wal_snapshot.as_ref().map(|s| s.commit_frame);
+
         Ok(VizData {
-            database_info: VizDatabaseInfo::from_header(&self.header, self.file_name.clone()),
-            schema: VizSchema::from_schema(&schema),
+            database_info,
+            schema: VizSchema::from_schema(&schema, &self.statistics(&schema)),
             btrees: btrees.iter().map(VizBTree::from_btree).collect(),
-            pages: all_pages.iter().map(VizPage::from_page).collect(),
+            pages: all_pages
+                .iter()
+                .map(|page| {
+                    let layout = page_trees.get(&page.page_number).map(|&tree| &tree_layouts[tree]);
+                    VizPage::from_page(page, layout.unwrap_or(&RowLayout::default()))
+                })
+                .collect(),
+            freelist: VizFreelist::from_freelist(&freelist),
+            ptrmap: ptrmap.iter().map(VizPtrmapPage::from_ptrmap_page).collect(),
+            page_map: VizPageOwnership::from_page_map(&page_map),
+            lazy: false,
+            lookup: None,
+        })
+    }
+
+    /// Every B-tree in the schema as (name, type, root page), starting with sqlite_schema
+    pub fn btree_roots(&self, schema: &Schema) -> Vec<(String, BTreeType, u32)> {
+        let tables = schema.tables().filter(|e| e.root_page != 0)
+            .map(|e| (e.name.clone(), btree_type(e), e.root_page));
+        let indexes = schema.indexes().filter(|e| e.root_page != 0)
+            .map(|e| (e.name.clone(), BTreeType::Index, e.root_page));
+        std::iter::once(("sqlite_schema".to_string(), BTreeType::Table, 1))
+            .chain(tables)
+            .chain(indexes)
+            .collect()
+    }
+
+    /// Generate visualization data without walking any B-tree, for the `serve` mode
+    /// where B-trees and pages are fetched one at a time as the user navigates
+    pub fn generate_viz_summary(&self) -> Result<VizData> {
+        let schema = self.parse_schema()?;
+        let btrees = self.btree_roots(&schema)
+            .iter()
+            .map(|(name, tree_type, root_page)| VizBTree::summary(name, *tree_type, *root_page))
+            .collect();
+
+        let mut database_info = VizDatabaseInfo::from_header(&self.header, self.file_name.clone());
+        database_info.page_count = self.page_count();
+        database_info.wal_commit_frame = self.wal_snapshot.as_ref().map(|s| s.commit_frame);
+
+        Ok(VizData {
+            database_info,
+            schema: VizSchema::from_schema(&schema, &self.statistics(&schema)),
+            btrees,
+            pages: Vec::new(),
+            freelist: VizFreelist::from_freelist(&self.parse_freelist().unwrap_or_default()),
+            ptrmap: self.parse_ptrmap().unwrap_or_default().iter().map(VizPtrmapPage::from_ptrmap_page).collect(),
+            page_map: Vec::new(),
+            lazy: true,
+            lookup: None,
         })
     }
 
@@ -193,6 +840,9 @@ pub fn print_database_info(db: &Database, verbose: bool) {
     println!("SQLite Database Information");
     println!("===========================");
     println!("File: {}", db.file_name);
+    if let Some(snapshot) = &db.wal_snapshot {
+        println!("WAL snapshot: commit frame {} ({} pages from WAL)", snapshot.commit_frame, snapshot.page_frames.len());
+    }
     println!("Page size: {} bytes", header.page_size);
     println!("Page count: {}", db.page_count());
     println!("SQLite version: {}", header.sqlite_version_string());
@@ -200,8 +850,30 @@ pub fn print_database_info(db: &Database, verbose: bool) {
     println!("Schema format: {}", header.schema_format);
     println!("User version: {}", header.user_version);
 
+    if header.is_auto_vacuum() {
+        let mode = if header.incremental_vacuum != 0 { "incremental" } else { "full" };
+        println!("Auto-vacuum: {} (largest root page: {})", mode, header.largest_root_page);
+        match db.parse_ptrmap() {
+            Ok(ptrmap) => {
+                let pages: Vec<u32> = ptrmap.iter().map(|p| p.page_number).collect();
+                println!("Pointer-map pages: {:?}", pages);
+            }
+            Err(e) => println!("Pointer-map error: {}", e),
+        }
+    }
+
     if header.first_freelist_page > 0 {
         println!("Freelist pages: {} (first: {})", header.freelist_page_count, header.first_freelist_page);
+        match db.parse_freelist() {
+            Ok(freelist) => {
+                println!("Freelist trunks: {}, leaves: {}", freelist.trunks.len(), freelist.leaf_pages().count());
+                if freelist.total_pages() != header.freelist_page_count as usize {
+                    println!("  Warning: header claims {} freelist pages, chain holds {}",
+                        header.freelist_page_count, freelist.total_pages());
+                }
+            }
+            Err(e) => println!("Freelist error: {}", e),
+        }
     }
 
     if verbose {
@@ -210,11 +882,27 @@ pub fn print_database_info(db: &Database, verbose: bool) {
         println!("------");
 
         if let Ok(schema) = db.parse_schema() {
+            let statistics = db.statistics(&schema);
             for entry in schema.tables() {
-                println!("  TABLE {} (root page {})", entry.name, entry.root_page);
+                match table_definition(&schema, &entry.name) {
+                    Ok(table) if table.without_rowid => {
+                        println!(
+                            "  TABLE {} (root page {}, WITHOUT ROWID, key: {})",
+                            entry.name,
+                            entry.root_page,
+                            key_description(&table.primary_key)
+                        );
+                    }
+                    _ => println!("  TABLE {} (root page {})", entry.name, entry.root_page),
+                }
+                print_tree_statistics(&schema, &statistics, &entry.name);
             }
             for entry in schema.indexes() {
                 println!("  INDEX {} on {} (root page {})", entry.name, entry.table_name, entry.root_page);
+                print_tree_statistics(&schema, &statistics, &entry.name);
+            }
+            for error in &statistics.errors {
+                println!("  Warning: {}", error);
             }
         }
 
@@ -227,9 +915,11 @@ pub fn print_database_info(db: &Database, verbose: bool) {
                 if entry.root_page == 0 || entry.name.starts_with("sqlite_") {
                     continue;
                 }
-                if let Ok(btree) = db.build_btree(&entry.name, entry.root_page, BTreeType::Table) {
+                let tree_type = btree_type(entry);
+                if let Ok(btree) = db.build_btree(&entry.name, entry.root_page, tree_type) {
                     let stats = btree.stats();
-                    println!("  {} (table):", entry.name);
+                    let kind = if tree_type == BTreeType::Index { "WITHOUT ROWID table" } else { "table" };
+                    println!("  {} ({}):", entry.name, kind);
                     println!("    Pages: {} (interior: {}, leaf: {})", stats.total_pages, stats.interior_pages, stats.leaf_pages);
                     println!("    Cells: {}, Depth: {}", stats.total_cells, stats.depth);
                     if stats.overflow_pages > 0 {
@@ -238,5 +928,390 @@ pub fn print_database_info(db: &Database, verbose: bool) {
                 }
             }
         }
+
+        if let Ok(ptrmap) = db.parse_ptrmap() {
+            if !ptrmap.is_empty() {
+                println!();
+                println!("Pointer Map");
+                println!("-----------");
+                for ptrmap_page in &ptrmap {
+                    let mut counts: Vec<(String, usize)> = Vec::new();
+                    for entry in &ptrmap_page.entries {
+                        let name = format!("{:?}", entry.entry_type);
+                        match counts.iter_mut().find(|(n, _)| *n == name) {
+                            Some((_, count)) => *count += 1,
+                            None => counts.push((name, 1)),
+                        }
+                    }
+                    let summary: Vec<String> = counts.iter().map(|(n, c)| format!("{}: {}", n, c)).collect();
+                    println!("  Page {} ({} entries): {}", ptrmap_page.page_number, ptrmap_page.entries.len(), summary.join(", "));
+                }
+            }
+        }
+
+        if let Ok(freelist) = db.parse_freelist() {
+            if !freelist.trunks.is_empty() {
+                println!();
+                println!("Freelist");
+                println!("--------");
+                for trunk in &freelist.trunks {
+                    println!("  Trunk page {} ({} leaves): {:?}", trunk.page_number, trunk.leaf_pages.len(), trunk.leaf_pages);
+                }
+            }
+        }
+    }
+}
+
+/// Print the sqlite_stat1 estimates and sqlite_stat4 samples of a B-tree
+fn print_tree_statistics(schema: &Schema, statistics: &Statistics, tree: &str) {
+    let names = record_column_names(schema, tree);
+    if let Some(stat) = statistics.stat1_for(tree) {
+        let per_key: Vec<String> = stat
+            .rows_per_key
+            .iter()
+            .enumerate()
+            .map(|(i, rows)| match i {
+                0 => format!("{} {}", column_label(&names, 0), rows),
+                _ => {
+                    let prefix: Vec<String> = (0..=i).map(|j| column_label(&names, j)).collect();
+                    format!("({}) {}", prefix.join(", "), rows)
+                }
+            })
+            .collect();
+        let mut line = format!("    stat1: {} rows", stat.row_count);
+        if !per_key.is_empty() {
+            line.push_str(&format!("; avg rows per key: {}", per_key.join(", ")));
+        }
+        if !stat.options.is_empty() {
+            line.push_str(&format!("; {}", stat.options.join(" ")));
+        }
+        println!("{}", line);
+    }
+
+    let samples: Vec<_> = statistics.stat4_for(tree).collect();
+    if !samples.is_empty() {
+        println!("    stat4: {} samples", samples.len());
+        for sample in samples {
+            println!(
+                "      ({})  eq {:?}, lt {:?}, distinct lt {:?}",
+                labeled_values(&names, &sample.sample.values, 40).join(", "),
+                sample.equal,
+                sample.less,
+                sample.distinct_less
+            );
+        }
+    }
+}
+
+/// Print the path of a rowid lookup, one line per page
+pub fn print_rowid_lookup(db: &Database, lookup: &RowidLookup) {
+    println!("Lookup rowid {} in {}", lookup.rowid, lookup.tree);
+
+    for (depth, step) in lookup.path.iter().enumerate() {
+        let indent = "  ".repeat(depth + 1);
+        let choice = match (step.child_page, step.cell_index) {
+            (Some(child), Some(i)) => format!("cell {} of {} -> page {}", i, step.cell_count, child),
+            (Some(child), None) => format!("right-most pointer -> page {}", child),
+            (None, Some(i)) => format!("cell {} of {}", i, step.cell_count),
+            (None, None) => format!("{} cells", step.cell_count),
+        };
+        println!("{}page {} ({:?}): {}", indent, step.page_number, step.page_type, choice);
+    }
+
+    match &lookup.result {
+        LookupResult::Found { cell_index, offset, record, .. } => {
+            println!("Found: cell {} at offset {} on page {}", cell_index, offset, lookup.leaf_page().unwrap_or(0));
+            let layout = db.parse_schema().map(|schema| row_layout(&schema, &lookup.tree)).unwrap_or_default();
+            for value in labeled_row(&layout, Some(lookup.rowid), &record.values, 60) {
+                println!("  {}", value);
+            }
+        }
+        LookupResult::NotFound { insert_index } => {
+            println!(
+                "Not found: would be cell {} on page {}",
+                insert_index,
+                lookup.leaf_page().unwrap_or(0)
+            );
+        }
+    }
+}
+
+/// Print the path of an index key search and up to `limit` of the entries it matched
+pub fn print_index_lookup(db: &Database, lookup: &IndexLookup, limit: usize) {
+    let layout = db.parse_schema().map(|schema| row_layout(&schema, &lookup.tree)).unwrap_or_default();
+    let key = labeled_values(&layout.names, &lookup.key, 40);
+    println!("Lookup ({}) in {}", key.join(", "), lookup.tree);
+
+    for (depth, step) in lookup.path.iter().enumerate() {
+        let indent = "  ".repeat(depth + 1);
+        let choice = match (step.child_page, step.cell_index) {
+            (Some(child), Some(i)) => format!("cell {} of {} -> page {}", i, step.cell_count, child),
+            (Some(child), None) => format!("right-most pointer -> page {}", child),
+            (None, Some(i)) => format!("first match at cell {} of {}", i, step.cell_count),
+            (None, None) => format!("no match among {} cells", step.cell_count),
+        };
+        println!("{}page {} ({:?}): {}", indent, step.page_number, step.page_type, choice);
+    }
+
+    if lookup.matches.is_empty() {
+        println!("No matching entries");
+        return;
+    }
+    println!("{} matching entries:", lookup.matches.len());
+    for entry in lookup.matches.iter().take(limit) {
+        let values = labeled_row(&layout, None, &entry.record.values, 40);
+        println!("  page {} cell {}: ({})", entry.page_number, entry.cell_index, values.join(", "));
+    }
+    if lookup.matches.len() > limit {
+        println!("  ... and {} more", lookup.matches.len() - limit);
+    }
+}
+
+/// Print the computed sqlite_stat1 estimates of each B-tree next to the stored ones
+pub fn print_stat_report(report: &StatReport) {
+    println!("sqlite_stat1 (computed vs stored)");
+    println!("=================================");
+    for comparison in &report.comparisons {
+        let name = match &comparison.index {
+            Some(index) if *index != comparison.table => format!("{} on {}", index, comparison.table),
+            _ => comparison.table.clone(),
+        };
+        let status = match comparison.status {
+            StatStatus::Current => "up to date",
+            StatStatus::Stale => "STALE",
+            StatStatus::Missing => "not analyzed",
+        };
+        println!("  {}: {}", name, status);
+        let text = |stat: &Option<Stat1Entry>| stat.as_ref().map_or("(none)".to_string(), Stat1Entry::stat_text);
+        println!("    computed: {}", text(&comparison.computed));
+        println!("    stored:   {}", text(&comparison.stored));
+    }
+    if report.comparisons.is_empty() {
+        println!("  No non-empty tables or indexes");
+    }
+
+    let count = |status| report.comparisons.iter().filter(|c| c.status == status).count();
+    println!();
+    println!(
+        "{} up to date, {} stale, {} not analyzed",
+        count(StatStatus::Current),
+        count(StatStatus::Stale),
+        count(StatStatus::Missing)
+    );
+    for error in &report.errors {
+        println!("Warning: {}", error);
+    }
+}
+
+/// Print an integrity check report
+pub fn print_integrity_report(db: &Database, report: &IntegrityReport) {
+    println!("Integrity check: {}", db.file_name);
+    println!("Checked {} B-trees, {} pages", report.trees_checked, report.pages_checked);
+
+    if report.is_ok() {
+        println!("ok");
+        return;
+    }
+
+    println!("{} problems found:", report.issues.len());
+    for issue in &report.issues {
+        match issue.offset {
+            Some(offset) => println!("  {}: page {} offset {}: {}", issue.tree, issue.page, offset, issue.message),
+            None => println!("  {}: page {}: {}", issue.tree, issue.page, issue.message),
+        }
+    }
+}
+
+/// Print the owner of every page, with duplicate claims and orphaned pages
+pub fn print_page_map(db: &Database) {
+    println!();
+    println!("Page Ownership");
+    println!("--------------");
+
+    let map = match db.page_map() {
+        Ok(map) => map,
+        Err(e) => {
+            println!("Error: {}", e);
+            return;
+        }
+    };
+
+    // Collapse runs of consecutive pages with the same owners
+    let labels: Vec<String> = (1..=map.page_count)
+        .map(|page_num| match map.claims.get(&page_num) {
+            Some(owners) => owners.iter().map(|o| o.to_string()).collect::<Vec<_>>().join(" + "),
+            None => "(orphan)".to_string(),
+        })
+        .collect();
+    let mut start = 0;
+    while start < labels.len() {
+        let end = start + labels[start..].iter().take_while(|l| **l == labels[start]).count();
+        if end - start == 1 {
+            println!("  {:>13}  {}", start + 1, labels[start]);
+        } else {
+            println!("  {:>13}  {}", format!("{}-{}", start + 1, end), labels[start]);
+        }
+        start = end;
+    }
+
+    let duplicates: Vec<_> = map.duplicates().collect();
+    let orphans = map.orphans();
+    println!();
+    println!("Pages claimed more than once: {}", duplicates.len());
+    for (page_num, owners) in &duplicates {
+        let owners: Vec<String> = owners.iter().map(|o| o.to_string()).collect();
+        println!("  Page {}: {}", page_num, owners.join(", "));
+    }
+    println!("Orphaned pages: {}", orphans.len());
+    if !orphans.is_empty() {
+        println!("  {:?}", orphans);
+    }
+}
+
+/// Print records recovered from free space
+pub fn print_recovered_records(db: &Database, records: &[RecoveredRecord]) {
+    println!("Recovered records: {}", db.file_name);
+    println!("Found {} records", records.len());
+    let schema = db.parse_schema();
+
+    for record in records {
+        let source = match record.source {
+            CarveSource::Freeblock => "freeblock",
+            CarveSource::Unallocated => "unallocated",
+            CarveSource::FreelistPage => "freelist page",
+        };
+        println!();
+        println!(
+            "Page {} offset {} ({}), confidence {:.2}",
+            record.page_number, record.offset, source, record.confidence
+        );
+        println!("  Tree: {}", record.tree.as_deref().unwrap_or("(unknown)"));
+        if let Some(rowid) = record.rowid {
+            println!("  Rowid: {}", rowid);
+        }
+        if record.header_reconstructed {
+            println!("  Record header partly overwritten, reconstructed from live rows");
+        }
+        let names = match (&schema, &record.tree) {
+            (Ok(schema), Some(tree)) => record_column_names(schema, tree),
+            _ => Vec::new(),
+        };
+        println!("  Values: [{}]", labeled_values(&names, &record.record.values, 60).join(", "));
+    }
+}
+
+/// Print the row differences between two databases
+pub fn print_database_diff(old: &Database, new: &Database, diff: &DatabaseDiff) {
+    println!("Diff: {} -> {}", old.file_name, new.file_name);
+    let old_schema = old.parse_schema();
+    let new_schema = new.parse_schema();
+
+    for table in &diff.tables {
+        if let Some(reason) = &table.skipped {
+            println!();
+            println!("Table {}: skipped ({})", table.name, reason);
+            continue;
+        }
+        if table.is_unchanged() {
+            continue;
+        }
+
+        println!();
+        let status = match (table.old_root, table.new_root) {
+            (None, _) => " (new table)",
+            (_, None) => " (dropped table)",
+            _ => "",
+        };
+        println!(
+            "Table {}{}: {} inserted, {} deleted, {} changed",
+            table.name,
+            status,
+            table.inserted(),
+            table.deleted(),
+            table.changed()
+        );
+        for error in &table.errors {
+            println!("  ! {}", error);
+        }
+
+        // Columns are named as in the new database, or the old one for a dropped table
+        let names = match (&new_schema, &old_schema) {
+            (Ok(schema), _) if table.new_root.is_some() => record_column_names(schema, &table.name),
+            (_, Ok(schema)) => record_column_names(schema, &table.name),
+            _ => Vec::new(),
+        };
+        for change in &table.changes {
+            match change {
+                RowChange::Inserted { rowid, values } => {
+                    println!("  + rowid {}: [{}]", rowid, labeled_values(&names, values, 40).join(", "));
+                }
+                RowChange::Deleted { rowid, values } => {
+                    println!("  - rowid {}: [{}]", rowid, labeled_values(&names, values, 40).join(", "));
+                }
+                RowChange::Changed { rowid, columns } => {
+                    println!("  ~ rowid {}:", rowid);
+                    for column in columns {
+                        let old = column.old.as_ref().map_or("(missing)".to_string(), |v| v.preview(40));
+                        let new = column.new.as_ref().map_or("(missing)".to_string(), |v| v.preview(40));
+                        println!("      {}: {} -> {}", column_label(&names, column.column), old, new);
+                    }
+                }
+            }
+        }
+    }
+
+    if diff.is_empty() {
+        println!();
+        println!("No differences");
+    }
+}
+
+/// Print the pages that differ between two databases, with per-owner totals
+pub fn print_page_diff(report: &PageDiffReport) {
+    println!("Page diff: {} -> {}", report.old_file, report.new_file);
+    println!(
+        "{} of {} pages changed ({} -> {} pages)",
+        report.pages.len(),
+        report.old_page_count.max(report.new_page_count),
+        report.old_page_count,
+        report.new_page_count
+    );
+
+    let summary = report.summary();
+    if !summary.is_empty() {
+        println!();
+        println!("  {:<30} {:>6} {:>9} {:>6} {:>6} {:>6} {:>6}", "Owner", "Pages", "Bytes", "Added", "Remov", "Moved", "Rewr");
+        for s in &summary {
+            println!(
+                "  {:<30} {:>6} {:>9} {:>6} {:>6} {:>6} {:>6}",
+                s.owner, s.pages_changed, s.bytes_changed, s.cells_added, s.cells_removed, s.cells_moved, s.cells_rewritten
+            );
+        }
+    }
+
+    for page in &report.pages {
+        println!();
+        let types = match (page.old_type, page.new_type) {
+            (Some(o), Some(n)) if o == n => format!("{:?}", n),
+            (o, n) => format!(
+                "{} -> {}",
+                o.map_or("(none)".to_string(), |t| format!("{:?}", t)),
+                n.map_or("(none)".to_string(), |t| format!("{:?}", t))
+            ),
+        };
+        println!("Page {} ({}), {}: {} bytes changed", page.page_number, types, page.owner, page.bytes_changed);
+        if page.header_changed {
+            println!("  page header / cell pointers changed");
+        }
+        for change in &page.cell_changes {
+            let rowid = change.rowid.map_or(String::new(), |r| format!(" rowid {}", r));
+            let offsets = match (change.old_offset, change.new_offset) {
+                (Some(o), Some(n)) if o != n => format!("offset {} -> {}", o, n),
+                (_, Some(n)) => format!("offset {}", n),
+                (Some(o), None) => format!("offset {}", o),
+                (None, None) => String::new(),
+            };
+            println!("  {:?}{}: {}, {} bytes", change.kind, rowid, offsets, change.size);
+        }
     }
 }
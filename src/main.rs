@@ -1,8 +1,14 @@
-use std::path::PathBuf;
-use clap::{Parser, Subcommand};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use clap::{Args, Parser, Subcommand};
 use anyhow::Result;
 
-use sqlite_viz::{Database, print_database_info, dump, parser};
+use sqlite_viz::{Database, print_database_info, print_integrity_report, print_page_map, print_recovered_records, print_database_diff, print_page_diff, print_rowid_lookup, print_index_lookup, print_stat_report, dump, parser, server, tui, export};
+use sqlite_viz::export::ExportFormat;
+use sqlite_viz::error::SqliteVizError;
+use sqlite_viz::model::{Value, WalIndex};
+use sqlite_viz::visualization::{generate_heatmap_html, generate_html, VizLookup};
 
 #[derive(Parser)]
 #[command(name = "sqlite-viz")]
@@ -31,6 +37,90 @@ enum Commands {
         /// Filter to specific index(es) - can be specified multiple times
         #[arg(short, long)]
         index: Option<Vec<String>>,
+
+        /// Highlight the search path of a lookup, as TABLE:ROWID, or INDEX:VALUE for the first
+        /// column of an index or the first PRIMARY KEY column of a WITHOUT ROWID table
+        #[arg(long, value_name = "TREE:KEY", value_parser = parse_lookup_spec)]
+        lookup: Option<(String, Value)>,
+
+        #[command(flatten)]
+        wal: WalArgs,
+    },
+
+    /// Serve the visualization over HTTP, loading pages on demand
+    Serve {
+        /// Path to SQLite database file
+        #[arg(value_name = "DATABASE")]
+        database: PathBuf,
+
+        /// Port to listen on
+        #[arg(short, long, default_value_t = 8080)]
+        port: u16,
+
+        /// Address to bind to
+        #[arg(long, default_value = "127.0.0.1")]
+        bind: String,
+
+        #[command(flatten)]
+        wal: WalArgs,
+    },
+
+    /// Browse schema, B-trees and pages in an interactive terminal UI
+    Tui {
+        /// Path to SQLite database file
+        #[arg(value_name = "DATABASE")]
+        database: PathBuf,
+
+        #[command(flatten)]
+        wal: WalArgs,
+    },
+
+    /// Trace the pages a rowid lookup visits, from the root of a table to the leaf
+    Lookup {
+        /// Path to SQLite database file
+        #[arg(value_name = "DATABASE")]
+        database: PathBuf,
+
+        /// Table to search
+        #[arg(value_name = "TABLE")]
+        table: String,
+
+        /// Rowid to look up
+        #[arg(value_name = "ROWID", allow_negative_numbers = true)]
+        rowid: i64,
+
+        /// Print the path as JSON
+        #[arg(long)]
+        json: bool,
+
+        #[command(flatten)]
+        wal: WalArgs,
+    },
+
+    /// Search an index for a key and show the path taken and the matching entries
+    IndexLookup {
+        /// Path to SQLite database file
+        #[arg(value_name = "DATABASE")]
+        database: PathBuf,
+
+        /// Index to search
+        #[arg(value_name = "INDEX")]
+        index: String,
+
+        /// Values for the leading index columns: numbers, NULL, 'text', x'hex' or bare text
+        #[arg(value_name = "VALUE", required = true, allow_negative_numbers = true, value_parser = parse_key_value)]
+        key: Vec<Value>,
+
+        /// Maximum number of matching entries to print
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+
+        /// Print the path and matches as JSON
+        #[arg(long)]
+        json: bool,
+
+        #[command(flatten)]
+        wal: WalArgs,
     },
 
     /// Display information about database structure
@@ -42,11 +132,115 @@ enum Commands {
         /// Show detailed information including schema and B-tree stats
         #[arg(short, long)]
         verbose: bool,
+
+        /// Show the owner of every page, pages claimed twice and orphaned pages
+        #[arg(long)]
+        pages: bool,
+
+        #[command(flatten)]
+        wal: WalArgs,
+    },
+
+    /// Check B-tree structure for corruption, like PRAGMA integrity_check.
+    /// Exits with status 1 if problems are found.
+    Check {
+        /// Path to SQLite database file
+        #[arg(value_name = "DATABASE")]
+        database: PathBuf,
+
+        #[command(flatten)]
+        wal: WalArgs,
+    },
+
+    /// Compute the sqlite_stat1 statistics ANALYZE would gather, without writing them, and
+    /// compare them with the stored ones to find stale statistics
+    Stats {
+        /// Path to SQLite database file
+        #[arg(value_name = "DATABASE")]
+        database: PathBuf,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+
+        #[command(flatten)]
+        wal: WalArgs,
+    },
+
+    /// Recover deleted records from freeblocks, unallocated space and freelist pages
+    Recover {
+        /// Path to SQLite database file
+        #[arg(value_name = "DATABASE")]
+        database: PathBuf,
+
+        /// Only show records attributed to this table or index - can be specified multiple times
+        #[arg(short, long)]
+        table: Option<Vec<String>>,
+
+        /// Hide records scored below this confidence (0.0 to 1.0)
+        #[arg(long, default_value_t = 0.5)]
+        min_confidence: f32,
+
+        /// Print recovered records as JSON
+        #[arg(long)]
+        json: bool,
+
+        #[command(flatten)]
+        wal: WalArgs,
+    },
+
+    /// Export the rows of a table to CSV, JSON or NDJSON without libsqlite3
+    Export {
+        /// Path to SQLite database file
+        #[arg(value_name = "DATABASE")]
+        database: PathBuf,
+
+        /// Table to export
+        #[arg(value_name = "TABLE")]
+        table: String,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+
+        /// Output file path (default: standard output)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        #[command(flatten)]
+        wal: WalArgs,
+    },
+
+    /// Compare the rows of two database files
+    Diff {
+        /// Database before the change
+        #[arg(value_name = "OLD")]
+        old: PathBuf,
+
+        /// Database after the change
+        #[arg(value_name = "NEW")]
+        new: PathBuf,
+
+        /// Only compare these table(s) - can be specified multiple times
+        #[arg(short, long, conflicts_with = "pages")]
+        table: Option<Vec<String>>,
+
+        /// Compare page by page instead of row by row, and write an HTML heatmap
+        #[arg(long)]
+        pages: bool,
+
+        /// Heatmap output path for --pages (default: <new>.diff.html)
+        #[arg(short, long, requires = "pages")]
+        output: Option<PathBuf>,
+
+        /// Print differences as JSON
+        #[arg(long)]
+        json: bool,
     },
 
-    /// Dump database or WAL structure to a human-readable text file for debugging
+    /// Dump a database, WAL, WAL-index or rollback journal to a human-readable text file
     Dump {
-        /// Path to SQLite database or WAL file
+        /// Path to SQLite database, WAL, WAL-index or rollback journal file
         #[arg(value_name = "FILE")]
         database: PathBuf,
 
@@ -65,15 +259,131 @@ enum Commands {
         /// Omit hex dumps from output
         #[arg(long)]
         no_hex: bool,
+
+        #[command(flatten)]
+        wal: WalArgs,
     },
 }
 
+/// WAL options shared by the commands that read a database
+#[derive(Args)]
+struct WalArgs {
+    /// Overlay this WAL file, reading the database as of a commit in it
+    #[arg(long, value_name = "WAL")]
+    wal: Option<PathBuf>,
+
+    /// WAL commit frame to read at (default: last valid commit)
+    #[arg(long, requires = "wal")]
+    commit_frame: Option<usize>,
+}
+
+/// Open a database, optionally overlaying a WAL file up to a commit frame
+fn open_database(path: &Path, wal: &WalArgs) -> Result<Database> {
+    let mut db = Database::open(path)?;
+
+    if let Some(wal_path) = &wal.wal {
+        let file_name = file_name_of(wal_path, "wal");
+        let wal_file = parser::parse_wal_file(&std::fs::read(wal_path)?, file_name)?;
+        db.apply_wal(&wal_file, wal.commit_frame)?;
+    }
+
+    Ok(db)
+}
+
+/// Parse a `TREE:KEY` lookup argument; the key is a rowid or a value in `index-lookup` syntax
+fn parse_lookup_spec(spec: &str) -> std::result::Result<(String, Value), String> {
+    let (tree, key) = spec
+        .split_once(':')
+        .ok_or_else(|| format!("expected TREE:KEY, got {}", spec))?;
+    Ok((tree.to_string(), parse_key_value(key)?))
+}
+
+/// Parse a key value given on the command line, using SQL literal syntax where it applies
+fn parse_key_value(text: &str) -> std::result::Result<Value, String> {
+    if text.eq_ignore_ascii_case("NULL") {
+        return Ok(Value::Null);
+    }
+    if let Ok(i) = text.parse::<i64>() {
+        return Ok(Value::Integer(i));
+    }
+    if text.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '.') {
+        if let Ok(f) = text.parse::<f64>() {
+            return Ok(Value::Real(f));
+        }
+    }
+    if let Some(hex) = text.strip_prefix(['x', 'X']).and_then(|t| t.strip_prefix('\'')).and_then(|t| t.strip_suffix('\'')) {
+        if hex.len() % 2 != 0 {
+            return Err(format!("odd number of hex digits in {}", text));
+        }
+        return (0..hex.len())
+            .step_by(2)
+            .map(|i| {
+                hex.get(i..i + 2)
+                    .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                    .ok_or_else(|| format!("invalid blob literal: {}", text))
+            })
+            .collect::<std::result::Result<Vec<u8>, String>>()
+            .map(Value::Blob);
+    }
+    if let Some(quoted) = text.strip_prefix('\'').and_then(|t| t.strip_suffix('\'')) {
+        return Ok(Value::Text(quoted.replace("''", "'")));
+    }
+    Ok(Value::Text(text.to_string()))
+}
+
+/// File name of a path, for display
+fn file_name_of(path: &Path, default: &str) -> String {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(default)
+        .to_string()
+}
+
+/// Parse the `-shm` file next to a `-wal` file, if it exists.
+/// The WAL-index is only a cache of the WAL, so a zeroed or truncated one is warned about and skipped.
+fn sibling_wal_index(wal_path: &Path) -> Option<WalIndex> {
+    let shm_name = wal_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .and_then(|n| n.strip_suffix("-wal"))
+        .map(|stem| format!("{}-shm", stem))?;
+
+    let shm_path = wal_path.with_file_name(&shm_name);
+    if !shm_path.exists() {
+        return None;
+    }
+    let index = std::fs::read(&shm_path)
+        .map_err(SqliteVizError::from)
+        .and_then(|data| parser::parse_wal_index(&data, shm_name));
+    match index {
+        Ok(index) => Some(index),
+        Err(error) => {
+            eprintln!("Warning: ignoring {}: {}", shm_path.display(), error);
+            None
+        }
+    }
+}
+
+/// Whether an error is the reader of our output going away, e.g. `export ... | head`
+fn is_broken_pipe(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .any(|cause| cause.downcast_ref::<std::io::Error>().is_some_and(|e| e.kind() == std::io::ErrorKind::BrokenPipe))
+}
+
 fn main() -> Result<()> {
+    match run() {
+        Err(e) if is_broken_pipe(&e) => Ok(()),
+        result => result,
+    }
+}
+
+fn run() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Viz { database, output, table, index } => {
-            let db = Database::open(&database)?;
+        Commands::Viz { database, output, mut table, mut index, lookup, wal } => {
+            let db = open_database(&database, &wal)?;
 
             let output_path = output.unwrap_or_else(|| {
                 let mut path = database.clone();
@@ -81,20 +391,151 @@ fn main() -> Result<()> {
                 path
             });
 
+            // The looked-up tree must be in the visualization for its path to be shown
+            if let Some((name, _)) = &lookup {
+                for filter in [&mut table, &mut index].into_iter().flatten() {
+                    filter.push(name.clone());
+                }
+            }
             let filter_tables = table.as_deref();
             let filter_indexes = index.as_deref();
 
-            db.generate_visualization(&output_path, filter_tables, filter_indexes)?;
+            let mut viz_data = db.generate_viz_data(filter_tables, filter_indexes)?;
+            if let Some((name, key)) = &lookup {
+                let has_rowids = db.table_root(&db.parse_schema()?, name).is_ok();
+                viz_data.lookup = Some(match key {
+                    Value::Integer(rowid) if has_rowids => VizLookup::from_rowid_lookup(&db.lookup_rowid(name, *rowid)?),
+                    _ => VizLookup::from_index_lookup(&db.lookup_index_key(name, std::slice::from_ref(key))?),
+                });
+            }
+            generate_html(&viz_data, &output_path)?;
 
             println!("Visualization generated: {}", output_path.display());
         }
 
-        Commands::Info { database, verbose } => {
-            let db = Database::open(&database)?;
+        Commands::Serve { database, port, bind, wal } => {
+            let db = open_database(&database, &wal)?;
+            server::serve(&db, &format!("{}:{}", bind, port))?;
+        }
+
+        Commands::Tui { database, wal } => {
+            let db = open_database(&database, &wal)?;
+            tui::run(&db)?;
+        }
+
+        Commands::Lookup { database, table, rowid, json, wal } => {
+            let db = open_database(&database, &wal)?;
+            let lookup = db.lookup_rowid(&table, rowid)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&lookup)?);
+            } else {
+                print_rowid_lookup(&db, &lookup);
+            }
+        }
+
+        Commands::IndexLookup { database, index, key, limit, json, wal } => {
+            let db = open_database(&database, &wal)?;
+            let lookup = db.lookup_index_key(&index, &key)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&lookup)?);
+            } else {
+                print_index_lookup(&db, &lookup, limit);
+            }
+        }
+
+        Commands::Info { database, verbose, pages, wal } => {
+            let db = open_database(&database, &wal)?;
             print_database_info(&db, verbose);
+            if pages {
+                print_page_map(&db);
+            }
+        }
+
+        Commands::Check { database, wal } => {
+            let db = open_database(&database, &wal)?;
+            let report = db.check_integrity();
+            print_integrity_report(&db, &report);
+            if !report.is_ok() {
+                std::process::exit(1);
+            }
+        }
+
+        Commands::Stats { database, json, wal } => {
+            let db = open_database(&database, &wal)?;
+            let report = db.stat_report()?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print_stat_report(&report);
+            }
+        }
+
+        Commands::Recover { database, table, min_confidence, json, wal } => {
+            let db = open_database(&database, &wal)?;
+            let records: Vec<_> = db
+                .recover_deleted_records()?
+                .into_iter()
+                .filter(|r| r.confidence >= min_confidence)
+                .filter(|r| match &table {
+                    Some(names) => r.tree.as_ref().is_some_and(|tree| names.contains(tree)),
+                    None => true,
+                })
+                .collect();
+            if json {
+                println!("{}", serde_json::to_string_pretty(&records)?);
+            } else {
+                print_recovered_records(&db, &records);
+            }
+        }
+
+        Commands::Export { database, table, format, output, wal } => {
+            let db = open_database(&database, &wal)?;
+            let mut out: Box<dyn Write> = match &output {
+                Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+                None => Box::new(BufWriter::new(std::io::stdout().lock())),
+            };
+            let summary = export::export_table(&db, &table, format, &mut out)?;
+            drop(out);
+
+            for error in &summary.errors {
+                eprintln!("Warning: skipped {}", error);
+            }
+            if let Some(path) = &output {
+                println!("Exported {} rows to {}", summary.rows, path.display());
+            }
         }
 
-        Commands::Dump { database, output, tree, page, no_hex } => {
+        Commands::Diff { old, new, table, pages, output, json } => {
+            let old_db = Database::open(&old)?;
+            let new_db = Database::open(&new)?;
+
+            if pages {
+                let report = old_db.diff_pages(&new_db)?;
+                let output_path = output.unwrap_or_else(|| {
+                    let mut path = new.clone();
+                    path.set_extension("diff.html");
+                    path
+                });
+                generate_heatmap_html(&report, &output_path)?;
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                } else {
+                    print_page_diff(&report);
+                    println!();
+                    println!("Heatmap generated: {}", output_path.display());
+                }
+                return Ok(());
+            }
+
+            let diff = old_db.diff_rows(&new_db, table.as_deref())?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&diff)?);
+            } else {
+                print_database_diff(&old_db, &new_db, &diff);
+            }
+        }
+
+        Commands::Dump { database, output, tree, page, no_hex, wal } => {
             // Read file to detect type
             let file_data = std::fs::read(&database)?;
 
@@ -116,7 +557,7 @@ fn main() -> Result<()> {
 
             match dump::detect_file_type(&file_data) {
                 dump::FileType::SqliteDb => {
-                    let db = Database::open(&database)?;
+                    let db = open_database(&database, &wal)?;
                     dump::dump_to_file(&db, &output_path, &options)?;
                 }
                 dump::FileType::WalFile => {
@@ -124,19 +565,36 @@ fn main() -> Result<()> {
                     if tree.is_some() {
                         eprintln!("Warning: --tree option is ignored for WAL files");
                     }
+                    if wal.wal.is_some() {
+                        eprintln!("Warning: --wal option is ignored for WAL files");
+                    }
 
-                    let file_name = database
-                        .file_name()
-                        .and_then(|n| n.to_str())
-                        .unwrap_or("wal")
-                        .to_string();
-
+                    let file_name = file_name_of(&database, "wal");
                     let wal = parser::parse_wal_file(&file_data, file_name)?;
-                    dump::dump_wal_to_file(&wal, &output_path, &options)?;
+
+                    // Include the sibling -shm file, if there is one
+                    let wal_index = sibling_wal_index(&database);
+                    dump::dump_wal_to_file(&wal, wal_index.as_ref(), &output_path, &options)?;
+                }
+                dump::FileType::WalIndex => {
+                    if tree.is_some() {
+                        eprintln!("Warning: --tree option is ignored for WAL-index files");
+                    }
+
+                    let index = parser::parse_wal_index(&file_data, file_name_of(&database, "shm"))?;
+                    dump::dump_wal_index_to_file(&index, &output_path, &options)?;
+                }
+                dump::FileType::RollbackJournal => {
+                    if tree.is_some() {
+                        eprintln!("Warning: --tree option is ignored for rollback journals");
+                    }
+
+                    let journal = parser::parse_journal(&file_data, file_name_of(&database, "journal"))?;
+                    dump::dump_journal_to_file(&journal, &output_path, &options)?;
                 }
                 dump::FileType::Unknown => {
                     anyhow::bail!(
-                        "Unrecognized file format. Expected SQLite database or WAL file."
+                        "Unrecognized file format. Expected SQLite database, WAL, WAL-index or rollback journal file."
                     );
                 }
             }
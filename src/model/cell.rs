@@ -44,6 +44,36 @@ impl Cell {
         }
     }
 
+    /// Number of payload bytes stored locally in the cell (None for table interior cells)
+    pub fn local_payload_size(&self) -> Option<usize> {
+        match self {
+            Cell::TableLeaf(c) => Some(c.local_payload_size),
+            Cell::IndexLeaf(c) => Some(c.local_payload_size),
+            Cell::IndexInterior(c) => Some(c.local_payload_size),
+            Cell::TableInterior(_) => None,
+        }
+    }
+
+    /// Get the parsed payload record, if any
+    pub fn payload(&self) -> Option<&Record> {
+        match self {
+            Cell::TableLeaf(c) => c.payload.as_ref(),
+            Cell::IndexLeaf(c) => c.payload.as_ref(),
+            Cell::IndexInterior(c) => c.payload.as_ref(),
+            Cell::TableInterior(_) => None,
+        }
+    }
+
+    /// Replace the parsed payload record (e.g. after reassembling overflow pages)
+    pub fn set_payload(&mut self, record: Option<Record>) {
+        match self {
+            Cell::TableLeaf(c) => c.payload = record,
+            Cell::IndexLeaf(c) => c.payload = record,
+            Cell::IndexInterior(c) => c.payload = record,
+            Cell::TableInterior(_) => {}
+        }
+    }
+
     /// Get the byte offset where this cell starts in the page
     pub fn cell_offset(&self) -> u16 {
         match self {
@@ -203,7 +233,7 @@ impl SerialType {
 }
 
 /// A parsed column value
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 #[serde(untagged)]
 pub enum Value {
     Null,
@@ -211,6 +241,8 @@ pub enum Value {
     Real(f64),
     Text(String),
     Blob(Vec<u8>),
+    /// Text that is not valid in the database encoding (invalid sequences replaced with U+FFFD)
+    MalformedText(String),
 }
 
 impl Value {
@@ -220,13 +252,8 @@ impl Value {
             Value::Null => "NULL".to_string(),
             Value::Integer(i) => i.to_string(),
             Value::Real(f) => format!("{:.6}", f),
-            Value::Text(s) => {
-                if s.len() <= max_len {
-                    format!("\"{}\"", s)
-                } else {
-                    format!("\"{}...\"", &s[..max_len])
-                }
-            }
+            Value::Text(s) => preview_text(s, max_len),
+            Value::MalformedText(s) => format!("{} [malformed text]", preview_text(s, max_len)),
             Value::Blob(b) => {
                 if b.len() <= max_len / 2 {
                     format!("x'{}'", hex_encode(b))
@@ -238,6 +265,22 @@ impl Value {
     }
 }
 
+fn preview_text(s: &str, max_len: usize) -> String {
+    if s.chars().count() <= max_len {
+        format!("\"{}\"", s)
+    } else {
+        format!("\"{}...\"", truncate_chars(s, max_len))
+    }
+}
+
+/// Truncate a string to at most `max_chars` characters
+pub fn truncate_chars(s: &str, max_chars: usize) -> &str {
+    match s.char_indices().nth(max_chars) {
+        Some((idx, _)) => &s[..idx],
+        None => s,
+    }
+}
+
 fn hex_encode(bytes: &[u8]) -> String {
     bytes.iter().map(|b| format!("{:02x}", b)).collect()
 }
@@ -1,5 +1,8 @@
 use serde::Serialize;
 
+/// File offset of the lock byte range; the page containing it is never used
+pub const PENDING_BYTE: u64 = 0x4000_0000;
+
 /// Text encoding used in the database
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub enum TextEncoding {
@@ -72,6 +75,16 @@ impl DatabaseHeader {
         self.page_size - self.reserved_bytes_per_page as u32
     }
 
+    /// Whether the database uses auto-vacuum (and therefore has pointer-map pages)
+    pub fn is_auto_vacuum(&self) -> bool {
+        self.largest_root_page != 0
+    }
+
+    /// Page number of the lock-byte page (the page containing byte offset 1073741824)
+    pub fn lock_byte_page(&self) -> u32 {
+        (PENDING_BYTE / self.page_size as u64) as u32 + 1
+    }
+
     /// Format SQLite version as string (e.g., "3.39.0")
     pub fn sqlite_version_string(&self) -> String {
         let major = self.sqlite_version / 1_000_000;
@@ -0,0 +1,211 @@
+//! Differences between two database files.
+
+use serde::Serialize;
+
+use super::{PageType, Value};
+
+/// A column whose value differs between the two versions of a row
+#[derive(Debug, Clone, Serialize)]
+pub struct ColumnChange {
+    /// Column index in the record
+    pub column: usize,
+    /// Old value (None if the old row had fewer columns)
+    pub old: Option<Value>,
+    /// New value (None if the new row has fewer columns)
+    pub new: Option<Value>,
+}
+
+/// A row that differs between the two databases
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum RowChange {
+    /// Row only in the new database
+    Inserted { rowid: i64, values: Vec<Value> },
+    /// Row only in the old database
+    Deleted { rowid: i64, values: Vec<Value> },
+    /// Row in both, with different column values
+    Changed { rowid: i64, columns: Vec<ColumnChange> },
+}
+
+impl RowChange {
+    pub fn rowid(&self) -> i64 {
+        match self {
+            RowChange::Inserted { rowid, .. } | RowChange::Deleted { rowid, .. } | RowChange::Changed { rowid, .. } => {
+                *rowid
+            }
+        }
+    }
+}
+
+/// Row differences of one table
+#[derive(Debug, Clone, Serialize)]
+pub struct TableDiff {
+    /// Table name
+    pub name: String,
+    /// Root page in the old database (None if the table is new)
+    pub old_root: Option<u32>,
+    /// Root page in the new database (None if the table was dropped)
+    pub new_root: Option<u32>,
+    /// Why the table's rows were not compared, if they were not
+    pub skipped: Option<String>,
+    /// Changed rows, in rowid order
+    pub changes: Vec<RowChange>,
+    /// Pages and records that could not be read in either database. Their rows are missing
+    /// from that side of the comparison, so they may be listed as inserted or deleted.
+    pub errors: Vec<String>,
+}
+
+impl TableDiff {
+    pub fn inserted(&self) -> usize {
+        self.changes.iter().filter(|c| matches!(c, RowChange::Inserted { .. })).count()
+    }
+
+    pub fn deleted(&self) -> usize {
+        self.changes.iter().filter(|c| matches!(c, RowChange::Deleted { .. })).count()
+    }
+
+    pub fn changed(&self) -> usize {
+        self.changes.iter().filter(|c| matches!(c, RowChange::Changed { .. })).count()
+    }
+
+    /// True if the table is in both databases and was compared without finding differences
+    pub fn is_unchanged(&self) -> bool {
+        self.changes.is_empty()
+            && self.errors.is_empty()
+            && self.skipped.is_none()
+            && self.old_root.is_some()
+            && self.new_root.is_some()
+    }
+}
+
+/// Row differences of every table in two databases
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DatabaseDiff {
+    /// One entry per table in either database, in the order tables are found
+    pub tables: Vec<TableDiff>,
+}
+
+impl DatabaseDiff {
+    /// True if no table has any differences
+    pub fn is_empty(&self) -> bool {
+        self.tables
+            .iter()
+            .all(|t| t.is_unchanged())
+    }
+}
+
+/// How a cell differs between two versions of a page
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CellChangeKind {
+    /// Cell only on the new page
+    Added,
+    /// Cell only on the old page
+    Removed,
+    /// Same bytes, different offset
+    Moved,
+    /// Same rowid, different bytes
+    Rewritten,
+}
+
+/// A cell that differs between two versions of a page
+#[derive(Debug, Clone, Serialize)]
+pub struct CellChange {
+    pub kind: CellChangeKind,
+    /// Rowid of the cell (table pages only)
+    pub rowid: Option<i64>,
+    /// Index in the old cell pointer array
+    pub old_index: Option<usize>,
+    /// Index in the new cell pointer array
+    pub new_index: Option<usize>,
+    /// Offset of the cell on the old page
+    pub old_offset: Option<u16>,
+    /// Offset of the cell on the new page
+    pub new_offset: Option<u16>,
+    /// Size of the cell on the new page, or the old page if removed
+    pub size: usize,
+}
+
+/// A page whose bytes differ between two databases
+#[derive(Debug, Clone, Serialize)]
+pub struct PageDiff {
+    pub page_number: u32,
+    /// Page type in the old database (None if the page is new)
+    pub old_type: Option<PageType>,
+    /// Page type in the new database (None if the file shrank)
+    pub new_type: Option<PageType>,
+    /// Structure owning the page (new database first, then old)
+    pub owner: String,
+    /// B-tree the page belongs to, if any
+    pub tree: Option<String>,
+    /// Number of bytes that differ (all bytes for pages in only one file)
+    pub bytes_changed: usize,
+    /// Whether the page header or cell pointer array changed (B-tree pages)
+    pub header_changed: bool,
+    /// Cell-level changes, when both versions are B-tree pages of the same type
+    pub cell_changes: Vec<CellChange>,
+}
+
+impl PageDiff {
+    /// Number of cell changes of one kind
+    pub fn count(&self, kind: CellChangeKind) -> usize {
+        self.cell_changes.iter().filter(|c| c.kind == kind).count()
+    }
+}
+
+/// Pages changed by one owner (B-tree or other structure)
+#[derive(Debug, Clone, Serialize)]
+pub struct PageDiffSummary {
+    pub owner: String,
+    pub pages_changed: usize,
+    pub bytes_changed: usize,
+    pub cells_added: usize,
+    pub cells_removed: usize,
+    pub cells_moved: usize,
+    pub cells_rewritten: usize,
+}
+
+/// Page-by-page differences between two database files
+#[derive(Debug, Clone, Serialize)]
+pub struct PageDiffReport {
+    pub old_file: String,
+    pub new_file: String,
+    pub page_size: u32,
+    pub old_page_count: u32,
+    pub new_page_count: u32,
+    /// Changed pages, in page order
+    pub pages: Vec<PageDiff>,
+}
+
+impl PageDiffReport {
+    /// Totals per owner, most pages changed first
+    pub fn summary(&self) -> Vec<PageDiffSummary> {
+        let mut summary: Vec<PageDiffSummary> = Vec::new();
+        for page in &self.pages {
+            let owner = page.tree.clone().unwrap_or_else(|| page.owner.clone());
+            let entry = match summary.iter().position(|s| s.owner == owner) {
+                Some(i) => &mut summary[i],
+                None => {
+                    summary.push(PageDiffSummary {
+                        owner,
+                        pages_changed: 0,
+                        bytes_changed: 0,
+                        cells_added: 0,
+                        cells_removed: 0,
+                        cells_moved: 0,
+                        cells_rewritten: 0,
+                    });
+                    summary.last_mut().unwrap()
+                }
+            };
+            entry.pages_changed += 1;
+            entry.bytes_changed += page.bytes_changed;
+            entry.cells_added += page.count(CellChangeKind::Added);
+            entry.cells_removed += page.count(CellChangeKind::Removed);
+            entry.cells_moved += page.count(CellChangeKind::Moved);
+            entry.cells_rewritten += page.count(CellChangeKind::Rewritten);
+        }
+        summary.sort_by(|a, b| b.pages_changed.cmp(&a.pages_changed).then(b.bytes_changed.cmp(&a.bytes_changed)));
+        summary
+    }
+}
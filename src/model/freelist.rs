@@ -0,0 +1,42 @@
+use serde::Serialize;
+use crate::model::PageType;
+
+/// A freelist trunk page and the leaf pages it lists
+#[derive(Debug, Clone, Serialize)]
+pub struct FreelistTrunkPage {
+    /// Page number of this trunk page
+    pub page_number: u32,
+    /// Next trunk page in the chain (None = last trunk)
+    pub next_trunk: Option<u32>,
+    /// Freelist leaf pages listed on this trunk
+    pub leaf_pages: Vec<u32>,
+}
+
+/// The complete freelist, in trunk chain order
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Freelist {
+    /// First trunk page (from the database header, 0 = empty freelist)
+    pub first_trunk: u32,
+    /// Trunk pages in chain order
+    pub trunks: Vec<FreelistTrunkPage>,
+}
+
+impl Freelist {
+    /// Total number of pages on the freelist (trunks + leaves)
+    pub fn total_pages(&self) -> usize {
+        self.trunks.iter().map(|t| 1 + t.leaf_pages.len()).sum()
+    }
+
+    /// Iterate over every leaf page on the freelist
+    pub fn leaf_pages(&self) -> impl Iterator<Item = u32> + '_ {
+        self.trunks.iter().flat_map(|t| t.leaf_pages.iter().copied())
+    }
+
+    /// Iterate over every freelist page with its page type
+    pub fn pages(&self) -> impl Iterator<Item = (u32, PageType)> + '_ {
+        self.trunks.iter().flat_map(|t| {
+            std::iter::once((t.page_number, PageType::FreelistTrunk))
+                .chain(t.leaf_pages.iter().map(|&p| (p, PageType::FreelistLeaf)))
+        })
+    }
+}
@@ -0,0 +1,96 @@
+//! Rollback journal (-journal) data structures.
+
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use super::Page;
+
+/// Rollback journal header magic
+pub const JOURNAL_MAGIC: [u8; 8] = [0xd9, 0xd5, 0x05, 0xf9, 0x20, 0xa1, 0x63, 0xd7];
+
+/// Journal segment header (padded to the sector size)
+#[derive(Debug, Clone, Serialize)]
+pub struct JournalHeader {
+    /// Byte offset of the header in the journal file
+    pub offset: usize,
+    /// Number of page records in the segment, as stored (0xffffffff = up to end of file)
+    pub record_count: u32,
+    /// Random value that seeds every record checksum
+    pub nonce: u32,
+    /// Database size in pages before the transaction began
+    pub initial_page_count: u32,
+    /// Disk sector size; headers and segments are aligned to it
+    pub sector_size: u32,
+    /// Database page size
+    pub page_size: u32,
+}
+
+/// Original content of one database page, saved before it was modified
+#[derive(Debug, Clone)]
+pub struct JournalRecord {
+    /// Byte offset of the record in the journal file
+    pub offset: usize,
+    /// Database page the content belongs to
+    pub page_number: u32,
+    /// Parsed page content (None if page could not be parsed)
+    pub page: Option<Page>,
+    /// Raw page data
+    pub raw_page_data: Vec<u8>,
+    /// Stored checksum
+    pub checksum: u32,
+    /// Whether the stored checksum matches the computed one
+    pub checksum_valid: bool,
+}
+
+/// A journal header and the records that follow it
+#[derive(Debug, Clone)]
+pub struct JournalSegment {
+    pub header: JournalHeader,
+    pub records: Vec<JournalRecord>,
+}
+
+/// Parsed rollback journal
+#[derive(Debug, Clone)]
+pub struct RollbackJournal {
+    /// Source file name
+    pub file_name: String,
+    /// Segments in file order. A new segment starts each time the journal is synced.
+    pub segments: Vec<JournalSegment>,
+}
+
+impl RollbackJournal {
+    /// Whether the journal would be rolled back if found next to its database
+    /// with no lock held: it needs a valid header describing at least one record.
+    pub fn is_hot(&self) -> bool {
+        self.segments.first().is_some_and(|s| s.header.record_count > 0)
+    }
+
+    /// All records in playback order
+    pub fn records(&self) -> impl Iterator<Item = &JournalRecord> {
+        self.segments.iter().flat_map(|s| s.records.iter())
+    }
+
+    /// Page numbers a rollback would write back to the database, in playback order.
+    /// Playback stops at the first bad checksum, and pages past the original
+    /// database size are dropped by truncation instead of restored.
+    pub fn restored_pages(&self) -> Vec<u32> {
+        let initial_page_count = match self.segments.first() {
+            Some(segment) => segment.header.initial_page_count,
+            None => return Vec::new(),
+        };
+
+        let mut seen = HashSet::new();
+        let mut pages = Vec::new();
+        for record in self.records() {
+            if !record.checksum_valid {
+                break;
+            }
+            if record.page_number > initial_page_count || !seen.insert(record.page_number) {
+                continue;
+            }
+            pages.push(record.page_number);
+        }
+        pages
+    }
+}
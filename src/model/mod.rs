@@ -4,6 +4,14 @@ pub mod cell;
 pub mod schema;
 pub mod btree;
 pub mod wal;
+pub mod shm;
+pub mod journal;
+pub mod page_map;
+pub mod freelist;
+pub mod ptrmap;
+pub mod row;
+pub mod diff;
+pub mod stats;
 
 pub use database::*;
 pub use page::*;
@@ -11,3 +19,11 @@ pub use cell::*;
 pub use schema::*;
 pub use btree::*;
 pub use wal::*;
+pub use shm::*;
+pub use journal::*;
+pub use page_map::*;
+pub use freelist::*;
+pub use ptrmap::*;
+pub use row::*;
+pub use diff::*;
+pub use stats::*;
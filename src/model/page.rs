@@ -51,6 +51,10 @@ impl PageType {
         matches!(self, PageType::InteriorIndex | PageType::LeafIndex)
     }
 
+    pub fn is_btree(&self) -> bool {
+        self.is_table() || self.is_index()
+    }
+
     pub fn header_size(&self) -> usize {
         if self.is_interior() { 12 } else { 8 }
     }
@@ -86,13 +90,57 @@ pub struct Page {
     pub cell_pointers: Vec<u16>,
     /// Parsed cells
     pub cells: Vec<Cell>,
-    /// Calculated free space
+    /// Calculated free space: unallocated gap, freeblocks and fragmented bytes
     pub free_space: usize,
+    /// Freeblock chain, in chain order
+    pub freeblocks: Vec<Freeblock>,
+    /// Where the free space is (B-tree pages only)
+    pub free_space_breakdown: Option<FreeSpace>,
     /// Raw page data
     #[serde(skip)]
     pub raw_data: Vec<u8>,
 }
 
+/// A freeblock: a run of unused bytes inside the cell content area,
+/// linked into a chain starting at the page header's first freeblock offset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Freeblock {
+    /// Offset of the freeblock within the page
+    pub offset: u16,
+    /// Size of the freeblock in bytes, including its 4-byte header
+    pub size: u16,
+    /// Offset of the next freeblock (None at the end of the chain)
+    pub next: Option<u16>,
+}
+
+/// Free space of a B-tree page, split by kind
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FreeSpace {
+    /// Gap between the end of the cell pointer array and the start of the cell content area
+    pub unallocated: usize,
+    /// Bytes in freeblocks
+    pub freeblocks: usize,
+    /// Fragmented bytes (free runs too small for a freeblock), as counted in the page header
+    pub fragmented: usize,
+    /// Bytes of the cell content area covered by neither cells nor free space.
+    /// Anything but zero means the header, freeblock chain and cells disagree.
+    pub unaccounted: i64,
+    /// Why the freeblock chain could not be followed, if it could not
+    pub freeblock_error: Option<String>,
+}
+
+impl FreeSpace {
+    /// Total free bytes
+    pub fn total(&self) -> usize {
+        self.unallocated + self.freeblocks + self.fragmented
+    }
+
+    /// True if the pieces add up to the space not used by cells
+    pub fn is_consistent(&self) -> bool {
+        self.unaccounted == 0 && self.freeblock_error.is_none()
+    }
+}
+
 /// Overflow page
 #[derive(Debug, Clone, Serialize)]
 pub struct OverflowPage {
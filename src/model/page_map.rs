@@ -0,0 +1,114 @@
+//! Whole-file page ownership.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::Serialize;
+
+use super::PageType;
+
+/// What a page is used for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum PageOwner {
+    /// Interior or leaf page of a named B-tree
+    BTree { tree: String, page_type: PageType },
+    /// Overflow page holding part of a cell's payload
+    Overflow { tree: String, page: u32, cell_index: usize },
+    /// Page a B-tree refers to that could not be parsed as a B-tree page
+    Unreadable { tree: String },
+    /// Freelist trunk page
+    FreelistTrunk,
+    /// Freelist leaf page
+    FreelistLeaf,
+    /// Pointer-map page
+    PointerMap,
+    /// Lock-byte page
+    LockByte,
+}
+
+impl PageOwner {
+    /// Page type implied by the owner (None for unreadable pages)
+    pub fn page_type(&self) -> Option<PageType> {
+        match self {
+            PageOwner::BTree { page_type, .. } => Some(*page_type),
+            PageOwner::Overflow { .. } => Some(PageType::Overflow),
+            PageOwner::Unreadable { .. } => None,
+            PageOwner::FreelistTrunk => Some(PageType::FreelistTrunk),
+            PageOwner::FreelistLeaf => Some(PageType::FreelistLeaf),
+            PageOwner::PointerMap => Some(PageType::PointerMap),
+            PageOwner::LockByte => Some(PageType::LockByte),
+        }
+    }
+
+    /// Name of the B-tree the page belongs to, for B-tree and overflow pages
+    pub fn tree(&self) -> Option<&str> {
+        match self {
+            PageOwner::BTree { tree, .. } | PageOwner::Overflow { tree, .. } | PageOwner::Unreadable { tree } => {
+                Some(tree)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for PageOwner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PageOwner::BTree { tree, page_type } => {
+                let kind = if page_type.is_interior() { "interior" } else { "leaf" };
+                write!(f, "{} ({})", tree, kind)
+            }
+            PageOwner::Overflow { tree, page, cell_index } => {
+                write!(f, "{} overflow (page {} cell {})", tree, page, cell_index)
+            }
+            PageOwner::Unreadable { tree } => write!(f, "{} (unreadable)", tree),
+            PageOwner::FreelistTrunk => write!(f, "freelist trunk"),
+            PageOwner::FreelistLeaf => write!(f, "freelist leaf"),
+            PageOwner::PointerMap => write!(f, "pointer map"),
+            PageOwner::LockByte => write!(f, "lock byte"),
+        }
+    }
+}
+
+/// Every claim made on every page of the file
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PageMap {
+    /// Number of pages in the database
+    pub page_count: u32,
+    /// Owners claiming each page, in the order the claims were found
+    pub claims: BTreeMap<u32, Vec<PageOwner>>,
+}
+
+impl PageMap {
+    pub fn new(page_count: u32) -> Self {
+        Self { page_count, claims: BTreeMap::new() }
+    }
+
+    /// Record that `owner` uses `page_number`
+    pub fn claim(&mut self, page_number: u32, owner: PageOwner) {
+        self.claims.entry(page_number).or_default().push(owner);
+    }
+
+    /// Whether any owner has claimed the page
+    pub fn is_claimed(&self, page_number: u32) -> bool {
+        self.claims.contains_key(&page_number)
+    }
+
+    /// The first owner to claim a page
+    pub fn owner(&self, page_number: u32) -> Option<&PageOwner> {
+        self.claims.get(&page_number).and_then(|owners| owners.first())
+    }
+
+    /// Pages claimed by more than one owner
+    pub fn duplicates(&self) -> impl Iterator<Item = (u32, &[PageOwner])> {
+        self.claims
+            .iter()
+            .filter(|(_, owners)| owners.len() > 1)
+            .map(|(&page, owners)| (page, owners.as_slice()))
+    }
+
+    /// Pages in the file that nothing claims (leaked pages)
+    pub fn orphans(&self) -> Vec<u32> {
+        (1..=self.page_count).filter(|p| !self.claims.contains_key(p)).collect()
+    }
+}
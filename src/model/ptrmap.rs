@@ -0,0 +1,51 @@
+use serde::Serialize;
+
+/// Type of a pointer-map entry, describing how the page is used
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum PtrmapEntryType {
+    /// Root page of a B-tree (parent is 0)
+    RootPage,
+    /// Page on the freelist (parent is 0)
+    FreePage,
+    /// First page of an overflow chain (parent is the B-tree page holding the cell)
+    Overflow1,
+    /// Later page of an overflow chain (parent is the previous overflow page)
+    Overflow2,
+    /// Non-root B-tree page (parent is the parent B-tree page)
+    BTree,
+    /// Unrecognized type byte
+    Unknown(u8),
+}
+
+impl PtrmapEntryType {
+    pub fn from_byte(byte: u8) -> Self {
+        match byte {
+            1 => PtrmapEntryType::RootPage,
+            2 => PtrmapEntryType::FreePage,
+            3 => PtrmapEntryType::Overflow1,
+            4 => PtrmapEntryType::Overflow2,
+            5 => PtrmapEntryType::BTree,
+            n => PtrmapEntryType::Unknown(n),
+        }
+    }
+}
+
+/// A single 5-byte pointer-map entry
+#[derive(Debug, Clone, Serialize)]
+pub struct PtrmapEntry {
+    /// Page this entry describes
+    pub page_number: u32,
+    /// How the page is used
+    pub entry_type: PtrmapEntryType,
+    /// Parent page number (0 for root and free pages)
+    pub parent_page: u32,
+}
+
+/// A pointer-map page (auto-vacuum databases only)
+#[derive(Debug, Clone, Serialize)]
+pub struct PtrmapPage {
+    /// Page number of the pointer-map page itself
+    pub page_number: u32,
+    /// Entries for the pages following this pointer-map page
+    pub entries: Vec<PtrmapEntry>,
+}
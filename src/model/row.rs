@@ -0,0 +1,131 @@
+//! Rows read from table B-trees.
+
+use serde::Serialize;
+
+use super::{Affinity, Record, Value};
+
+/// A row of a table B-tree
+#[derive(Debug, Clone, Serialize)]
+pub struct TableRow {
+    /// Rowid (the table B-tree key)
+    pub rowid: i64,
+    /// Page the row's cell is on
+    pub page_number: u32,
+    /// Decoded record
+    pub record: Record,
+}
+
+/// Where a value of a row comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValueSource {
+    /// Read from the record
+    Stored,
+    /// The rowid, for an INTEGER PRIMARY KEY column; the record stores NULL
+    RowidAlias,
+    /// The column's DEFAULT, for a record written before ALTER TABLE ADD COLUMN added it
+    Default,
+}
+
+impl ValueSource {
+    /// Description for display
+    pub fn label(&self) -> &'static str {
+        match self {
+            ValueSource::Stored => "stored",
+            ValueSource::RowidAlias => "rowid alias",
+            ValueSource::Default => "default",
+        }
+    }
+}
+
+/// A column value as SQLite reads it, with where it came from
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RowValue {
+    pub value: Value,
+    pub source: ValueSource,
+}
+
+/// How the records of a B-tree read as rows
+#[derive(Debug, Clone, Default)]
+pub struct RowLayout {
+    /// Column names in record order
+    pub names: Vec<String>,
+    /// Record position of the INTEGER PRIMARY KEY column, which reads as the rowid
+    pub rowid_alias: Option<usize>,
+    /// Value of each column in records that predate it, in record order; empty for indexes
+    pub defaults: Vec<Value>,
+    /// Affinity of each column, in record order; empty for indexes
+    pub affinities: Vec<Affinity>,
+}
+
+impl RowLayout {
+    /// Layout that only names the record values
+    pub fn with_names(names: Vec<String>) -> Self {
+        Self { names, ..Self::default() }
+    }
+
+    /// The values of a row: the record's values with the rowid in place of its alias and
+    /// defaults for missing trailing columns. REAL columns read back as reals, since SQLite
+    /// stores whole numbers in them as integers.
+    pub fn materialize(&self, rowid: Option<i64>, values: &[Value]) -> Vec<RowValue> {
+        (0..values.len().max(self.defaults.len()))
+            .map(|i| match (values.get(i), rowid) {
+                (_, Some(rowid)) if self.rowid_alias == Some(i) => {
+                    RowValue { value: Value::Integer(rowid), source: ValueSource::RowidAlias }
+                }
+                (Some(Value::Integer(n)), _) if self.affinities.get(i) == Some(&Affinity::Real) => {
+                    RowValue { value: Value::Real(*n as f64), source: ValueSource::Stored }
+                }
+                (Some(value), _) => RowValue { value: value.clone(), source: ValueSource::Stored },
+                (None, _) => RowValue { value: self.defaults[i].clone(), source: ValueSource::Default },
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout() -> RowLayout {
+        RowLayout {
+            names: vec!["id".to_string(), "price".to_string(), "added".to_string()],
+            rowid_alias: Some(0),
+            defaults: vec![Value::Null, Value::Null, Value::Text("new".to_string())],
+            affinities: vec![Affinity::Integer, Affinity::Real, Affinity::Text],
+        }
+    }
+
+    #[test]
+    fn test_materialize_reads_as_sqlite_does() {
+        // Written before `added` existed: the alias holds NULL and the record is a column short
+        let row = layout().materialize(Some(7), &[Value::Null, Value::Integer(3)]);
+        assert_eq!(
+            row,
+            [
+                RowValue { value: Value::Integer(7), source: ValueSource::RowidAlias },
+                RowValue { value: Value::Real(3.0), source: ValueSource::Stored },
+                RowValue { value: Value::Text("new".to_string()), source: ValueSource::Default },
+            ]
+        );
+
+        // Without a rowid, as for a WITHOUT ROWID table, the stored NULL stays
+        let row = layout().materialize(None, &[Value::Null, Value::Real(1.5), Value::Null]);
+        assert_eq!(row[0], RowValue { value: Value::Null, source: ValueSource::Stored });
+        assert_eq!(row[1].value, Value::Real(1.5));
+        assert_eq!(row[2], RowValue { value: Value::Null, source: ValueSource::Stored });
+    }
+
+    #[test]
+    fn test_materialize_keeps_extra_values() {
+        // A damaged record can have more values than the table has columns
+        let values = [Value::Null, Value::Integer(1), Value::Null, Value::Integer(9)];
+        let row = layout().materialize(Some(1), &values);
+        assert_eq!(row.len(), 4);
+        assert_eq!(row[3], RowValue { value: Value::Integer(9), source: ValueSource::Stored });
+
+        let row = RowLayout::with_names(Vec::new()).materialize(Some(1), &values);
+        assert!(row.iter().all(|v| v.source == ValueSource::Stored));
+        assert_eq!(row[1].value, Value::Integer(1));
+    }
+}
@@ -1,5 +1,7 @@
 use serde::Serialize;
 
+use super::Value;
+
 /// Type of object in the schema
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub enum ObjectType {
@@ -10,6 +12,7 @@ pub enum ObjectType {
 }
 
 impl ObjectType {
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
             "table" => Some(ObjectType::Table),
@@ -73,3 +76,155 @@ impl Schema {
         self.indexes().filter(move |e| e.table_name == table_name)
     }
 }
+
+/// Type affinity of a column, from its declared type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Affinity {
+    Integer,
+    Text,
+    Blob,
+    Real,
+    Numeric,
+}
+
+impl Affinity {
+    /// Affinity of a declared type, by SQLite's substring rules
+    pub fn from_declared_type(declared_type: Option<&str>) -> Self {
+        let Some(declared) = declared_type.map(str::to_ascii_uppercase) else {
+            return Affinity::Blob;
+        };
+        if declared.contains("INT") {
+            Affinity::Integer
+        } else if ["CHAR", "CLOB", "TEXT"].iter().any(|s| declared.contains(s)) {
+            Affinity::Text
+        } else if declared.contains("BLOB") {
+            Affinity::Blob
+        } else if ["REAL", "FLOA", "DOUB"].iter().any(|s| declared.contains(s)) {
+            Affinity::Real
+        } else {
+            Affinity::Numeric
+        }
+    }
+
+    /// Convert a value as SQLite does when storing it in a column of this affinity
+    pub fn apply(&self, value: Value) -> Value {
+        match (self, value) {
+            (Affinity::Text, Value::Integer(i)) => Value::Text(i.to_string()),
+            (Affinity::Text, Value::Real(f)) => Value::Text(format!("{:?}", f)),
+            (Affinity::Integer | Affinity::Numeric, Value::Real(f)) => whole_number(f),
+            (Affinity::Integer | Affinity::Numeric, Value::Text(s)) => match parse_number(&s) {
+                Some(Value::Real(f)) => whole_number(f),
+                Some(number) => number,
+                None => Value::Text(s),
+            },
+            (Affinity::Real, Value::Integer(i)) => Value::Real(i as f64),
+            (Affinity::Real, Value::Text(s)) => match parse_number(&s) {
+                Some(Value::Integer(i)) => Value::Real(i as f64),
+                Some(number) => number,
+                None => Value::Text(s),
+            },
+            (_, value) => value,
+        }
+    }
+}
+
+/// A real as an integer if it has no fractional part and fits
+fn whole_number(f: f64) -> Value {
+    if f.fract() == 0.0 && (-9_223_372_036_854_775_808.0..9_223_372_036_854_775_808.0).contains(&f) {
+        Value::Integer(f as i64)
+    } else {
+        Value::Real(f)
+    }
+}
+
+/// Text that is a well-formed decimal number, surrounding spaces allowed
+fn parse_number(text: &str) -> Option<Value> {
+    let text = text.trim();
+    let digits = text.strip_prefix(['+', '-']).unwrap_or(text);
+    if !digits.starts_with(|c: char| c.is_ascii_digit() || c == '.')
+        || !digits.chars().all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-'))
+    {
+        return None;
+    }
+    match text.parse::<i64>() {
+        Ok(i) => Some(Value::Integer(i)),
+        Err(_) => text.parse::<f64>().ok().map(Value::Real),
+    }
+}
+
+/// How a generated column (`GENERATED ALWAYS AS (...)`) keeps its value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Generated {
+    /// Computed when read; not stored in the record (the default)
+    Virtual,
+    /// Computed when written and stored in the record like any other column
+    Stored,
+}
+
+/// A column of a CREATE TABLE statement
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ColumnDefinition {
+    pub name: String,
+    /// Declared type as written, e.g. `VARCHAR(20)`
+    pub declared_type: Option<String>,
+    pub affinity: Affinity,
+    /// Whether the column has a PRIMARY KEY constraint or is part of the table's PRIMARY KEY
+    pub primary_key: bool,
+    /// Collating sequence from a COLLATE clause
+    pub collation: Option<String>,
+    /// Expression of a DEFAULT clause as written
+    pub default: Option<String>,
+    /// Whether the column is generated, and if so how
+    pub generated: Option<Generated>,
+}
+
+impl ColumnDefinition {
+    /// Whether the column has a value in the table's records; VIRTUAL generated columns do not
+    pub fn is_stored(&self) -> bool {
+        self.generated != Some(Generated::Virtual)
+    }
+}
+
+/// A key column of an index, PRIMARY KEY or UNIQUE constraint
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IndexColumn {
+    /// Column name, or the SQL text of an expression
+    pub name: String,
+    pub expression: bool,
+    /// Collating sequence from a COLLATE clause
+    pub collation: Option<String>,
+    pub descending: bool,
+}
+
+/// A parsed CREATE TABLE statement
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TableDefinition {
+    pub name: String,
+    pub columns: Vec<ColumnDefinition>,
+    /// PRIMARY KEY columns, from a column or table constraint
+    pub primary_key: Vec<IndexColumn>,
+    /// Column that is an alias for the rowid (INTEGER PRIMARY KEY)
+    pub rowid_alias: Option<usize>,
+    /// Keys of the automatic indexes for PRIMARY KEY and UNIQUE constraints, in the order
+    /// they are numbered: `sqlite_autoindex_<table>_1` is the first
+    pub autoindexes: Vec<Vec<IndexColumn>>,
+    pub without_rowid: bool,
+}
+
+impl TableDefinition {
+    /// Get a column by name, ignoring case
+    pub fn column(&self, name: &str) -> Option<&ColumnDefinition> {
+        self.columns.iter().find(|c| c.name.eq_ignore_ascii_case(name))
+    }
+}
+
+/// A parsed CREATE INDEX statement
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IndexDefinition {
+    pub name: String,
+    pub table: String,
+    pub unique: bool,
+    pub columns: Vec<IndexColumn>,
+    /// Whether the index has a WHERE clause
+    pub partial: bool,
+}
@@ -0,0 +1,102 @@
+//! WAL-index (-shm) data structures.
+
+use serde::Serialize;
+
+/// Version number stored in the WAL-index header
+pub const WAL_INDEX_VERSION: u32 = 3007000;
+/// Read-mark value meaning the slot is unused
+pub const READ_MARK_NOT_USED: u32 = 0xffff_ffff;
+
+/// One copy of the WAL-index header (48 bytes).
+/// SQLite writes two copies and only trusts the header when they are identical.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WalIndexHeader {
+    /// WAL-index format version (3007000)
+    pub version: u32,
+    /// Counter incremented on every transaction
+    pub change_counter: u32,
+    /// Whether the header has been initialized
+    pub is_initialized: bool,
+    /// Whether the WAL uses big-endian checksums
+    pub big_endian_checksum: bool,
+    /// Database page size
+    pub page_size: u32,
+    /// Index of the last valid frame in the WAL (1-based, 0 if empty)
+    pub max_frame: u32,
+    /// Database size in pages
+    pub page_count: u32,
+    /// Checksum of the last frame in the WAL
+    pub frame_checksum: (u32, u32),
+    /// Salt values copied from the WAL header
+    pub salt1: u32,
+    pub salt2: u32,
+    /// Checksum over the first 40 bytes of this header
+    pub checksum1: u32,
+    pub checksum2: u32,
+    /// Whether the stored checksum matches the computed one
+    pub checksum_valid: bool,
+}
+
+/// Checkpoint state (40 bytes following the two header copies)
+#[derive(Debug, Clone, Serialize)]
+pub struct WalCheckpointInfo {
+    /// Number of WAL frames already copied back into the database
+    pub backfill: u32,
+    /// Read marks: the mxFrame each reader slot is pinned at
+    pub read_marks: Vec<u32>,
+    /// Reserved bytes used as lock slots by some VFSes
+    pub locks: Vec<u8>,
+    /// Number of frames a checkpoint has attempted to backfill
+    pub backfill_attempted: u32,
+}
+
+/// One hash-table block of the WAL-index, covering a range of frames
+#[derive(Debug, Clone, Serialize)]
+pub struct WalIndexHashTable {
+    /// Block index within the -shm file
+    pub block: usize,
+    /// Frame number (1-based) described by the first entry of `page_numbers`
+    pub first_frame: u32,
+    /// Page number written by each frame in the block (0 = not yet used)
+    pub page_numbers: Vec<u32>,
+    /// Hash slots: 1-based index into `page_numbers`, 0 for an empty slot
+    pub hash_slots: Vec<u16>,
+}
+
+impl WalIndexHashTable {
+    /// Number of occupied hash slots
+    pub fn used_slots(&self) -> usize {
+        self.hash_slots.iter().filter(|&&s| s != 0).count()
+    }
+}
+
+/// Parsed WAL-index (-shm) file
+#[derive(Debug, Clone, Serialize)]
+pub struct WalIndex {
+    /// Source file name
+    pub file_name: String,
+    /// Whether the file was written by a big-endian host
+    pub big_endian: bool,
+    /// Both copies of the header
+    pub headers: [WalIndexHeader; 2],
+    /// Checkpoint info
+    pub checkpoint: WalCheckpointInfo,
+    /// Hash-table blocks
+    pub hash_tables: Vec<WalIndexHashTable>,
+}
+
+impl WalIndex {
+    /// Whether the two header copies agree. A mismatch means a writer was
+    /// interrupted and readers will retry or rebuild the index.
+    pub fn headers_match(&self) -> bool {
+        self.headers[0] == self.headers[1]
+    }
+
+    /// Page number written by a frame (1-based), according to the index
+    pub fn page_for_frame(&self, frame: u32) -> Option<u32> {
+        self.hash_tables.iter().find_map(|table| {
+            let idx = frame.checked_sub(table.first_frame)? as usize;
+            table.page_numbers.get(idx).copied().filter(|&p| p != 0)
+        })
+    }
+}
@@ -0,0 +1,107 @@
+//! Query planner statistics gathered by ANALYZE.
+
+use serde::Serialize;
+
+use super::Record;
+
+/// A row of sqlite_stat1: size estimates for an index
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Stat1Entry {
+    pub table: String,
+    /// Index the estimates are for; a WITHOUT ROWID table is named by itself. None for a
+    /// table without indexes, where only its row count is recorded.
+    pub index: Option<String>,
+    /// Estimated number of entries
+    pub row_count: u64,
+    /// Average number of entries sharing each distinct value of the first 1, 2, ... key columns
+    pub rows_per_key: Vec<u64>,
+    /// Keywords after the numbers, such as `unordered` or `sz=N`
+    pub options: Vec<String>,
+}
+
+impl Stat1Entry {
+    /// The stat column as ANALYZE writes it, e.g. `200 16 3`
+    pub fn stat_text(&self) -> String {
+        std::iter::once(self.row_count.to_string())
+            .chain(self.rows_per_key.iter().map(u64::to_string))
+            .chain(self.options.iter().cloned())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Whether two entries hold the same estimates, ignoring options
+    pub fn same_estimates(&self, other: &Stat1Entry) -> bool {
+        self.row_count == other.row_count && self.rows_per_key == other.rows_per_key
+    }
+}
+
+/// A row of sqlite_stat4: a sampled index entry and where it falls in the index
+#[derive(Debug, Clone, Serialize)]
+pub struct Stat4Sample {
+    pub table: String,
+    pub index: String,
+    /// Entries equal to the sample in the first 1, 2, ... columns
+    pub equal: Vec<u64>,
+    /// Entries less than the sample in the first 1, 2, ... columns
+    pub less: Vec<u64>,
+    /// Distinct keys less than the sample in the first 1, 2, ... columns
+    pub distinct_less: Vec<u64>,
+    /// The sampled index entry
+    pub sample: Record,
+}
+
+/// Contents of sqlite_stat1 and sqlite_stat4
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Statistics {
+    pub stat1: Vec<Stat1Entry>,
+    pub stat4: Vec<Stat4Sample>,
+    /// Rows that could not be read or decoded
+    pub errors: Vec<String>,
+}
+
+impl Statistics {
+    /// sqlite_stat1 entry of an index or WITHOUT ROWID table, or of a table without indexes
+    pub fn stat1_for(&self, tree: &str) -> Option<&Stat1Entry> {
+        self.stat1.iter().find(|e| match &e.index {
+            Some(index) => index == tree,
+            None => e.table == tree,
+        })
+    }
+
+    /// sqlite_stat4 samples of an index or WITHOUT ROWID table, in stored order
+    pub fn stat4_for<'a>(&'a self, index: &'a str) -> impl Iterator<Item = &'a Stat4Sample> {
+        self.stat4.iter().filter(move |s| s.index == index)
+    }
+}
+
+/// How a sqlite_stat1 row compares with the statistics ANALYZE would gather now
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatStatus {
+    /// The stored estimates match
+    Current,
+    /// The stored estimates differ, or ANALYZE would now remove the row
+    Stale,
+    /// Nothing is stored for a B-tree ANALYZE would describe
+    Missing,
+}
+
+/// Statistics computed from one B-tree next to the sqlite_stat1 row stored for it
+#[derive(Debug, Clone, Serialize)]
+pub struct StatComparison {
+    pub table: String,
+    /// Index or WITHOUT ROWID table; None for a table without indexes
+    pub index: Option<String>,
+    /// What ANALYZE would store; None if it would store nothing, as for an empty tree
+    pub computed: Option<Stat1Entry>,
+    pub stored: Option<Stat1Entry>,
+    pub status: StatStatus,
+}
+
+/// sqlite_stat1 as ANALYZE would write it now, compared with its current contents
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StatReport {
+    pub comparisons: Vec<StatComparison>,
+    /// B-trees and stored rows that could not be read
+    pub errors: Vec<String>,
+}
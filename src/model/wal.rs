@@ -1,13 +1,15 @@
 //! WAL (Write-Ahead Log) data structures.
 
+use std::collections::HashMap;
+
 use serde::Serialize;
 
 use super::Page;
 
-/// WAL file magic number for big-endian checksums
-pub const WAL_MAGIC_BIG_ENDIAN: u32 = 0x377f0682;
 /// WAL file magic number for little-endian checksums
-pub const WAL_MAGIC_LITTLE_ENDIAN: u32 = 0x377f0683;
+pub const WAL_MAGIC_LITTLE_ENDIAN: u32 = 0x377f0682;
+/// WAL file magic number for big-endian checksums (low bit set)
+pub const WAL_MAGIC_BIG_ENDIAN: u32 = 0x377f0683;
 
 /// WAL file header (32 bytes)
 #[derive(Debug, Clone, Serialize)]
@@ -72,6 +74,8 @@ pub struct WalFrame {
     pub page: Option<Page>,
     /// Raw page data
     pub raw_page_data: Vec<u8>,
+    /// Whether the frame's cumulative checksum matches the computed one
+    pub checksum_valid: bool,
 }
 
 /// Parsed WAL file structure
@@ -83,4 +87,40 @@ pub struct WalFile {
     pub frames: Vec<WalFrame>,
     /// Source file name
     pub file_name: String,
+    /// Whether the header checksum matches the computed one
+    pub header_checksum_valid: bool,
+}
+
+impl WalFile {
+    /// Index of the first frame whose checksum does not match.
+    /// SQLite stops reading the WAL at this frame.
+    pub fn first_invalid_frame(&self) -> Option<usize> {
+        if !self.header_checksum_valid {
+            return self.frames.first().map(|f| f.frame_index);
+        }
+        self.frames.iter().find(|f| !f.checksum_valid).map(|f| f.frame_index)
+    }
+
+    /// Index of the last valid commit frame, i.e. the last frame SQLite would replay.
+    /// Frames after it belong to uncommitted or damaged transactions.
+    pub fn last_valid_commit_frame(&self) -> Option<usize> {
+        let valid_end = self.first_invalid_frame().unwrap_or(self.frames.len());
+        self.frames[..valid_end]
+            .iter()
+            .rev()
+            .find(|f| f.header.is_commit_frame())
+            .map(|f| f.frame_index)
+    }
+}
+
+/// The database as SQLite would read it at a given WAL commit:
+/// for every page written up to that commit, the frame holding its latest version.
+#[derive(Debug, Clone, Serialize)]
+pub struct WalSnapshot {
+    /// Commit frame the snapshot ends at (inclusive)
+    pub commit_frame: usize,
+    /// Database size in pages after that commit
+    pub db_size: u32,
+    /// Page number -> index of the frame holding the page's latest version
+    pub page_frames: HashMap<u32, usize>,
 }
@@ -1,7 +1,7 @@
 use crate::error::{Result, SqliteVizError};
 use crate::model::{
     Cell, TableLeafCell, TableInteriorCell, IndexLeafCell, IndexInteriorCell,
-    PageType,
+    PageType, TextEncoding,
 };
 use crate::parser::varint::{parse_varint, parse_signed_varint};
 use crate::parser::record::parse_record;
@@ -58,20 +58,21 @@ pub fn parse_cell(
     cell_offset: u16,
     page_type: PageType,
     usable_size: u32,
+    encoding: TextEncoding,
 ) -> Result<Cell> {
     let data = &page_data[cell_offset as usize..];
 
     match page_type {
-        PageType::LeafTable => parse_table_leaf_cell(data, cell_offset, usable_size),
+        PageType::LeafTable => parse_table_leaf_cell(data, cell_offset, usable_size, encoding),
         PageType::InteriorTable => parse_table_interior_cell(data, cell_offset),
-        PageType::LeafIndex => parse_index_leaf_cell(data, cell_offset, usable_size),
-        PageType::InteriorIndex => parse_index_interior_cell(data, cell_offset, usable_size),
+        PageType::LeafIndex => parse_index_leaf_cell(data, cell_offset, usable_size, encoding),
+        PageType::InteriorIndex => parse_index_interior_cell(data, cell_offset, usable_size, encoding),
         _ => Err(SqliteVizError::InvalidPageType(0)),
     }
 }
 
 /// Parse a table B-tree leaf cell (page type 0x0d)
-fn parse_table_leaf_cell(data: &[u8], cell_offset: u16, usable_size: u32) -> Result<Cell> {
+fn parse_table_leaf_cell(data: &[u8], cell_offset: u16, usable_size: u32, encoding: TextEncoding) -> Result<Cell> {
     let mut offset = 0;
 
     // Payload size (varint)
@@ -87,7 +88,7 @@ fn parse_table_leaf_cell(data: &[u8], cell_offset: u16, usable_size: u32) -> Res
 
     // Parse payload (if we have enough data)
     let payload = if offset + local_payload_size <= data.len() {
-        parse_record(&data[offset..offset + local_payload_size]).ok()
+        parse_record(&data[offset..offset + local_payload_size], encoding).ok()
     } else {
         None
     };
@@ -146,7 +147,7 @@ fn parse_table_interior_cell(data: &[u8], cell_offset: u16) -> Result<Cell> {
 }
 
 /// Parse an index B-tree leaf cell (page type 0x0a)
-fn parse_index_leaf_cell(data: &[u8], cell_offset: u16, usable_size: u32) -> Result<Cell> {
+fn parse_index_leaf_cell(data: &[u8], cell_offset: u16, usable_size: u32, encoding: TextEncoding) -> Result<Cell> {
     let mut offset = 0;
 
     // Payload size (varint)
@@ -158,7 +159,7 @@ fn parse_index_leaf_cell(data: &[u8], cell_offset: u16, usable_size: u32) -> Res
 
     // Parse payload
     let payload = if offset + local_payload_size <= data.len() {
-        parse_record(&data[offset..offset + local_payload_size]).ok()
+        parse_record(&data[offset..offset + local_payload_size], encoding).ok()
     } else {
         None
     };
@@ -193,7 +194,7 @@ fn parse_index_leaf_cell(data: &[u8], cell_offset: u16, usable_size: u32) -> Res
 }
 
 /// Parse an index B-tree interior cell (page type 0x02)
-fn parse_index_interior_cell(data: &[u8], cell_offset: u16, usable_size: u32) -> Result<Cell> {
+fn parse_index_interior_cell(data: &[u8], cell_offset: u16, usable_size: u32, encoding: TextEncoding) -> Result<Cell> {
     if data.len() < 4 {
         return Err(SqliteVizError::UnexpectedEof { context: "index interior cell" });
     }
@@ -211,7 +212,7 @@ fn parse_index_interior_cell(data: &[u8], cell_offset: u16, usable_size: u32) ->
 
     // Parse payload
     let payload = if offset + local_payload_size <= data.len() {
-        parse_record(&data[offset..offset + local_payload_size]).ok()
+        parse_record(&data[offset..offset + local_payload_size], encoding).ok()
     } else {
         None
     };
@@ -0,0 +1,149 @@
+//! Freelist trunk page parsing and chain walking.
+
+use std::collections::HashSet;
+
+use crate::error::{Result, SqliteVizError};
+use crate::model::{Freelist, FreelistTrunkPage};
+
+/// Parse a freelist trunk page.
+/// Layout: next trunk page (4 bytes), leaf count (4 bytes), then leaf page numbers (4 bytes each).
+/// Every leaf must be a page of the database.
+pub fn parse_freelist_trunk(page_data: &[u8], page_number: u32, usable_size: u32, page_count: u32) -> Result<FreelistTrunkPage> {
+    if page_data.len() < 8 {
+        return Err(SqliteVizError::UnexpectedEof { context: "freelist trunk page" });
+    }
+
+    let next_trunk = u32::from_be_bytes([page_data[0], page_data[1], page_data[2], page_data[3]]);
+    let next_trunk = if next_trunk == 0 { None } else { Some(next_trunk) };
+    let leaf_count = u32::from_be_bytes([page_data[4], page_data[5], page_data[6], page_data[7]]) as usize;
+
+    // A trunk holds at most (usable_size / 4) - 2 leaf pointers
+    let max_leaves = (usable_size as usize / 4).saturating_sub(2);
+    if leaf_count > max_leaves || 8 + leaf_count * 4 > page_data.len() {
+        return Err(SqliteVizError::FreelistError(format!(
+            "trunk page {} claims {} leaves, at most {} fit",
+            page_number, leaf_count, max_leaves
+        )));
+    }
+
+    let leaf_pages: Vec<u32> = page_data[8..8 + leaf_count * 4]
+        .chunks_exact(4)
+        .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+    if let Some(leaf) = leaf_pages.iter().find(|&&leaf| leaf == 0 || leaf > page_count) {
+        return Err(SqliteVizError::FreelistError(format!(
+            "trunk page {} lists leaf page {}, outside the database ({} pages)",
+            page_number, leaf, page_count
+        )));
+    }
+
+    Ok(FreelistTrunkPage {
+        page_number,
+        next_trunk,
+        leaf_pages,
+    })
+}
+
+/// Follow the freelist trunk chain starting at `first_trunk` and collect all trunks.
+/// The pages found must add up to `expected_pages`, the header's freelist page count.
+pub fn walk_freelist<F>(
+    first_trunk: u32,
+    expected_pages: u32,
+    usable_size: u32,
+    page_count: u32,
+    mut read_page: F,
+) -> Result<Freelist>
+where
+    F: FnMut(u32) -> Result<Vec<u8>>,
+{
+    let mut trunks = Vec::new();
+    let mut visited = HashSet::new();
+    let mut current = if first_trunk == 0 { None } else { Some(first_trunk) };
+
+    while let Some(page_num) = current {
+        if page_num > page_count {
+            return Err(SqliteVizError::FreelistError(format!(
+                "trunk page {} is beyond the end of the database ({} pages)",
+                page_num, page_count
+            )));
+        }
+        if !visited.insert(page_num) {
+            return Err(SqliteVizError::FreelistError(format!(
+                "trunk chain loops back to page {}",
+                page_num
+            )));
+        }
+
+        let page_data = read_page(page_num)?;
+        let trunk = parse_freelist_trunk(&page_data, page_num, usable_size, page_count)?;
+        current = trunk.next_trunk;
+        trunks.push(trunk);
+    }
+
+    let freelist = Freelist { first_trunk, trunks };
+    if freelist.total_pages() != expected_pages as usize {
+        return Err(SqliteVizError::FreelistError(format!(
+            "freelist holds {} pages, the header says {}",
+            freelist.total_pages(),
+            expected_pages
+        )));
+    }
+    Ok(freelist)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trunk_page(next: u32, leaves: &[u32]) -> Vec<u8> {
+        let mut data = vec![0u8; 512];
+        data[0..4].copy_from_slice(&next.to_be_bytes());
+        data[4..8].copy_from_slice(&(leaves.len() as u32).to_be_bytes());
+        for (i, leaf) in leaves.iter().enumerate() {
+            data[8 + i * 4..12 + i * 4].copy_from_slice(&leaf.to_be_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn test_walk_trunk_chain() {
+        let freelist = walk_freelist(3, 5, 512, 10, |p| match p {
+            3 => Ok(trunk_page(7, &[4, 5])),
+            7 => Ok(trunk_page(0, &[8])),
+            _ => unreachable!(),
+        })
+        .unwrap();
+
+        assert_eq!(freelist.trunks.len(), 2);
+        assert_eq!(freelist.total_pages(), 5);
+        assert_eq!(freelist.leaf_pages().collect::<Vec<_>>(), vec![4, 5, 8]);
+    }
+
+    #[test]
+    fn test_trunk_cycle_is_error() {
+        let result = walk_freelist(3, 1, 512, 10, |_| Ok(trunk_page(3, &[])));
+        assert!(matches!(result, Err(SqliteVizError::FreelistError(_))));
+    }
+
+    #[test]
+    fn test_leaf_count_too_large() {
+        let mut data = trunk_page(0, &[]);
+        data[4..8].copy_from_slice(&1000u32.to_be_bytes());
+        assert!(parse_freelist_trunk(&data, 2, 512, 10).is_err());
+    }
+
+    #[test]
+    fn test_leaf_outside_database() {
+        assert!(parse_freelist_trunk(&trunk_page(0, &[4, 11]), 2, 512, 10).is_err());
+        assert!(parse_freelist_trunk(&trunk_page(0, &[0]), 2, 512, 10).is_err());
+        assert!(parse_freelist_trunk(&trunk_page(0, &[10]), 2, 512, 10).is_ok());
+    }
+
+    #[test]
+    fn test_count_must_match_header() {
+        let read_page = |_| Ok(trunk_page(0, &[4, 5]));
+        assert!(walk_freelist(3, 3, 512, 10, read_page).is_ok());
+        let result = walk_freelist(3, 4, 512, 10, read_page);
+        assert!(matches!(result, Err(SqliteVizError::FreelistError(_))));
+    }
+}
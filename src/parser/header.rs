@@ -56,8 +56,12 @@ pub fn parse_database_header(data: &[u8]) -> Result<DatabaseHeader> {
 
     // Text encoding (bytes 56-59)
     let text_encoding_raw = u32::from_be_bytes([data[56], data[57], data[58], data[59]]);
-    let text_encoding = TextEncoding::from_u32(text_encoding_raw)
-        .ok_or(SqliteVizError::InvalidTextEncoding(text_encoding_raw))?;
+    // A database that has never held a schema leaves this as 0 (e.g. when all
+    // of its content is still in the WAL); SQLite reads that as UTF-8.
+    let text_encoding = match text_encoding_raw {
+        0 => TextEncoding::Utf8,
+        raw => TextEncoding::from_u32(raw).ok_or(SqliteVizError::InvalidTextEncoding(raw))?,
+    };
 
     // User version (bytes 60-63)
     let user_version = u32::from_be_bytes([data[60], data[61], data[62], data[63]]);
@@ -0,0 +1,175 @@
+//! Rollback journal (-journal) parsing.
+
+use crate::error::{Result, SqliteVizError};
+use crate::model::{
+    JournalHeader, JournalRecord, JournalSegment, RollbackJournal, TextEncoding, JOURNAL_MAGIC,
+};
+
+use super::{parse_database_header, parse_page};
+
+/// Size of the fields at the start of a journal header
+const JOURNAL_HEADER_FIELDS_SIZE: usize = 28;
+
+/// Check if data looks like a rollback journal
+pub fn is_journal_file(data: &[u8]) -> bool {
+    data.len() >= JOURNAL_MAGIC.len() && data[..JOURNAL_MAGIC.len()] == JOURNAL_MAGIC
+}
+
+/// Compute a journal record checksum: the nonce plus every 200th byte of the
+/// page, starting from the end. Only a sample of bytes is summed, as in SQLite.
+pub fn journal_checksum(page_data: &[u8], nonce: u32) -> u32 {
+    let mut checksum = nonce;
+    let mut i = page_data.len() as isize - 200;
+    while i > 0 {
+        checksum = checksum.wrapping_add(page_data[i as usize] as u32);
+        i -= 200;
+    }
+    checksum
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+}
+
+/// Parse a journal header at `offset`. Returns None if there is no header there.
+pub fn parse_journal_header(data: &[u8], offset: usize) -> Option<JournalHeader> {
+    let fields = data.get(offset..offset + JOURNAL_HEADER_FIELDS_SIZE)?;
+    if !is_journal_file(fields) {
+        return None;
+    }
+
+    Some(JournalHeader {
+        offset,
+        record_count: read_u32(fields, 8),
+        nonce: read_u32(fields, 12),
+        initial_page_count: read_u32(fields, 16),
+        sector_size: read_u32(fields, 20),
+        page_size: read_u32(fields, 24),
+    })
+}
+
+/// Find the text encoding from a journaled copy of page 1, defaulting to UTF-8
+fn detect_text_encoding(data: &[u8], first_record: usize, record_size: usize) -> TextEncoding {
+    let mut offset = first_record;
+    while offset + record_size <= data.len() {
+        if read_u32(data, offset) == 1 {
+            if let Ok(db_header) = parse_database_header(&data[offset + 4..offset + record_size - 4]) {
+                return db_header.text_encoding;
+            }
+        }
+        offset += record_size;
+    }
+    TextEncoding::Utf8
+}
+
+/// Parse an entire rollback journal
+pub fn parse_journal(data: &[u8], file_name: String) -> Result<RollbackJournal> {
+    let first = parse_journal_header(data, 0).ok_or(SqliteVizError::InvalidJournal)?;
+    let page_size = first.page_size as usize;
+    let sector_size = first.sector_size as usize;
+    if !(512..=65536).contains(&page_size) || !page_size.is_power_of_two() || sector_size < JOURNAL_HEADER_FIELDS_SIZE {
+        return Err(SqliteVizError::InvalidJournal);
+    }
+
+    // pgno + page data + checksum
+    let record_size = page_size + 8;
+    let encoding = detect_text_encoding(data, sector_size, record_size);
+
+    let mut segments = Vec::new();
+    let mut header_offset = 0;
+
+    while let Some(header) = parse_journal_header(data, header_offset) {
+        let mut offset = header_offset + sector_size;
+        let available = data.len().saturating_sub(offset) / record_size;
+        // 0xffffffff is written when the journal is not synced; the records run to end of file
+        let record_count = if header.record_count == u32::MAX {
+            available
+        } else {
+            (header.record_count as usize).min(available)
+        };
+
+        let mut records = Vec::with_capacity(record_count);
+        for _ in 0..record_count {
+            let page_number = read_u32(data, offset);
+            let page_data = &data[offset + 4..offset + 4 + page_size];
+            let checksum = read_u32(data, offset + 4 + page_size);
+
+            let page = parse_page(page_data, page_number, first.page_size, first.page_size, encoding).ok();
+
+            records.push(JournalRecord {
+                offset,
+                page_number,
+                page,
+                raw_page_data: page_data.to_vec(),
+                checksum,
+                checksum_valid: checksum == journal_checksum(page_data, header.nonce),
+            });
+            offset += record_size;
+        }
+
+        let has_records = header.record_count != 0;
+        segments.push(JournalSegment { header, records });
+
+        // The next header starts at the next sector boundary; a segment with no
+        // records is the last one written
+        if !has_records {
+            break;
+        }
+        header_offset = offset.div_ceil(sector_size) * sector_size;
+    }
+
+    Ok(RollbackJournal { file_name, segments })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_journal(records: &[(u32, u8)]) -> Vec<u8> {
+        let nonce = 0x1234u32;
+        let mut data = vec![0u8; 512];
+        data[..8].copy_from_slice(&JOURNAL_MAGIC);
+        for (i, value) in [records.len() as u32, nonce, 3, 512, 512].iter().enumerate() {
+            data[8 + i * 4..12 + i * 4].copy_from_slice(&value.to_be_bytes());
+        }
+        for &(page_number, fill) in records {
+            let page = vec![fill; 512];
+            data.extend_from_slice(&page_number.to_be_bytes());
+            data.extend_from_slice(&page);
+            data.extend_from_slice(&journal_checksum(&page, nonce).to_be_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn test_journal_checksum() {
+        let mut page = vec![0u8; 1024];
+        page[824] = 1; // 1024 - 200
+        page[624] = 2;
+        page[24] = 3;
+        page[0] = 100; // never sampled
+        assert_eq!(journal_checksum(&page, 10), 16);
+    }
+
+    #[test]
+    fn test_parse_journal() {
+        let data = build_journal(&[(2, 0xaa), (5, 0xbb), (2, 0xcc)]);
+        let journal = parse_journal(&data, "test-journal".to_string()).unwrap();
+        assert!(journal.is_hot());
+        assert_eq!(journal.segments.len(), 1);
+        assert_eq!(journal.segments[0].header.initial_page_count, 3);
+        assert!(journal.records().all(|r| r.checksum_valid));
+        // Page 5 is past the original size, the second copy of page 2 is ignored
+        assert_eq!(journal.restored_pages(), vec![2]);
+    }
+
+    #[test]
+    fn test_playback_stops_at_bad_checksum() {
+        let mut data = build_journal(&[(2, 0xaa), (3, 0xbb)]);
+        let second_checksum = data.len() - 4;
+        data[second_checksum] ^= 0xff;
+        let journal = parse_journal(&data, "test-journal".to_string()).unwrap();
+        assert!(!journal.segments[0].records[1].checksum_valid);
+        assert_eq!(journal.restored_pages(), vec![2]);
+    }
+}
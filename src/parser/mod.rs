@@ -5,6 +5,11 @@ pub mod cell;
 pub mod record;
 pub mod overflow;
 pub mod wal;
+pub mod shm;
+pub mod journal;
+pub mod freelist;
+pub mod ptrmap;
+pub mod sql;
 
 pub use varint::*;
 pub use header::*;
@@ -13,3 +18,8 @@ pub use cell::*;
 pub use record::*;
 pub use overflow::*;
 pub use wal::*;
+pub use shm::*;
+pub use journal::*;
+pub use freelist::*;
+pub use ptrmap::*;
+pub use sql::*;
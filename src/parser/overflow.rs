@@ -1,5 +1,8 @@
+use std::collections::HashSet;
+
 use crate::error::{Result, SqliteVizError};
-use crate::model::OverflowPage;
+use crate::model::{OverflowPage, Page, TextEncoding};
+use crate::parser::record::parse_record;
 
 /// Information about an overflow chain
 #[derive(Debug, Clone)]
@@ -36,6 +39,7 @@ where
     F: FnMut(u32) -> Result<Vec<u8>>,
 {
     let mut pages = Vec::new();
+    let mut visited = HashSet::new();
     let mut current_page = Some(first_overflow_page);
     let mut bytes_remaining = remaining_bytes;
     let mut total_bytes = 0;
@@ -44,6 +48,10 @@ where
     let max_content_per_page = usable_size as usize - 4;
 
     while let Some(page_num) = current_page {
+        // A corrupt chain can loop back on itself
+        if !visited.insert(page_num) {
+            break;
+        }
         let page_data = read_page(page_num)?;
         let (next_page, _) = parse_overflow_header(&page_data)?;
 
@@ -68,3 +76,142 @@ where
 
     Ok(OverflowChainInfo { pages, total_bytes })
 }
+
+/// Read the overflow portion of a payload by following the chain from `first_overflow_page`.
+/// Returns exactly `overflow_bytes` bytes, concatenated in chain order.
+/// The size comes from the cell header, so it is checked against what `page_count`
+/// pages could hold before anything is read.
+pub fn read_overflow_payload<F>(
+    first_overflow_page: u32,
+    overflow_bytes: usize,
+    usable_size: u32,
+    page_count: u32,
+    mut read_page: F,
+) -> Result<Vec<u8>>
+where
+    F: FnMut(u32) -> Result<Vec<u8>>,
+{
+    let max_content_per_page = usable_size as usize - 4;
+    if overflow_bytes > page_count as usize * max_content_per_page {
+        return Err(SqliteVizError::BTreeError(format!(
+            "overflow payload of {} bytes cannot fit in a {} page database",
+            overflow_bytes, page_count
+        )));
+    }
+
+    let mut payload = Vec::new();
+    let mut visited = HashSet::new();
+    let mut current_page = Some(first_overflow_page);
+
+    while payload.len() < overflow_bytes {
+        let page_num = current_page.ok_or(SqliteVizError::UnexpectedEof { context: "overflow chain" })?;
+        if !visited.insert(page_num) {
+            return Err(SqliteVizError::BTreeError(format!(
+                "overflow chain loops back to page {}",
+                page_num
+            )));
+        }
+        let page_data = read_page(page_num)?;
+        let (next_page, _) = parse_overflow_header(&page_data)?;
+
+        let content_size = (overflow_bytes - payload.len()).min(max_content_per_page);
+        if 4 + content_size > page_data.len() {
+            return Err(SqliteVizError::UnexpectedEof { context: "overflow page" });
+        }
+        payload.extend_from_slice(&page_data[4..4 + content_size]);
+
+        current_page = next_page;
+    }
+
+    Ok(payload)
+}
+
+/// Replace the local-only records of a page's cells with records decoded from the
+/// full payload, reassembled from the overflow chain of each cell.
+/// Cells whose chain cannot be read keep their local-only record.
+pub fn reassemble_overflow_payloads<F>(
+    page: &mut Page,
+    usable_size: u32,
+    page_count: u32,
+    encoding: TextEncoding,
+    mut read_page: F,
+)
+where
+    F: FnMut(u32) -> Result<Vec<u8>>,
+{
+    for cell in &mut page.cells {
+        let (Some(first_overflow), Some(payload_size), Some(local_size)) =
+            (cell.overflow_page(), cell.payload_size(), cell.local_payload_size())
+        else {
+            continue;
+        };
+
+        // Local payload sits right before the 4-byte overflow page pointer
+        let local_end = cell.cell_offset() as usize + cell.cell_size() - 4;
+        let Some(local_start) = local_end.checked_sub(local_size) else {
+            continue;
+        };
+        if local_end > page.raw_data.len() {
+            continue;
+        }
+
+        let overflow_bytes = (payload_size as usize).saturating_sub(local_size);
+        if let Ok(overflow) = read_overflow_payload(first_overflow, overflow_bytes, usable_size, page_count, &mut read_page) {
+            let mut payload = page.raw_data[local_start..local_end].to_vec();
+            payload.extend_from_slice(&overflow);
+            if let Ok(record) = parse_record(&payload, encoding) {
+                cell.set_payload(Some(record));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overflow_page(next: u32, fill: u8) -> Vec<u8> {
+        let mut data = vec![fill; 64];
+        data[0..4].copy_from_slice(&next.to_be_bytes());
+        data
+    }
+
+    #[test]
+    fn test_read_overflow_payload_concatenates_chain() {
+        // 60 content bytes per page; 100 bytes spans two pages
+        let payload = read_overflow_payload(5, 100, 64, 10, |p| match p {
+            5 => Ok(overflow_page(9, 0xaa)),
+            9 => Ok(overflow_page(0, 0xbb)),
+            _ => unreachable!(),
+        })
+        .unwrap();
+
+        assert_eq!(payload.len(), 100);
+        assert!(payload[..60].iter().all(|&b| b == 0xaa));
+        assert!(payload[60..].iter().all(|&b| b == 0xbb));
+    }
+
+    #[test]
+    fn test_read_overflow_payload_short_chain() {
+        let result = read_overflow_payload(5, 100, 64, 10, |_| Ok(overflow_page(0, 0)));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_read_overflow_payload_rejects_oversized_payload() {
+        // A corrupt cell header claiming a payload far beyond the file must not be allocated
+        let result = read_overflow_payload(5, usize::MAX / 2, 64, 10, |_| Ok(overflow_page(0, 0)));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_read_overflow_payload_stops_on_loop() {
+        let mut reads = 0;
+        let result = read_overflow_payload(5, 500, 64, 10, |p| {
+            reads += 1;
+            Ok(overflow_page(if p == 5 { 9 } else { 5 }, 0))
+        });
+        assert!(result.is_err());
+        assert_eq!(reads, 2);
+    }
+}
@@ -1,5 +1,5 @@
 use crate::error::{Result, SqliteVizError};
-use crate::model::{Page, PageType, BTreePageHeader};
+use crate::model::{Page, PageType, BTreePageHeader, Freeblock, FreeSpace, TextEncoding};
 use crate::parser::cell::parse_cell;
 
 /// Parse a B-tree page header
@@ -38,6 +38,7 @@ pub fn parse_page(
     page_number: u32,
     page_size: u32,
     usable_size: u32,
+    encoding: TextEncoding,
 ) -> Result<Page> {
     // Page 1 has 100-byte database header at the start
     let header_offset = if page_number == 1 { 100 } else { 0 };
@@ -57,15 +58,7 @@ pub fn parse_page(
             // For now, we'll handle these separately
             if page_type_byte == 0 {
                 // Could be overflow or freelist leaf
-                return Ok(Page {
-                    page_number,
-                    page_type: PageType::Overflow,
-                    header: None,
-                    cell_pointers: Vec::new(),
-                    cells: Vec::new(),
-                    free_space: page_size as usize,
-                    raw_data: page_data.to_vec(),
-                });
+                return Ok(parse_raw_page(page_data, page_number, PageType::Overflow, page_size));
             }
             return Err(SqliteVizError::InvalidPageType(page_type_byte));
         }
@@ -93,7 +86,7 @@ pub fn parse_page(
     // Parse cells
     let mut cells = Vec::with_capacity(header.cell_count as usize);
     for &ptr in &cell_pointers {
-        match parse_cell(page_data, ptr, page_type, usable_size) {
+        match parse_cell(page_data, ptr, page_type, usable_size, encoding) {
             Ok(cell) => cells.push(cell),
             Err(_) => {
                 // Log error but continue parsing other cells
@@ -109,10 +102,23 @@ pub fn parse_page(
         header.cell_content_start as usize
     };
 
-    let _cells_total_size: usize = cells.iter().map(|c| c.cell_size()).sum();
-    let free_space = cell_content_start
-        .saturating_sub(cell_pointer_end)
-        .saturating_add(header.fragmented_free_bytes as usize);
+    let (freeblocks, freeblock_error) = match parse_freeblocks(page_data, header.first_freeblock, usable_size) {
+        Ok(freeblocks) => (freeblocks, None),
+        Err(e) => (Vec::new(), Some(e.to_string())),
+    };
+    let cells_total_size: usize = cells.iter().map(|c| c.cell_size()).sum();
+    let freeblock_bytes: usize = freeblocks.iter().map(|f| f.size as usize).sum();
+    let fragmented = header.fragmented_free_bytes as usize;
+
+    // The cell content area holds cells, freeblocks and fragments and nothing else
+    let content_area = (usable_size as usize).saturating_sub(cell_content_start);
+    let breakdown = FreeSpace {
+        unallocated: cell_content_start.saturating_sub(cell_pointer_end),
+        freeblocks: freeblock_bytes,
+        fragmented,
+        unaccounted: content_area as i64 - (cells_total_size + freeblock_bytes + fragmented) as i64,
+        freeblock_error,
+    };
 
     Ok(Page {
         page_number,
@@ -120,11 +126,70 @@ pub fn parse_page(
         header: Some(header),
         cell_pointers,
         cells,
-        free_space,
+        free_space: breakdown.total(),
+        freeblocks,
+        free_space_breakdown: Some(breakdown),
         raw_data: page_data.to_vec(),
     })
 }
 
+/// Build a page that has no B-tree structure (overflow, freelist, ...).
+/// The page type must come from context, since it cannot be inferred from the content.
+pub fn parse_raw_page(page_data: &[u8], page_number: u32, page_type: PageType, page_size: u32) -> Page {
+    Page {
+        page_number,
+        page_type,
+        header: None,
+        cell_pointers: Vec::new(),
+        cells: Vec::new(),
+        free_space: page_size as usize,
+        freeblocks: Vec::new(),
+        free_space_breakdown: None,
+        raw_data: page_data.to_vec(),
+    }
+}
+
+/// Walk the freeblock chain of a B-tree page starting at `first_freeblock`.
+/// Freeblocks must be in ascending order without overlapping, which also rules out cycles.
+pub fn parse_freeblocks(page_data: &[u8], first_freeblock: u16, usable_size: u32) -> Result<Vec<Freeblock>> {
+    let usable_size = (usable_size as usize).min(page_data.len());
+    let mut freeblocks = Vec::new();
+    let mut offset = first_freeblock as usize;
+
+    while offset != 0 {
+        if offset + 4 > usable_size {
+            return Err(SqliteVizError::FreeblockError(format!(
+                "freeblock at {} starts past the usable page size {}",
+                offset, usable_size
+            )));
+        }
+
+        let next = u16::from_be_bytes([page_data[offset], page_data[offset + 1]]) as usize;
+        let size = u16::from_be_bytes([page_data[offset + 2], page_data[offset + 3]]) as usize;
+        if size < 4 || offset + size > usable_size {
+            return Err(SqliteVizError::FreeblockError(format!(
+                "freeblock at {} has invalid size {}",
+                offset, size
+            )));
+        }
+        if next != 0 && next < offset + size {
+            return Err(SqliteVizError::FreeblockError(format!(
+                "freeblock at {} links back to {}",
+                offset, next
+            )));
+        }
+
+        freeblocks.push(Freeblock {
+            offset: offset as u16,
+            size: size as u16,
+            next: if next == 0 { None } else { Some(next as u16) },
+        });
+        offset = next;
+    }
+
+    Ok(freeblocks)
+}
+
 /// Parse an overflow page
 pub fn parse_overflow_page(page_data: &[u8], _page_number: u32, usable_size: u32) -> Result<(Option<u32>, usize)> {
     if page_data.len() < 4 {
@@ -140,3 +205,50 @@ pub fn parse_overflow_page(page_data: &[u8], _page_number: u32, usable_size: u32
 
     Ok((next_page, content_size))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_freeblock_chain() {
+        let mut data = vec![0u8; 512];
+        data[300..304].copy_from_slice(&[0x01, 0xa4, 0x00, 0x10]); // at 300: next 420, size 16
+        data[420..424].copy_from_slice(&[0x00, 0x00, 0x00, 0x08]); // at 420: last, size 8
+
+        let freeblocks = parse_freeblocks(&data, 300, 512).unwrap();
+        assert_eq!(freeblocks.len(), 2);
+        assert_eq!(freeblocks[0], Freeblock { offset: 300, size: 16, next: Some(420) });
+        assert_eq!(freeblocks[1].next, None);
+    }
+
+    #[test]
+    fn test_freeblock_cycle_rejected() {
+        let mut data = vec![0u8; 512];
+        data[300..304].copy_from_slice(&[0x01, 0x2c, 0x00, 0x10]); // points to itself
+        assert!(parse_freeblocks(&data, 300, 512).is_err());
+    }
+
+    #[test]
+    fn test_free_space_breakdown() {
+        let mut data = vec![0u8; 512];
+        // Leaf table page: freeblock at 480, 1 cell, content starts at 480, 2 fragmented bytes
+        data[0..8].copy_from_slice(&[0x0d, 0x01, 0xe0, 0x00, 0x01, 0x01, 0xe0, 0x02]);
+        data[8..10].copy_from_slice(&[0x01, 0xfb]); // cell at 507
+        data[480..484].copy_from_slice(&[0x00, 0x00, 0x00, 0x19]); // 480..505
+        data[507..512].copy_from_slice(&[0x03, 0x01, 0x02, 0x01, 0x2a]); // rowid 1, (42)
+
+        let page = parse_page(&data, 2, 512, 512, TextEncoding::Utf8).unwrap();
+        let free = page.free_space_breakdown.unwrap();
+        assert_eq!(free.unallocated, 470);
+        assert_eq!(free.freeblocks, 25);
+        assert_eq!(free.fragmented, 2);
+        assert!(free.is_consistent());
+        assert_eq!(page.free_space, 497);
+
+        // A fragment count that disagrees with the layout is caught
+        data[7] = 0x05;
+        let page = parse_page(&data, 2, 512, 512, TextEncoding::Utf8).unwrap();
+        assert_eq!(page.free_space_breakdown.unwrap().unaccounted, -3);
+    }
+}
@@ -0,0 +1,132 @@
+//! Pointer-map page location and parsing (auto-vacuum databases).
+
+use crate::error::{Result, SqliteVizError};
+use crate::model::{PtrmapEntry, PtrmapEntryType, PtrmapPage};
+
+/// Size of a single pointer-map entry: 1 type byte + 4-byte parent page number
+pub const PTRMAP_ENTRY_SIZE: usize = 5;
+
+/// Number of pages described by one pointer-map page
+fn entries_per_ptrmap_page(usable_size: u32) -> u32 {
+    usable_size / PTRMAP_ENTRY_SIZE as u32
+}
+
+/// Get the pointer-map page that holds the entry for `page_number`.
+/// Returns 0 for page 1, which has no entry.
+pub fn ptrmap_page_for(page_number: u32, usable_size: u32, lock_byte_page: u32) -> u32 {
+    if page_number < 2 {
+        return 0;
+    }
+    // Each pointer-map page is followed by the pages it describes
+    let pages_per_group = entries_per_ptrmap_page(usable_size) + 1;
+    let group = (page_number - 2) / pages_per_group;
+    let ptrmap_page = group * pages_per_group + 2;
+    if ptrmap_page == lock_byte_page {
+        ptrmap_page + 1
+    } else {
+        ptrmap_page
+    }
+}
+
+/// Check whether `page_number` is a pointer-map page
+pub fn is_ptrmap_page(page_number: u32, usable_size: u32, lock_byte_page: u32) -> bool {
+    page_number >= 2 && ptrmap_page_for(page_number, usable_size, lock_byte_page) == page_number
+}
+
+/// List all pointer-map pages in a database of `page_count` pages
+pub fn ptrmap_pages(page_count: u32, usable_size: u32, lock_byte_page: u32) -> Vec<u32> {
+    let pages_per_group = entries_per_ptrmap_page(usable_size) + 1;
+    let mut pages = Vec::new();
+    let mut group_start = 2;
+
+    while group_start <= page_count {
+        let page = ptrmap_page_for(group_start, usable_size, lock_byte_page);
+        if page <= page_count {
+            pages.push(page);
+        }
+        group_start += pages_per_group;
+    }
+
+    pages
+}
+
+/// Parse a pointer-map page. Entry `i` describes page `page_number + 1 + i`.
+pub fn parse_ptrmap_page(
+    page_data: &[u8],
+    page_number: u32,
+    usable_size: u32,
+    page_count: u32,
+    lock_byte_page: u32,
+) -> Result<PtrmapPage> {
+    let max_entries = entries_per_ptrmap_page(usable_size) as usize;
+    if page_data.len() < max_entries * PTRMAP_ENTRY_SIZE {
+        return Err(SqliteVizError::UnexpectedEof { context: "pointer-map page" });
+    }
+
+    let mut entries = Vec::new();
+    for i in 0..max_entries {
+        let described = page_number + 1 + i as u32;
+        if described > page_count {
+            break;
+        }
+        // The lock-byte page has a slot, but it is never used
+        if described == lock_byte_page {
+            continue;
+        }
+
+        let offset = i * PTRMAP_ENTRY_SIZE;
+        let entry_type = PtrmapEntryType::from_byte(page_data[offset]);
+        let parent_page = u32::from_be_bytes([
+            page_data[offset + 1],
+            page_data[offset + 2],
+            page_data[offset + 3],
+            page_data[offset + 4],
+        ]);
+
+        entries.push(PtrmapEntry {
+            page_number: described,
+            entry_type,
+            parent_page,
+        });
+    }
+
+    Ok(PtrmapPage { page_number, entries })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ptrmap_locations() {
+        // usable size 1024 -> 204 entries per pointer-map page
+        assert_eq!(ptrmap_page_for(1, 1024, 0), 0);
+        assert_eq!(ptrmap_page_for(3, 1024, 0), 2);
+        assert_eq!(ptrmap_page_for(206, 1024, 0), 2);
+        assert_eq!(ptrmap_page_for(207, 1024, 0), 207);
+        assert!(is_ptrmap_page(2, 1024, 0));
+        assert!(is_ptrmap_page(207, 1024, 0));
+        assert!(!is_ptrmap_page(208, 1024, 0));
+        assert_eq!(ptrmap_pages(500, 1024, 0), vec![2, 207, 412]);
+    }
+
+    #[test]
+    fn test_ptrmap_skips_lock_byte_page() {
+        assert_eq!(ptrmap_page_for(207, 1024, 207), 208);
+        assert!(!is_ptrmap_page(207, 1024, 207));
+    }
+
+    #[test]
+    fn test_parse_entries() {
+        let mut data = vec![0u8; 1024];
+        data[0] = 1; // page 3: root page
+        data[5] = 5; // page 4: B-tree page with parent 3
+        data[6..10].copy_from_slice(&3u32.to_be_bytes());
+
+        let page = parse_ptrmap_page(&data, 2, 1024, 4, 0).unwrap();
+        assert_eq!(page.entries.len(), 2);
+        assert_eq!(page.entries[0].entry_type, PtrmapEntryType::RootPage);
+        assert_eq!(page.entries[1].entry_type, PtrmapEntryType::BTree);
+        assert_eq!(page.entries[1].parent_page, 3);
+    }
+}
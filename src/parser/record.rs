@@ -1,9 +1,9 @@
 use crate::error::{Result, SqliteVizError};
-use crate::model::{Record, SerialType, Value};
+use crate::model::{Record, SerialType, TextEncoding, Value};
 use crate::parser::varint::parse_varint;
 
-/// Parse a record payload from cell data
-pub fn parse_record(data: &[u8]) -> Result<Record> {
+/// Parse a record payload from cell data, decoding TEXT values in the database encoding
+pub fn parse_record(data: &[u8], encoding: TextEncoding) -> Result<Record> {
     if data.is_empty() {
         return Err(SqliteVizError::UnexpectedEof { context: "record" });
     }
@@ -37,7 +37,7 @@ pub fn parse_record(data: &[u8]) -> Result<Record> {
         }
 
         let remaining = &data[value_offset..];
-        let (value, len) = parse_value(remaining, serial_type)?;
+        let (value, len) = parse_value(remaining, serial_type, encoding)?;
         values.push(value);
         value_offset += len;
     }
@@ -50,7 +50,7 @@ pub fn parse_record(data: &[u8]) -> Result<Record> {
 }
 
 /// Parse a single value based on its serial type
-fn parse_value(data: &[u8], serial_type: &SerialType) -> Result<(Value, usize)> {
+fn parse_value(data: &[u8], serial_type: &SerialType, encoding: TextEncoding) -> Result<(Value, usize)> {
     let size = serial_type.size();
 
     if data.len() < size {
@@ -131,10 +131,39 @@ fn parse_value(data: &[u8], serial_type: &SerialType) -> Result<(Value, usize)>
             Ok((Value::Blob(blob), *len))
         }
 
-        SerialType::Text(len) => {
-            let text = String::from_utf8_lossy(&data[..*len]).to_string();
-            Ok((Value::Text(text), *len))
+        SerialType::Text(len) => Ok((decode_text(&data[..*len], encoding), *len)),
+    }
+}
+
+/// Decode TEXT bytes in the given encoding.
+/// Invalid sequences are replaced with U+FFFD and the value is marked as malformed.
+pub fn decode_text(bytes: &[u8], encoding: TextEncoding) -> Value {
+    let (text, malformed) = match encoding {
+        TextEncoding::Utf8 => match std::str::from_utf8(bytes) {
+            Ok(s) => (s.to_string(), false),
+            Err(_) => (String::from_utf8_lossy(bytes).to_string(), true),
+        },
+        TextEncoding::Utf16Le | TextEncoding::Utf16Be => {
+            let units = bytes.chunks_exact(2).map(|pair| match encoding {
+                TextEncoding::Utf16Le => u16::from_le_bytes([pair[0], pair[1]]),
+                _ => u16::from_be_bytes([pair[0], pair[1]]),
+            });
+            // A trailing odd byte cannot be part of any UTF-16 code unit
+            let mut malformed = !bytes.len().is_multiple_of(2);
+            let text = char::decode_utf16(units)
+                .map(|c| c.unwrap_or_else(|_| {
+                    malformed = true;
+                    char::REPLACEMENT_CHARACTER
+                }))
+                .collect();
+            (text, malformed)
         }
+    };
+
+    if malformed {
+        Value::MalformedText(text)
+    } else {
+        Value::Text(text)
     }
 }
 
@@ -146,7 +175,7 @@ mod tests {
     fn test_parse_simple_record() {
         // A simple record with header size 2, one NULL column
         let data = [0x02, 0x00]; // header_size=2, serial_type=0 (NULL)
-        let record = parse_record(&data).unwrap();
+        let record = parse_record(&data, TextEncoding::Utf8).unwrap();
         assert_eq!(record.header_size, 2);
         assert_eq!(record.column_types.len(), 1);
         assert!(matches!(record.column_types[0], SerialType::Null));
@@ -159,7 +188,7 @@ mod tests {
         // Header: 03 (size=3), 0e (Blob(1)), 09 (One)
         // Values: 0x42 (the blob byte)
         let data = [0x03, 0x0e, 0x09, 0x42];
-        let record = parse_record(&data).unwrap();
+        let record = parse_record(&data, TextEncoding::Utf8).unwrap();
         assert_eq!(record.header_size, 3);
         assert_eq!(record.column_types.len(), 2);
         assert!(matches!(record.column_types[0], SerialType::Blob(1)));
@@ -175,7 +204,7 @@ mod tests {
         // Header: 03 (size=3), 0e (Blob(1)), 08 (Zero)
         // Values: 0x42 (the blob byte)
         let data = [0x03, 0x0e, 0x08, 0x42];
-        let record = parse_record(&data).unwrap();
+        let record = parse_record(&data, TextEncoding::Utf8).unwrap();
         assert_eq!(record.column_types.len(), 2);
         assert!(matches!(record.column_types[1], SerialType::Zero));
         assert!(matches!(record.values[1], Value::Integer(0)));
@@ -187,7 +216,7 @@ mod tests {
         // Header: 04 (size=4), 0e (Blob(1)), 08 (Zero), 09 (One)
         // Values: 0x42 (the blob byte)
         let data = [0x04, 0x0e, 0x08, 0x09, 0x42];
-        let record = parse_record(&data).unwrap();
+        let record = parse_record(&data, TextEncoding::Utf8).unwrap();
         assert_eq!(record.column_types.len(), 3);
         assert!(matches!(record.column_types[1], SerialType::Zero));
         assert!(matches!(record.column_types[2], SerialType::One));
@@ -201,7 +230,7 @@ mod tests {
         // Header: 04 (size=4), 00 (NULL), 08 (Zero), 09 (One)
         // No value bytes needed
         let data = [0x04, 0x00, 0x08, 0x09];
-        let record = parse_record(&data).unwrap();
+        let record = parse_record(&data, TextEncoding::Utf8).unwrap();
         assert_eq!(record.column_types.len(), 3);
         assert!(matches!(record.values[0], Value::Null));
         assert!(matches!(record.values[1], Value::Integer(0)));
@@ -214,7 +243,7 @@ mod tests {
         // Header: 04 (size=4), 09 (One), 0e (Blob(1)), 01 (Int8)
         // Values: 0x42 (blob), 0x07 (int8 = 7)
         let data = [0x04, 0x09, 0x0e, 0x01, 0x42, 0x07];
-        let record = parse_record(&data).unwrap();
+        let record = parse_record(&data, TextEncoding::Utf8).unwrap();
         assert_eq!(record.column_types.len(), 3);
         assert!(matches!(record.values[0], Value::Integer(1)));
         assert!(matches!(&record.values[1], Value::Blob(b) if b == &[0x42]));
@@ -227,10 +256,35 @@ mod tests {
         // Header: 03 (size=3), 0e (Blob(1)), 02 (Int16)
         // Values: 0x42 (blob), then truncated (missing second byte for Int16)
         let data = [0x03, 0x0e, 0x02, 0x42, 0x01];
-        let record = parse_record(&data).unwrap();
+        let record = parse_record(&data, TextEncoding::Utf8).unwrap();
         assert_eq!(record.column_types.len(), 2);
         assert!(matches!(&record.values[0], Value::Blob(b) if b == &[0x42]));
         // Int16 should be NULL because payload is truncated
         assert!(matches!(record.values[1], Value::Null));
     }
+
+    #[test]
+    fn test_decode_utf16_text() {
+        // "hé" in UTF-16LE and UTF-16BE
+        assert!(matches!(decode_text(&[0x68, 0x00, 0xe9, 0x00], TextEncoding::Utf16Le), Value::Text(s) if s == "hé"));
+        assert!(matches!(decode_text(&[0x00, 0x68, 0x00, 0xe9], TextEncoding::Utf16Be), Value::Text(s) if s == "hé"));
+    }
+
+    #[test]
+    fn test_decode_malformed_text() {
+        // Unpaired high surrogate
+        assert!(matches!(decode_text(&[0x00, 0xd8], TextEncoding::Utf16Le), Value::MalformedText(_)));
+        // Odd byte count
+        assert!(matches!(decode_text(&[0x68, 0x00, 0x69], TextEncoding::Utf16Le), Value::MalformedText(s) if s == "h"));
+        // Invalid UTF-8
+        assert!(matches!(decode_text(&[0x68, 0xff], TextEncoding::Utf8), Value::MalformedText(_)));
+    }
+
+    #[test]
+    fn test_parse_record_utf16() {
+        // Header: 02 (size=2), 11 (Text(2)); value "A" in UTF-16BE
+        let data = [0x02, 0x11, 0x00, 0x41];
+        let record = parse_record(&data, TextEncoding::Utf16Be).unwrap();
+        assert!(matches!(&record.values[0], Value::Text(s) if s == "A"));
+    }
 }
@@ -0,0 +1,209 @@
+//! WAL-index (-shm) parsing.
+//!
+//! Unlike the database and WAL, the WAL-index is stored in the native byte order
+//! of the host that wrote it.
+
+use crate::error::{Result, SqliteVizError};
+use crate::model::{
+    WalCheckpointInfo, WalIndex, WalIndexHashTable, WalIndexHeader, WAL_INDEX_VERSION,
+};
+
+use super::wal_checksum;
+
+/// Size of one WAL-index header copy
+pub const WAL_INDEX_HEADER_SIZE: usize = 48;
+/// Offset of the checkpoint info, after both header copies
+const CHECKPOINT_INFO_OFFSET: usize = 2 * WAL_INDEX_HEADER_SIZE;
+/// Size of the checkpoint info
+const CHECKPOINT_INFO_SIZE: usize = 40;
+/// Size of the headers and checkpoint info at the start of the first block
+const WAL_INDEX_PREFIX_SIZE: usize = CHECKPOINT_INFO_OFFSET + CHECKPOINT_INFO_SIZE;
+/// Size of each hash-table block
+pub const WAL_INDEX_BLOCK_SIZE: usize = 32768;
+/// Frames per hash-table block
+const HASHTABLE_NPAGE: usize = 4096;
+/// Frames in the first block, which also holds the headers
+const HASHTABLE_NPAGE_ONE: usize = HASHTABLE_NPAGE - WAL_INDEX_PREFIX_SIZE / 4;
+/// Hash slots per block
+const HASHTABLE_NSLOT: usize = 2 * HASHTABLE_NPAGE;
+/// Number of read marks in the checkpoint info
+const WAL_NREADER: usize = 5;
+
+/// Read a u32 in the byte order of the WAL-index
+fn read_u32(data: &[u8], offset: usize, big_endian: bool) -> u32 {
+    let bytes = [data[offset], data[offset + 1], data[offset + 2], data[offset + 3]];
+    if big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) }
+}
+
+/// Read a u16 in the byte order of the WAL-index
+fn read_u16(data: &[u8], offset: usize, big_endian: bool) -> u16 {
+    let bytes = [data[offset], data[offset + 1]];
+    if big_endian { u16::from_be_bytes(bytes) } else { u16::from_le_bytes(bytes) }
+}
+
+/// Detect the byte order of a WAL-index from its version field
+fn detect_byte_order(data: &[u8]) -> Option<bool> {
+    if data.len() < WAL_INDEX_PREFIX_SIZE {
+        return None;
+    }
+    if read_u32(data, 0, false) == WAL_INDEX_VERSION {
+        Some(false)
+    } else if read_u32(data, 0, true) == WAL_INDEX_VERSION {
+        Some(true)
+    } else {
+        None
+    }
+}
+
+/// Check if data looks like a WAL-index file
+pub fn is_wal_index_file(data: &[u8]) -> bool {
+    detect_byte_order(data).is_some()
+}
+
+/// Parse one copy of the WAL-index header
+pub fn parse_wal_index_header(data: &[u8], big_endian: bool) -> Result<WalIndexHeader> {
+    if data.len() < WAL_INDEX_HEADER_SIZE {
+        return Err(SqliteVizError::UnexpectedEof { context: "WAL-index header" });
+    }
+
+    // Page sizes of 65536 are stored as 1 to fit in 16 bits
+    let page_size = match read_u16(data, 14, big_endian) {
+        1 => 65536,
+        size => size as u32,
+    };
+
+    let checksum1 = read_u32(data, 40, big_endian);
+    let checksum2 = read_u32(data, 44, big_endian);
+    let checksum = wal_checksum(&data[..40], big_endian, (0, 0));
+
+    Ok(WalIndexHeader {
+        version: read_u32(data, 0, big_endian),
+        change_counter: read_u32(data, 8, big_endian),
+        is_initialized: data[12] != 0,
+        big_endian_checksum: data[13] != 0,
+        page_size,
+        max_frame: read_u32(data, 16, big_endian),
+        page_count: read_u32(data, 20, big_endian),
+        frame_checksum: (read_u32(data, 24, big_endian), read_u32(data, 28, big_endian)),
+        // Salts are copied byte-for-byte from the (big-endian) WAL header
+        salt1: read_u32(data, 32, true),
+        salt2: read_u32(data, 36, true),
+        checksum1,
+        checksum2,
+        checksum_valid: checksum == (checksum1, checksum2),
+    })
+}
+
+/// Parse the checkpoint info that follows the header copies
+fn parse_checkpoint_info(data: &[u8], big_endian: bool) -> WalCheckpointInfo {
+    let data = &data[CHECKPOINT_INFO_OFFSET..WAL_INDEX_PREFIX_SIZE];
+    WalCheckpointInfo {
+        backfill: read_u32(data, 0, big_endian),
+        read_marks: (0..WAL_NREADER).map(|i| read_u32(data, 4 + i * 4, big_endian)).collect(),
+        locks: data[24..32].to_vec(),
+        backfill_attempted: read_u32(data, 32, big_endian),
+    }
+}
+
+/// Parse the hash table of block `block`
+fn parse_hash_table(data: &[u8], block: usize, big_endian: bool) -> WalIndexHashTable {
+    let block_data = &data[block * WAL_INDEX_BLOCK_SIZE..(block + 1) * WAL_INDEX_BLOCK_SIZE];
+
+    // The first block shares its space with the headers, so it indexes fewer frames
+    let (pgno_offset, npage, first_frame) = if block == 0 {
+        (WAL_INDEX_PREFIX_SIZE, HASHTABLE_NPAGE_ONE, 1)
+    } else {
+        (0, HASHTABLE_NPAGE, HASHTABLE_NPAGE_ONE + (block - 1) * HASHTABLE_NPAGE + 1)
+    };
+    let hash_offset = pgno_offset + npage * 4;
+
+    WalIndexHashTable {
+        block,
+        first_frame: first_frame as u32,
+        page_numbers: (0..npage).map(|i| read_u32(block_data, pgno_offset + i * 4, big_endian)).collect(),
+        hash_slots: (0..HASHTABLE_NSLOT).map(|i| read_u16(block_data, hash_offset + i * 2, big_endian)).collect(),
+    }
+}
+
+/// Parse an entire WAL-index file
+pub fn parse_wal_index(data: &[u8], file_name: String) -> Result<WalIndex> {
+    let big_endian = detect_byte_order(data).ok_or(SqliteVizError::InvalidWalIndex)?;
+
+    let headers = [
+        parse_wal_index_header(&data[..WAL_INDEX_HEADER_SIZE], big_endian)?,
+        parse_wal_index_header(&data[WAL_INDEX_HEADER_SIZE..CHECKPOINT_INFO_OFFSET], big_endian)?,
+    ];
+    let checkpoint = parse_checkpoint_info(data, big_endian);
+
+    // A partially written trailing block is ignored
+    let hash_tables = (0..data.len() / WAL_INDEX_BLOCK_SIZE)
+        .map(|block| parse_hash_table(data, block, big_endian))
+        .collect();
+
+    Ok(WalIndex {
+        file_name,
+        big_endian,
+        headers,
+        checkpoint,
+        hash_tables,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_wal_index(big_endian: bool) -> Vec<u8> {
+        let put32 = |data: &mut Vec<u8>, offset: usize, value: u32| {
+            let bytes = if big_endian { value.to_be_bytes() } else { value.to_le_bytes() };
+            data[offset..offset + 4].copy_from_slice(&bytes);
+        };
+        let mut data = vec![0u8; WAL_INDEX_BLOCK_SIZE];
+
+        let mut header = vec![0u8; WAL_INDEX_HEADER_SIZE];
+        put32(&mut header, 0, WAL_INDEX_VERSION);
+        header[12] = 1;
+        header[14..16].copy_from_slice(&if big_endian { 4096u16.to_be_bytes() } else { 4096u16.to_le_bytes() });
+        put32(&mut header, 16, 2); // mxFrame
+        let sum = wal_checksum(&header[..40], big_endian, (0, 0));
+        put32(&mut header, 40, sum.0);
+        put32(&mut header, 44, sum.1);
+        data[..48].copy_from_slice(&header);
+        data[48..96].copy_from_slice(&header);
+
+        put32(&mut data, 96, 1); // nBackfill
+        put32(&mut data, 104, 2); // aReadMark[1]
+
+        // Frames 1 and 2 write pages 1 and 5
+        put32(&mut data, WAL_INDEX_PREFIX_SIZE, 1);
+        put32(&mut data, WAL_INDEX_PREFIX_SIZE + 4, 5);
+        data
+    }
+
+    #[test]
+    fn test_parse_wal_index() {
+        for big_endian in [false, true] {
+            let index = parse_wal_index(&build_wal_index(big_endian), "test-shm".to_string()).unwrap();
+            assert_eq!(index.big_endian, big_endian);
+            assert!(index.headers_match());
+            assert!(index.headers[0].checksum_valid);
+            assert_eq!(index.headers[0].page_size, 4096);
+            assert_eq!(index.headers[0].max_frame, 2);
+            assert_eq!(index.checkpoint.backfill, 1);
+            assert_eq!(index.checkpoint.read_marks[1], 2);
+            assert_eq!(index.page_for_frame(2), Some(5));
+            assert_eq!(index.page_for_frame(3), None);
+        }
+    }
+
+    #[test]
+    fn test_second_block_frame_numbers() {
+        let mut data = build_wal_index(false);
+        data.extend(vec![0u8; WAL_INDEX_BLOCK_SIZE]);
+        data[WAL_INDEX_BLOCK_SIZE..WAL_INDEX_BLOCK_SIZE + 4].copy_from_slice(&7u32.to_le_bytes());
+
+        let index = parse_wal_index(&data, "test-shm".to_string()).unwrap();
+        assert_eq!(index.hash_tables[1].first_frame, 4063);
+        assert_eq!(index.page_for_frame(4063), Some(7));
+    }
+}
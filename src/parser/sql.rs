@@ -0,0 +1,582 @@
+//! Minimal SQL parsing for the CREATE statements stored in sqlite_schema.
+
+use crate::error::{Result, SqliteVizError};
+use crate::model::{Affinity, ColumnDefinition, Generated, IndexColumn, IndexDefinition, TableDefinition, Value};
+
+/// A lexical token of a CREATE statement
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    /// Bare word: a keyword or an unquoted name
+    Word(String),
+    /// Name quoted with "", `` or []
+    Quoted(String),
+    /// String literal in ''
+    String(String),
+    /// Numeric literal
+    Number(String),
+    /// Any other single character (parentheses, commas, operators)
+    Punct(char),
+}
+
+impl Token {
+    /// True if this is the given keyword, ignoring case
+    pub fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, Token::Word(w) if w.eq_ignore_ascii_case(keyword))
+    }
+
+    /// The name this token spells, if it can be used as one
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Token::Word(s) | Token::Quoted(s) | Token::String(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+/// Split SQL into tokens, dropping whitespace and comments
+pub fn tokenize(sql: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            _ if c.is_whitespace() => i += 1,
+            '-' if chars.get(i + 1) == Some(&'-') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    i += 1;
+                }
+                i += 2;
+            }
+            '\'' | '"' | '`' | '[' => {
+                let close = if c == '[' { ']' } else { c };
+                let (text, end) = read_quoted(&chars, i + 1, close)?;
+                tokens.push(if c == '\'' { Token::String(text) } else { Token::Quoted(text) });
+                i = end;
+            }
+            _ if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(|d| d.is_ascii_digit())) => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '.') {
+                    // Exponent signs, as in 1e-5
+                    if matches!(chars[i], 'e' | 'E') && matches!(chars.get(i + 1), Some('+' | '-')) {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                tokens.push(Token::Number(chars[start..i].iter().collect()));
+            }
+            _ if c.is_alphanumeric() || c == '_' || c == '$' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '$') {
+                    i += 1;
+                }
+                tokens.push(Token::Word(chars[start..i].iter().collect()));
+            }
+            _ => {
+                tokens.push(Token::Punct(c));
+                i += 1;
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Read a quoted token starting after the opening quote; a doubled closing quote is an escaped quote.
+/// Returns the text and the position after the closing quote.
+fn read_quoted(chars: &[char], mut i: usize, close: char) -> Result<(String, usize)> {
+    let mut text = String::new();
+    loop {
+        match chars.get(i) {
+            None => return Err(SqliteVizError::SchemaError(format!("unterminated {} quote", close))),
+            Some(&c) if c == close => {
+                if close != ']' && chars.get(i + 1) == Some(&close) {
+                    text.push(close);
+                    i += 2;
+                } else {
+                    return Ok((text, i + 1));
+                }
+            }
+            Some(&c) => {
+                text.push(c);
+                i += 1;
+            }
+        }
+    }
+}
+
+/// Split the tokens between the parenthesis at `open` and its match at top-level commas.
+/// Returns the groups and the position after the closing parenthesis.
+pub fn split_parenthesized(tokens: &[Token], open: usize) -> Result<(Vec<&[Token]>, usize)> {
+    let mut groups = Vec::new();
+    let mut depth = 0;
+    let mut start = open + 1;
+    for (i, token) in tokens.iter().enumerate().skip(open) {
+        match token {
+            Token::Punct('(') => depth += 1,
+            Token::Punct(')') => {
+                depth -= 1;
+                if depth == 0 {
+                    groups.push(&tokens[start..i]);
+                    return Ok((groups, i + 1));
+                }
+            }
+            Token::Punct(',') if depth == 1 => {
+                groups.push(&tokens[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    Err(SqliteVizError::SchemaError("unbalanced parentheses".to_string()))
+}
+
+/// Keywords that start a table constraint rather than a column definition
+const TABLE_CONSTRAINTS: [&str; 5] = ["CONSTRAINT", "PRIMARY", "UNIQUE", "CHECK", "FOREIGN"];
+
+/// Keywords that end the declared type of a column and start its constraints
+const COLUMN_CONSTRAINTS: [&str; 11] = [
+    "CONSTRAINT", "PRIMARY", "NOT", "NULL", "UNIQUE", "CHECK", "DEFAULT", "COLLATE", "REFERENCES", "GENERATED", "AS",
+];
+
+/// Parse a CREATE TABLE statement
+pub fn parse_create_table(sql: &str) -> Result<TableDefinition> {
+    let tokens = tokenize(sql)?;
+    if !(tokens.first().is_some_and(|t| t.is_keyword("CREATE")) && tokens.iter().take(3).any(|t| t.is_keyword("TABLE"))) {
+        return Err(SqliteVizError::SchemaError("not a CREATE TABLE statement".to_string()));
+    }
+    // A virtual table's arguments belong to its module and are not column definitions
+    if tokens.iter().take(3).any(|t| t.is_keyword("VIRTUAL")) {
+        return Err(SqliteVizError::SchemaError("virtual tables have no column definitions".to_string()));
+    }
+    let open = tokens
+        .iter()
+        .position(|t| *t == Token::Punct('(') || t.is_keyword("AS"))
+        .filter(|&i| tokens[i] == Token::Punct('('))
+        .ok_or_else(|| SqliteVizError::SchemaError("CREATE TABLE has no column list".to_string()))?;
+    let name = open
+        .checked_sub(1)
+        .and_then(|i| tokens[i].name())
+        .ok_or_else(|| SqliteVizError::SchemaError("CREATE TABLE has no table name".to_string()))?;
+
+    let (definitions, close) = split_parenthesized(&tokens, open)?;
+    let mut columns = Vec::new();
+    let mut primary_key = Vec::new();
+    // PRIMARY KEY (true) and UNIQUE (false) constraints in declaration order
+    let mut constraints = Vec::new();
+    let mut column_key = false;
+
+    for definition in definitions {
+        let Some(first) = definition.first() else {
+            return Err(SqliteVizError::SchemaError("empty column definition".to_string()));
+        };
+        if TABLE_CONSTRAINTS.iter().any(|k| first.is_keyword(k)) {
+            let mut rest = definition;
+            if first.is_keyword("CONSTRAINT") {
+                rest = rest.get(2..).unwrap_or_default();
+            }
+            let is_primary = rest.first().is_some_and(|t| t.is_keyword("PRIMARY"));
+            if is_primary || rest.first().is_some_and(|t| t.is_keyword("UNIQUE")) {
+                let open = rest
+                    .iter()
+                    .position(|t| *t == Token::Punct('('))
+                    .ok_or_else(|| SqliteVizError::SchemaError("key constraint has no column list".to_string()))?;
+                let key = split_parenthesized(rest, open)?
+                    .0
+                    .into_iter()
+                    .map(parse_indexed_column)
+                    .collect::<Result<Vec<_>>>()?;
+                if is_primary {
+                    primary_key = key.clone();
+                }
+                constraints.push((is_primary, key));
+            }
+            continue;
+        }
+
+        let name = first
+            .name()
+            .ok_or_else(|| SqliteVizError::SchemaError(format!("unexpected {:?} in column list", first)))?;
+        let rest = &definition[1..];
+        let type_end = rest
+            .iter()
+            .position(|t| COLUMN_CONSTRAINTS.iter().any(|k| t.is_keyword(k)))
+            .unwrap_or(rest.len());
+        let declared_type = (type_end > 0).then(|| sql_text(&rest[..type_end]));
+
+        let mut column = ColumnDefinition {
+            name: name.to_string(),
+            affinity: Affinity::from_declared_type(declared_type.as_deref()),
+            declared_type,
+            primary_key: false,
+            collation: None,
+            default: None,
+            generated: None,
+        };
+        let mut depth = 0;
+        for (i, token) in rest.iter().enumerate().skip(type_end) {
+            match token {
+                Token::Punct('(') => depth += 1,
+                Token::Punct(')') => depth -= 1,
+                _ if depth > 0 => {}
+                _ if token.is_keyword("PRIMARY") => {
+                    column.primary_key = true;
+                    column_key = true;
+                    let key = IndexColumn {
+                        name: column.name.clone(),
+                        expression: false,
+                        collation: None,
+                        descending: rest.get(i + 2).is_some_and(|t| t.is_keyword("DESC")),
+                    };
+                    primary_key = vec![key.clone()];
+                    constraints.push((true, vec![key]));
+                }
+                _ if token.is_keyword("UNIQUE") => {
+                    let key = IndexColumn { name: column.name.clone(), expression: false, collation: None, descending: false };
+                    constraints.push((false, vec![key]));
+                }
+                _ if token.is_keyword("COLLATE") => {
+                    column.collation = rest.get(i + 1).and_then(Token::name).map(str::to_string);
+                }
+                // GENERATED ALWAYS AS (expr) [VIRTUAL | STORED], or just AS (expr)
+                _ if token.is_keyword("AS") => column.generated = Some(Generated::Virtual),
+                _ if token.is_keyword("STORED") => column.generated = Some(Generated::Stored),
+                _ if token.is_keyword("DEFAULT") => {
+                    // A parenthesized expression, a signed number, a blob or a single literal
+                    let end = match rest.get(i + 1) {
+                        Some(Token::Punct('(')) => split_parenthesized(rest, i + 1)?.1,
+                        Some(Token::Punct('+' | '-')) => i + 3,
+                        Some(x) if x.is_keyword("X") && matches!(rest.get(i + 2), Some(Token::String(_))) => i + 3,
+                        _ => i + 2,
+                    };
+                    column.default = rest.get(i + 1..end.min(rest.len())).map(sql_text).filter(|text| !text.is_empty());
+                }
+                _ => {}
+            }
+        }
+        columns.push(column);
+    }
+
+    for key in &primary_key {
+        if let Some(column) = columns.iter_mut().find(|c| c.name.eq_ignore_ascii_case(&key.name)) {
+            column.primary_key = true;
+        }
+    }
+
+    let options = &tokens[close..];
+    let without_rowid = options.windows(2).any(|w| w[0].is_keyword("WITHOUT") && w[1].is_keyword("ROWID"));
+
+    // An INTEGER PRIMARY KEY is the rowid itself, except for the quirk that a column
+    // constraint PRIMARY KEY DESC is not
+    let rowid_alias = match primary_key.as_slice() {
+        [key] if !(without_rowid || column_key && key.descending) => columns.iter().position(|c| {
+            c.name.eq_ignore_ascii_case(&key.name) && c.declared_type.as_deref().is_some_and(|t| t.eq_ignore_ascii_case("INTEGER"))
+        }),
+        _ => None,
+    };
+
+    // Each constraint gets an automatic index unless it is the rowid or repeats an earlier key
+    let mut autoindexes: Vec<Vec<IndexColumn>> = Vec::new();
+    for (is_primary, key) in constraints {
+        if is_primary && rowid_alias.is_some() {
+            continue;
+        }
+        if !autoindexes.iter().any(|existing| same_key(existing, &key)) {
+            autoindexes.push(key);
+        }
+    }
+
+    Ok(TableDefinition {
+        name: name.to_string(),
+        columns,
+        primary_key,
+        rowid_alias,
+        autoindexes,
+        without_rowid,
+    })
+}
+
+/// Parse a CREATE INDEX statement
+pub fn parse_create_index(sql: &str) -> Result<IndexDefinition> {
+    let tokens = tokenize(sql)?;
+    if !(tokens.first().is_some_and(|t| t.is_keyword("CREATE")) && tokens.iter().take(3).any(|t| t.is_keyword("INDEX"))) {
+        return Err(SqliteVizError::SchemaError("not a CREATE INDEX statement".to_string()));
+    }
+    let on = tokens
+        .iter()
+        .position(|t| t.is_keyword("ON"))
+        .ok_or_else(|| SqliteVizError::SchemaError("CREATE INDEX has no ON clause".to_string()))?;
+    let name = on.checked_sub(1).and_then(|i| tokens[i].name());
+    let table = tokens.get(on + 1).and_then(Token::name);
+    let (Some(name), Some(table)) = (name, table) else {
+        return Err(SqliteVizError::SchemaError("CREATE INDEX has no index or table name".to_string()));
+    };
+    let open = tokens
+        .iter()
+        .skip(on)
+        .position(|t| *t == Token::Punct('('))
+        .map(|i| on + i)
+        .ok_or_else(|| SqliteVizError::SchemaError("CREATE INDEX has no column list".to_string()))?;
+
+    let (groups, close) = split_parenthesized(&tokens, open)?;
+    Ok(IndexDefinition {
+        name: name.to_string(),
+        table: table.to_string(),
+        unique: tokens[1].is_keyword("UNIQUE"),
+        columns: groups.into_iter().map(parse_indexed_column).collect::<Result<_>>()?,
+        partial: tokens.get(close).is_some_and(|t| t.is_keyword("WHERE")),
+    })
+}
+
+/// Value of a constant DEFAULT expression, converted to the column's affinity as SQLite
+/// does when it reads a record written before the column was added. None if the
+/// expression is not a literal, such as CURRENT_TIMESTAMP.
+pub fn default_value(sql: &str, affinity: Affinity) -> Option<Value> {
+    let tokens = tokenize(sql).ok()?;
+    let mut tokens = tokens.as_slice();
+    while let [Token::Punct('('), inner @ .., Token::Punct(')')] = tokens {
+        tokens = inner;
+    }
+    let value = match tokens {
+        [Token::Word(w)] if w.eq_ignore_ascii_case("NULL") => Value::Null,
+        [Token::Word(w)] if w.eq_ignore_ascii_case("TRUE") => Value::Integer(1),
+        [Token::Word(w)] if w.eq_ignore_ascii_case("FALSE") => Value::Integer(0),
+        [Token::String(s)] => Value::Text(s.clone()),
+        [Token::Word(x), Token::String(hex)] if x.eq_ignore_ascii_case("x") && hex.len() % 2 == 0 => Value::Blob(
+            (0..hex.len())
+                .step_by(2)
+                .map(|i| hex.get(i..i + 2).and_then(|byte| u8::from_str_radix(byte, 16).ok()))
+                .collect::<Option<_>>()?,
+        ),
+        [Token::Number(n)] => return number_literal(n, false, affinity),
+        [Token::Punct(sign @ ('+' | '-')), Token::Number(n)] => return number_literal(n, *sign == '-', affinity),
+        _ => return None,
+    };
+    Some(affinity.apply(value))
+}
+
+/// A numeric literal; a column with TEXT affinity keeps the literal as written
+fn number_literal(text: &str, negative: bool, affinity: Affinity) -> Option<Value> {
+    if affinity == Affinity::Text {
+        return Some(Value::Text(if negative { format!("-{}", text) } else { text.to_string() }));
+    }
+    let sign = if negative { -1 } else { 1 };
+    let value = match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => Value::Integer(sign * i64::from_str_radix(hex, 16).ok()?),
+        None if text.contains(['.', 'e', 'E']) => Value::Real(sign as f64 * text.parse::<f64>().ok()?),
+        None => match text.parse::<i64>() {
+            Ok(i) => Value::Integer(sign * i),
+            Err(_) => Value::Real(sign as f64 * text.parse::<f64>().ok()?),
+        },
+    };
+    Some(affinity.apply(value))
+}
+
+/// Column names of a CREATE TABLE statement, in declaration order
+pub fn parse_column_names(sql: &str) -> Result<Vec<String>> {
+    Ok(parse_create_table(sql)?.columns.into_iter().map(|c| c.name).collect())
+}
+
+/// Parse one key column: a name or expression, then an optional COLLATE and ASC or DESC
+fn parse_indexed_column(mut tokens: &[Token]) -> Result<IndexColumn> {
+    let descending = match tokens.last() {
+        Some(t) if t.is_keyword("DESC") || t.is_keyword("ASC") => {
+            let descending = t.is_keyword("DESC");
+            tokens = &tokens[..tokens.len() - 1];
+            descending
+        }
+        _ => false,
+    };
+    let mut collation = None;
+    if tokens.len() >= 2 && tokens[tokens.len() - 2].is_keyword("COLLATE") {
+        collation = tokens[tokens.len() - 1].name().map(str::to_string);
+        tokens = &tokens[..tokens.len() - 2];
+    }
+    let (name, expression) = match tokens {
+        [] => return Err(SqliteVizError::SchemaError("empty key column".to_string())),
+        [token] if token.name().is_some() => (token.name().unwrap_or_default().to_string(), false),
+        _ => (sql_text(tokens), true),
+    };
+    Ok(IndexColumn { name, expression, collation, descending })
+}
+
+/// Whether two keys have the same columns with the same collations
+fn same_key(a: &[IndexColumn], b: &[IndexColumn]) -> bool {
+    a.len() == b.len()
+        && a.iter().zip(b).all(|(x, y)| {
+            x.name.eq_ignore_ascii_case(&y.name)
+                && x.collation.as_deref().map(str::to_ascii_uppercase) == y.collation.as_deref().map(str::to_ascii_uppercase)
+        })
+}
+
+/// Write tokens back as SQL text, e.g. for an index expression or a declared type
+fn sql_text(tokens: &[Token]) -> String {
+    let mut text = String::new();
+    let mut previous_word = false;
+    for token in tokens {
+        let word = !matches!(token, Token::Punct(_));
+        if word && previous_word {
+            text.push(' ');
+        }
+        match token {
+            Token::Word(s) | Token::Number(s) => text.push_str(s),
+            Token::Quoted(s) => text.push_str(&format!("\"{}\"", s.replace('"', "\"\""))),
+            Token::String(s) => text.push_str(&format!("'{}'", s.replace('\'', "''"))),
+            Token::Punct(c) => text.push(*c),
+        }
+        if *token == Token::Punct(',') {
+            text.push(' ');
+        }
+        previous_word = word;
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize_quotes_and_comments() {
+        let tokens = tokenize("CREATE TABLE \"a \"\"b\" ([c], `d`) -- trailing\n/* x */ 'it''s' 1.5e-3").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Word("CREATE".into()),
+                Token::Word("TABLE".into()),
+                Token::Quoted("a \"b".into()),
+                Token::Punct('('),
+                Token::Quoted("c".into()),
+                Token::Punct(','),
+                Token::Quoted("d".into()),
+                Token::Punct(')'),
+                Token::String("it's".into()),
+                Token::Number("1.5e-3".into()),
+            ]
+        );
+        assert!(tokenize("CREATE TABLE 'x").is_err());
+    }
+
+    #[test]
+    fn test_parse_column_names() {
+        let sql = "CREATE TABLE IF NOT EXISTS main.t(\n  id INTEGER PRIMARY KEY,\n  \"first name\" TEXT DEFAULT ('a,b'),\n  \
+                   price NUMERIC(10, 2) CHECK (price > 0),\n  CONSTRAINT u UNIQUE (price)\n) WITHOUT ROWID";
+        assert_eq!(parse_column_names(sql).unwrap(), vec!["id", "first name", "price"]);
+
+        assert_eq!(parse_column_names("CREATE TABLE t(a, b, PRIMARY KEY (a))").unwrap(), vec!["a", "b"]);
+        assert!(parse_column_names("CREATE TABLE t AS SELECT (1) AS x").is_err());
+    }
+
+    #[test]
+    fn test_parse_create_table() {
+        let table = parse_create_table(
+            "CREATE TABLE \"order items\"(id INTEGER PRIMARY KEY, sku VARCHAR(20) NOT NULL COLLATE nocase UNIQUE, \
+             price NUMERIC(10, 2) CHECK (price COLLATE rtrim > 0), note, UNIQUE (sku))",
+        )
+        .unwrap();
+        assert_eq!(table.name, "order items");
+        let types: Vec<_> = table.columns.iter().map(|c| (c.declared_type.as_deref(), c.affinity)).collect();
+        assert_eq!(
+            types,
+            vec![
+                (Some("INTEGER"), Affinity::Integer),
+                (Some("VARCHAR(20)"), Affinity::Text),
+                (Some("NUMERIC(10, 2)"), Affinity::Numeric),
+                (None, Affinity::Blob),
+            ]
+        );
+        let collations: Vec<_> = table.columns.iter().map(|c| c.collation.as_deref()).collect();
+        assert_eq!(collations, vec![None, Some("nocase"), None, None]);
+        assert_eq!(table.rowid_alias, Some(0));
+        assert!(!table.without_rowid);
+        // The duplicate UNIQUE (sku) gets no index of its own
+        assert_eq!(table.autoindexes.len(), 1);
+        assert_eq!(table.autoindexes[0][0].name, "sku");
+
+        let table = parse_create_table("CREATE TABLE t(a INT, b TEXT, c, PRIMARY KEY (a, c DESC)) WITHOUT ROWID").unwrap();
+        assert!(table.without_rowid);
+        assert_eq!(table.rowid_alias, None);
+        let key: Vec<_> = table.primary_key.iter().map(|k| (k.name.as_str(), k.descending)).collect();
+        assert_eq!(key, vec![("a", false), ("c", true)]);
+        let primary: Vec<_> = table.columns.iter().map(|c| c.primary_key).collect();
+        assert_eq!(primary, vec![true, false, true]);
+
+        assert_eq!(parse_create_table("CREATE TABLE t(id INTEGER PRIMARY KEY DESC)").unwrap().rowid_alias, None);
+        assert_eq!(parse_create_table("CREATE TABLE t(id int PRIMARY KEY)").unwrap().rowid_alias, None);
+
+        let virtual_table = parse_create_table("CREATE VIRTUAL TABLE v USING fts5(a, b)");
+        assert!(matches!(virtual_table, Err(SqliteVizError::SchemaError(_))));
+    }
+
+    #[test]
+    fn test_parse_column_defaults() {
+        let table = parse_create_table(
+            "CREATE TABLE t(a DEFAULT -1, b TEXT NOT NULL DEFAULT 'x''y' COLLATE nocase, c REAL DEFAULT (2 * 3), d, \
+             e DEFAULT CURRENT_TIMESTAMP, f BLOB DEFAULT X'0a', n INTEGER DEFAULT (0) NOT NULL, \
+             g TEXT DEFAULT ('x') COLLATE nocase)",
+        )
+        .unwrap();
+        let defaults: Vec<_> = table.columns.iter().map(|c| c.default.as_deref()).collect();
+        assert_eq!(
+            defaults,
+            vec![Some("-1"), Some("'x''y'"), Some("(2*3)"), None, Some("CURRENT_TIMESTAMP"), Some("X '0a'"), Some("(0)"), Some("('x')")]
+        );
+        assert_eq!(table.columns[1].collation.as_deref(), Some("nocase"));
+        assert_eq!(table.columns[7].collation.as_deref(), Some("nocase"));
+    }
+
+    #[test]
+    fn test_parse_generated_columns() {
+        let table = parse_create_table(
+            "CREATE TABLE g(id INTEGER PRIMARY KEY, a INT, v INT GENERATED ALWAYS AS (a*2) VIRTUAL, b TEXT, \
+             s INT AS (CAST(a AS TEXT)) STORED, w AS (a+1))",
+        )
+        .unwrap();
+        let generated: Vec<_> = table.columns.iter().map(|c| c.generated).collect();
+        assert_eq!(generated, vec![None, None, Some(Generated::Virtual), None, Some(Generated::Stored), Some(Generated::Virtual)]);
+        let stored: Vec<_> = table.columns.iter().filter(|c| c.is_stored()).map(|c| c.name.as_str()).collect();
+        assert_eq!(stored, vec!["id", "a", "b", "s"]);
+        assert_eq!(table.columns[2].affinity, Affinity::Integer);
+    }
+
+    #[test]
+    fn test_default_value() {
+        assert_eq!(default_value("-1", Affinity::Blob), Some(Value::Integer(-1)));
+        assert_eq!(default_value("1.50", Affinity::Text), Some(Value::Text("1.50".into())));
+        assert_eq!(default_value("'7'", Affinity::Integer), Some(Value::Integer(7)));
+        assert_eq!(default_value("' 2.5 '", Affinity::Numeric), Some(Value::Real(2.5)));
+        assert_eq!(default_value("'abc'", Affinity::Real), Some(Value::Text("abc".into())));
+        assert_eq!(default_value("3", Affinity::Real), Some(Value::Real(3.0)));
+        assert_eq!(default_value("4.0", Affinity::Integer), Some(Value::Integer(4)));
+        assert_eq!(default_value("0x10", Affinity::Integer), Some(Value::Integer(16)));
+        assert_eq!(default_value("(NULL)", Affinity::Text), Some(Value::Null));
+        assert_eq!(default_value("x'00ff'", Affinity::Blob), Some(Value::Blob(vec![0, 255])));
+        assert_eq!(default_value("TRUE", Affinity::Text), Some(Value::Text("1".into())));
+        assert_eq!(default_value("CURRENT_TIMESTAMP", Affinity::Text), None);
+        assert_eq!(default_value("(2*3)", Affinity::Integer), None);
+    }
+
+    #[test]
+    fn test_parse_create_index() {
+        let index =
+            parse_create_index("CREATE UNIQUE INDEX IF NOT EXISTS main.i ON t(a DESC, \"b\" COLLATE RTRIM ASC, lower(c) COLLATE binary) WHERE a > 0")
+                .unwrap();
+        assert_eq!((index.name.as_str(), index.table.as_str(), index.unique, index.partial), ("i", "t", true, true));
+        assert_eq!(
+            index.columns,
+            vec![
+                IndexColumn { name: "a".into(), expression: false, collation: None, descending: true },
+                IndexColumn { name: "b".into(), expression: false, collation: Some("RTRIM".into()), descending: false },
+                IndexColumn { name: "lower(c)".into(), expression: true, collation: Some("binary".into()), descending: false },
+            ]
+        );
+        assert!(parse_create_index("CREATE TABLE t(a)").is_err());
+    }
+}
@@ -36,6 +36,25 @@ pub fn parse_signed_varint(data: &[u8]) -> Result<(i64, usize)> {
     Ok((value as i64, len))
 }
 
+/// Encode a value as a SQLite varint
+pub fn encode_varint(value: u64) -> Vec<u8> {
+    // Values that need the full 64 bits use 8 bytes of 7 bits plus a final 8-bit byte
+    if value > 0x00ff_ffff_ffff_ffff {
+        let mut bytes: Vec<u8> = (0..8).map(|i| ((value >> (57 - i * 7)) as u8 & 0x7f) | 0x80).collect();
+        bytes.push(value as u8);
+        return bytes;
+    }
+
+    let mut bytes = vec![(value & 0x7f) as u8];
+    let mut rest = value >> 7;
+    while rest > 0 {
+        bytes.push((rest & 0x7f) as u8 | 0x80);
+        rest >>= 7;
+    }
+    bytes.reverse();
+    bytes
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -56,6 +75,15 @@ mod tests {
         assert_eq!(parse_varint(&[0x82, 0x2C]).unwrap(), (300, 2));
     }
 
+    #[test]
+    fn test_encode_round_trip() {
+        for value in [0, 1, 127, 128, 300, 0xffff_ffff, 0x00ff_ffff_ffff_ffff, u64::MAX] {
+            let bytes = encode_varint(value);
+            assert_eq!(parse_varint(&bytes).unwrap(), (value, bytes.len()));
+        }
+        assert_eq!(encode_varint(300), vec![0x82, 0x2C]);
+    }
+
     #[test]
     fn test_empty_input() {
         assert!(parse_varint(&[]).is_err());
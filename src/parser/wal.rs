@@ -2,8 +2,10 @@
 
 use crate::error::{Result, SqliteVizError};
 use crate::model::{
-    WalFile, WalFrame, WalFrameHeader, WalHeader, WAL_MAGIC_BIG_ENDIAN, WAL_MAGIC_LITTLE_ENDIAN,
+    TextEncoding, WalFile, WalFrame, WalFrameHeader, WalHeader, WAL_MAGIC_BIG_ENDIAN,
+    WAL_MAGIC_LITTLE_ENDIAN,
 };
+use crate::parser::header::parse_database_header;
 use crate::parser::page::parse_page;
 
 /// WAL header size in bytes
@@ -73,6 +75,45 @@ pub fn parse_wal_frame_header(data: &[u8]) -> Result<WalFrameHeader> {
     })
 }
 
+/// Compute SQLite's WAL checksum over `data` (a multiple of 8 bytes),
+/// continuing from the running checksum `initial`.
+pub fn wal_checksum(data: &[u8], big_endian: bool, initial: (u32, u32)) -> (u32, u32) {
+    let (mut s1, mut s2) = initial;
+    for chunk in data.chunks_exact(8) {
+        let (x0, x1) = if big_endian {
+            (
+                u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]),
+                u32::from_be_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]),
+            )
+        } else {
+            (
+                u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]),
+                u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]),
+            )
+        };
+        s1 = s1.wrapping_add(x0).wrapping_add(s2);
+        s2 = s2.wrapping_add(x1).wrapping_add(s1);
+    }
+    (s1, s2)
+}
+
+/// Find the text encoding from the database header in any frame holding page 1.
+/// Falls back to UTF-8 when the WAL does not contain page 1.
+fn detect_text_encoding(data: &[u8], frame_size: usize) -> TextEncoding {
+    let mut offset = WAL_HEADER_SIZE;
+    while offset + frame_size <= data.len() {
+        let is_page1 = u32::from_be_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]]) == 1;
+        if is_page1 {
+            let page_data = &data[offset + WAL_FRAME_HEADER_SIZE..offset + frame_size];
+            if let Ok(db_header) = parse_database_header(page_data) {
+                return db_header.text_encoding;
+            }
+        }
+        offset += frame_size;
+    }
+    TextEncoding::Utf8
+}
+
 /// Parse an entire WAL file
 pub fn parse_wal_file(data: &[u8], file_name: String) -> Result<WalFile> {
     let header = parse_wal_header(data)?;
@@ -81,6 +122,12 @@ pub fn parse_wal_file(data: &[u8], file_name: String) -> Result<WalFile> {
 
     // For WAL frames, we assume usable_size = page_size (no reserved bytes)
     let usable_size = header.page_size;
+    let encoding = detect_text_encoding(data, frame_size);
+
+    // The header checksum covers the first 24 bytes; frame checksums continue from it
+    let big_endian = header.is_big_endian();
+    let mut checksum = wal_checksum(&data[..24], big_endian, (0, 0));
+    let header_checksum_valid = checksum == (header.checksum1, header.checksum2);
 
     let mut frames = Vec::new();
     let mut offset = WAL_HEADER_SIZE;
@@ -98,6 +145,11 @@ pub fn parse_wal_file(data: &[u8], file_name: String) -> Result<WalFile> {
         let page_data_start = offset + WAL_FRAME_HEADER_SIZE;
         let page_data = &data[page_data_start..page_data_start + page_size];
 
+        // Frame checksum covers the first 8 bytes of the frame header and the page data
+        checksum = wal_checksum(&data[offset..offset + 8], big_endian, checksum);
+        checksum = wal_checksum(page_data, big_endian, checksum);
+        let checksum_valid = checksum == (frame_header.checksum1, frame_header.checksum2);
+
         // Parse the page content
         // Use the DB page number from the frame header for correct page 1 handling
         let page = parse_page(
@@ -105,6 +157,7 @@ pub fn parse_wal_file(data: &[u8], file_name: String) -> Result<WalFile> {
             frame_header.page_number,
             header.page_size,
             usable_size,
+            encoding,
         )
         .ok(); // Convert errors to None since some frames may have unparseable pages
 
@@ -113,6 +166,7 @@ pub fn parse_wal_file(data: &[u8], file_name: String) -> Result<WalFile> {
             header: frame_header,
             page,
             raw_page_data: page_data.to_vec(),
+            checksum_valid,
         });
 
         offset += frame_size;
@@ -123,5 +177,60 @@ pub fn parse_wal_file(data: &[u8], file_name: String) -> Result<WalFile> {
         header,
         frames,
         file_name,
+        header_checksum_valid,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_wal(big_endian: bool) -> Vec<u8> {
+        let magic = if big_endian { WAL_MAGIC_BIG_ENDIAN } else { WAL_MAGIC_LITTLE_ENDIAN };
+        let page_size = 512u32;
+        let mut data = Vec::new();
+        for word in [magic, 3007000, page_size, 0, 0x1111, 0x2222] {
+            data.extend_from_slice(&word.to_be_bytes());
+        }
+        let header_sum = wal_checksum(&data, big_endian, (0, 0));
+        data.extend_from_slice(&header_sum.0.to_be_bytes());
+        data.extend_from_slice(&header_sum.1.to_be_bytes());
+
+        // One commit frame for page 2 (an overflow-like page of zeros)
+        let mut frame = Vec::new();
+        for word in [2u32, 2, 0x1111, 0x2222] {
+            frame.extend_from_slice(&word.to_be_bytes());
+        }
+        let page = vec![0u8; page_size as usize];
+        let sum = wal_checksum(&frame[..8], big_endian, header_sum);
+        let sum = wal_checksum(&page, big_endian, sum);
+        frame.extend_from_slice(&sum.0.to_be_bytes());
+        frame.extend_from_slice(&sum.1.to_be_bytes());
+        frame.extend_from_slice(&page);
+        data.extend_from_slice(&frame);
+        data
+    }
+
+    #[test]
+    fn test_valid_checksums() {
+        for big_endian in [false, true] {
+            let wal = parse_wal_file(&build_wal(big_endian), "test-wal".to_string()).unwrap();
+            assert!(wal.header_checksum_valid);
+            assert!(wal.frames[0].checksum_valid);
+            assert_eq!(wal.first_invalid_frame(), None);
+            assert_eq!(wal.last_valid_commit_frame(), Some(0));
+        }
+    }
+
+    #[test]
+    fn test_corrupted_frame_checksum() {
+        let mut data = build_wal(false);
+        let last = data.len() - 1;
+        data[last] ^= 0xff;
+        let wal = parse_wal_file(&data, "test-wal".to_string()).unwrap();
+        assert!(wal.header_checksum_valid);
+        assert!(!wal.frames[0].checksum_valid);
+        assert_eq!(wal.first_invalid_frame(), Some(0));
+        assert_eq!(wal.last_valid_commit_frame(), None);
+    }
+}
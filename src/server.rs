@@ -0,0 +1,205 @@
+//! Local HTTP server that serves the visualization with pages fetched on demand,
+//! for databases too large to inline into a single HTML file.
+//!
+//! Endpoints:
+//! - `GET /` - the visualization, with only the header and schema inlined
+//! - `GET /api/header` - the database header
+//! - `GET /api/schema` - tables and indexes
+//! - `GET /api/btrees` - name, type and root page of every B-tree
+//! - `GET /api/node/<n>?depth=<d>` - one B-tree page: its fill and child page numbers, so
+//!   the visualization walks a tree a level at a time as nodes are expanded
+//! - `GET /api/page/<n>?tree=<name>` - one parsed page, with record values labeled with
+//!   the columns of the named B-tree
+//! - `GET /api/page/<n>/raw` - the page's bytes
+//! - `GET /api/page-map` - the owner of every page (walks the whole file)
+
+use std::io::Cursor;
+
+use serde::Serialize;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::analyzer::{btree_node, row_layout};
+use crate::error::{Result, SqliteVizError};
+use crate::visualization::{render_html, VizBTree, VizBTreeNode, VizPage, VizPageOwnership, VizSchema};
+use crate::Database;
+
+type HttpResponse = Response<Cursor<Vec<u8>>>;
+
+/// Serve the visualization of a database on `addr` until the process is stopped
+pub fn serve(db: &Database, addr: &str) -> Result<()> {
+    let server = Server::http(addr).map_err(|e| SqliteVizError::Io(std::io::Error::other(e)))?;
+    println!("Serving {} at http://{}/ (Ctrl+C to stop)", db.file_name(), server.server_addr());
+
+    for request in server.incoming_requests() {
+        let response = handle(db, &request);
+        if let Err(e) = request.respond(response) {
+            eprintln!("Failed to send response: {}", e);
+        }
+    }
+    Ok(())
+}
+
+fn handle(db: &Database, request: &Request) -> HttpResponse {
+    if *request.method() != Method::Get {
+        return error_response(405, "only GET is supported");
+    }
+
+    let (path, query) = request.url().split_once('?').unwrap_or((request.url(), ""));
+    let segments: Vec<String> = path
+        .trim_matches('/')
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(percent_decode)
+        .collect();
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+
+    let result = match segments.as_slice() {
+        [] => db
+            .generate_viz_summary()
+            .and_then(|data| render_html(&data))
+            .map(|html| with_content_type(Response::from_string(html), "text/html; charset=utf-8")),
+        ["api", "header"] => json_response(&db.header),
+        ["api", "schema"] => db
+            .parse_schema()
+            .and_then(|schema| json_response(&VizSchema::from_schema(&schema, &db.statistics(&schema)))),
+        ["api", "btrees"] => db.parse_schema().and_then(|schema| {
+            let summaries: Vec<VizBTree> = db
+                .btree_roots(&schema)
+                .iter()
+                .map(|(name, tree_type, root_page)| VizBTree::summary(name, *tree_type, *root_page))
+                .collect();
+            json_response(&summaries)
+        }),
+        ["api", "node", number] => match parse_page_number(number, db.page_count()) {
+            Ok(page_number) => db.parse_page(page_number).and_then(|page| {
+                if !page.page_type.is_btree() {
+                    return Ok(error_response(
+                        422,
+                        &format!("page {} is a {:?} page, not a B-tree page", page_number, page.page_type),
+                    ));
+                }
+                let depth = query_param(query, "depth").and_then(|d| d.parse().ok()).unwrap_or(0);
+                json_response(&VizBTreeNode::from_node(&btree_node(&page, depth, None)))
+            }),
+            Err(response) => Ok(response),
+        },
+        ["api", "page-map"] => db
+            .page_map()
+            .and_then(|map| json_response(&VizPageOwnership::from_page_map(&map))),
+        ["api", "page", number] => match parse_page_number(number, db.page_count()) {
+            Ok(page_number) => db.parse_page(page_number).and_then(|page| {
+                let layout = match query_param(query, "tree") {
+                    Some(tree) => row_layout(&db.parse_schema()?, &tree),
+                    None => Default::default(),
+                };
+                json_response(&VizPage::from_page(&page, &layout))
+            }),
+            Err(response) => Ok(response),
+        },
+        ["api", "page", number, "raw"] => match parse_page_number(number, db.page_count()) {
+            Ok(page_number) => db.read_page_raw(page_number).map(|data| {
+                with_content_type(Response::from_data(data.to_vec()), "application/octet-stream")
+            }),
+            Err(response) => Ok(response),
+        },
+        _ => Ok(error_response(404, &format!("no such endpoint: {}", path))),
+    };
+
+    result.unwrap_or_else(|e| error_response(500, &e.to_string()))
+}
+
+/// Parse a page number from the URL, or the response to send if it is not a page of the file
+fn parse_page_number(text: &str, page_count: u32) -> std::result::Result<u32, HttpResponse> {
+    match text.parse::<u32>() {
+        Ok(n) if n >= 1 && n <= page_count => Ok(n),
+        Ok(n) => Err(error_response(
+            404,
+            &format!("page {} out of range 1..={}", n, page_count),
+        )),
+        Err(_) => Err(error_response(400, &format!("invalid page number: {}", text))),
+    }
+}
+
+fn json_response<T: Serialize>(value: &T) -> Result<HttpResponse> {
+    let json = serde_json::to_string(value)?;
+    Ok(with_content_type(Response::from_string(json), "application/json"))
+}
+
+fn error_response(status: u16, message: &str) -> HttpResponse {
+    let json = serde_json::json!({ "error": message }).to_string();
+    with_content_type(Response::from_string(json), "application/json").with_status_code(status)
+}
+
+fn with_content_type(response: HttpResponse, content_type: &str) -> HttpResponse {
+    let header = Header::from_bytes("Content-Type", content_type).expect("valid header");
+    response.with_header(header)
+}
+
+/// Value of a query string parameter
+fn query_param(query: &str, key: &str) -> Option<String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(k, _)| *k == key)
+        .map(|(_, value)| percent_decode(value))
+}
+
+/// Decode `%XX` escapes in a URL path segment, e.g. a B-tree name with spaces
+fn percent_decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| segment.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("order%20items"), "order items");
+        assert_eq!(percent_decode("caf%C3%A9"), "café");
+        // Escapes that are cut short or not hex are kept as written
+        assert_eq!(percent_decode("100%4"), "100%4");
+        assert_eq!(percent_decode("%zz%"), "%zz%");
+        // Bytes that are not UTF-8 are replaced
+        assert_eq!(percent_decode("a%FFb"), "a\u{fffd}b");
+    }
+
+    #[test]
+    fn test_query_param() {
+        let query = "tree=order%20items&depth=2&flag";
+        assert_eq!(query_param(query, "tree").as_deref(), Some("order items"));
+        assert_eq!(query_param(query, "depth").as_deref(), Some("2"));
+        assert_eq!(query_param(query, "flag"), None);
+        assert_eq!(query_param("", "tree"), None);
+    }
+
+    #[test]
+    fn test_parse_page_number() {
+        let status = |text| parse_page_number(text, 10).err().map(|response| response.status_code().0);
+        assert_eq!(parse_page_number("1", 10).ok(), Some(1));
+        assert_eq!(parse_page_number("10", 10).ok(), Some(10));
+        assert_eq!(status("0"), Some(404));
+        assert_eq!(status("11"), Some(404));
+        assert_eq!(status("abc"), Some(400));
+        assert_eq!(status("-1"), Some(400));
+    }
+}
@@ -0,0 +1,726 @@
+//! Interactive terminal browser for the schema, B-trees and pages of a database,
+//! for when opening an HTML file is not practical (e.g. over SSH).
+
+use std::collections::{HashMap, HashSet};
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::analyzer::{btree_node, column_label, labeled_row, row_layout};
+use crate::error::Result;
+use crate::model::{BTreeNode, BTreeType, Cell, Page, PageType, RowLayout, Schema, ValueSource};
+use crate::parser::parse_raw_page;
+use crate::Database;
+
+const HELP: &str = "Tab focus  ↑↓ move  Enter open  →/l child  r right-most  ←/h parent  o overflow  \
+                    b back  g go to page  PgUp/PgDn hex  q quit";
+
+/// Pane that receives the arrow keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Focus {
+    Schema,
+    Outline,
+    Cells,
+}
+
+/// A page of the current B-tree, in depth-first order. The outline grows as pages are
+/// visited: type and cell count are known, and children listed, once a page has been read.
+#[derive(Debug, Clone, PartialEq)]
+struct OutlineEntry {
+    page_number: u32,
+    depth: usize,
+    parent: Option<u32>,
+    /// Page type and cell count, once the page has been visited
+    visited: Option<(PageType, usize)>,
+}
+
+impl OutlineEntry {
+    fn unvisited(page_number: u32, depth: usize, parent: Option<u32>) -> Self {
+        Self { page_number, depth, parent, visited: None }
+    }
+}
+
+struct App<'a> {
+    db: &'a Database,
+    /// Every B-tree in the schema as (name, type, root page)
+    trees: Vec<(String, BTreeType, u32)>,
+    schema: Schema,
+    /// Column names, rowid alias and defaults of the current B-tree's records
+    row_layout: RowLayout,
+    schema_state: ListState,
+    /// Name of the current B-tree
+    tree_name: String,
+    outline: Vec<OutlineEntry>,
+    outline_state: ListState,
+    /// Page being viewed, and the parsed page unless it could not be parsed
+    page_number: u32,
+    page: Option<Page>,
+    cell_state: ListState,
+    /// Pages visited before the current one, for going back
+    history: Vec<u32>,
+    /// Known overflow pages, which cannot be told apart from B-tree pages by content
+    overflow_pages: HashSet<u32>,
+    /// Page each overflow page was reached from
+    overflow_parents: HashMap<u32, u32>,
+    /// First line of the hex pane
+    hex_scroll: usize,
+    focus: Focus,
+    /// Page number being typed after `g`
+    goto: Option<String>,
+    status: String,
+    quit: bool,
+}
+
+/// Run the terminal browser until the user quits
+pub fn run(db: &Database) -> Result<()> {
+    let mut app = App::new(db)?;
+    let mut terminal = ratatui::try_init()?;
+    let result = app.event_loop(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl<'a> App<'a> {
+    fn new(db: &'a Database) -> Result<Self> {
+        let schema = db.parse_schema()?;
+        let mut app = Self {
+            db,
+            trees: db.btree_roots(&schema),
+            schema,
+            row_layout: RowLayout::default(),
+            schema_state: ListState::default().with_selected(Some(0)),
+            tree_name: String::new(),
+            outline: Vec::new(),
+            outline_state: ListState::default(),
+            page_number: 1,
+            page: None,
+            cell_state: ListState::default(),
+            history: Vec::new(),
+            overflow_pages: HashSet::new(),
+            overflow_parents: HashMap::new(),
+            hex_scroll: 0,
+            focus: Focus::Schema,
+            goto: None,
+            status: String::new(),
+            quit: false,
+        };
+        app.open_tree(0);
+        Ok(app)
+    }
+
+    fn event_loop(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        while !self.quit {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    self.handle_key(key);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // ---------------------------------------------------------------------
+    // Navigation
+    // ---------------------------------------------------------------------
+
+    /// Show the root page of the B-tree at `index` in the schema list. Only the pages
+    /// visited are read, so opening a tree is quick however large it is.
+    fn open_tree(&mut self, index: usize) {
+        let Some((name, tree_type, root_page)) = self.trees.get(index).cloned() else { return };
+        self.schema_state.select(Some(index));
+        self.row_layout = row_layout(&self.schema, &name);
+        self.status = format!(
+            "{} {}: root page {}",
+            if tree_type == BTreeType::Table { "Table" } else { "Index" },
+            name,
+            root_page
+        );
+        self.tree_name = name;
+        self.outline = vec![OutlineEntry::unvisited(root_page, 0, None)];
+        self.show_page(root_page);
+    }
+
+    /// Show a page, remembering the current one for `back`
+    fn open_page(&mut self, page_number: u32) {
+        if page_number == self.page_number {
+            return;
+        }
+        if page_number < 1 || page_number > self.db.page_count() {
+            self.status = format!("Page {} out of range 1..={}", page_number, self.db.page_count());
+            return;
+        }
+        self.history.push(self.page_number);
+        self.show_page(page_number);
+    }
+
+    fn show_page(&mut self, page_number: u32) {
+        self.page_number = page_number;
+        self.page = if self.overflow_pages.contains(&page_number) {
+            self.db
+                .read_page_raw(page_number)
+                .ok()
+                .map(|data| parse_raw_page(data, page_number, PageType::Overflow, self.db.header.page_size))
+        } else {
+            match self.db.parse_page(page_number) {
+                Ok(page) => {
+                    if page.page_type.is_btree() {
+                        self.visit_tree_page(&page);
+                    }
+                    Some(page)
+                }
+                Err(e) => {
+                    self.status = format!("Page {}: {}", page_number, e);
+                    None
+                }
+            }
+        };
+
+        let cell_count = self.page.as_ref().map_or(0, |p| p.cells.len());
+        self.cell_state.select(if cell_count > 0 { Some(0) } else { None });
+        let in_outline = self.outline.iter().position(|e| e.page_number == page_number);
+        if in_outline.is_some() {
+            self.outline_state.select(in_outline);
+        }
+        self.scroll_to_cell();
+    }
+
+    /// Add a page of the current B-tree to the outline, and note where its overflow chains start
+    fn visit_tree_page(&mut self, page: &Page) {
+        if !self.outline.iter().any(|e| e.page_number == page.page_number) {
+            return;
+        }
+        let node = btree_node(page, 0, None);
+        for chain in &node.overflow_chains {
+            if let Some(&first) = chain.pages.first() {
+                self.overflow_pages.insert(first);
+                self.overflow_parents.insert(first, page.page_number);
+            }
+        }
+        visit_outline(&mut self.outline, &node);
+    }
+
+    fn selected_cell(&self) -> Option<&Cell> {
+        self.page.as_ref()?.cells.get(self.cell_state.selected()?)
+    }
+
+    /// Row layout of the records on the current page, if it belongs to the current B-tree
+    fn page_row_layout(&self) -> RowLayout {
+        if self.outline.iter().any(|entry| entry.page_number == self.page_number) {
+            self.row_layout.clone()
+        } else {
+            RowLayout::default()
+        }
+    }
+
+    /// Page the current page hangs off: its B-tree parent, or the page an overflow page was reached from
+    fn parent_page(&self) -> Option<u32> {
+        let node_parent = self.outline.iter().find(|e| e.page_number == self.page_number).and_then(|e| e.parent);
+        node_parent.or_else(|| self.overflow_parents.get(&self.page_number).copied())
+    }
+
+    /// Next page of an overflow chain, from the first four bytes of an overflow page
+    fn next_overflow_page(&self) -> Option<u32> {
+        let data = self.db.read_page_raw(self.page_number).ok()?;
+        let next = u32::from_be_bytes(data.get(..4)?.try_into().ok()?);
+        (next != 0).then_some(next)
+    }
+
+    fn follow_overflow(&mut self, target: u32) {
+        self.overflow_pages.insert(target);
+        self.overflow_parents.insert(target, self.page_number);
+        self.open_page(target);
+    }
+
+    fn go_child(&mut self) {
+        let Some(cell) = self.selected_cell() else { return };
+        if let Some(child) = cell.left_child() {
+            self.open_page(child);
+        } else if let Some(overflow) = cell.overflow_page() {
+            self.follow_overflow(overflow);
+        } else {
+            self.status = "Cell has no child or overflow page".to_string();
+        }
+    }
+
+    fn go_right_most(&mut self) {
+        let right_most = self.page.as_ref().and_then(|p| p.header.as_ref()).and_then(|h| h.right_most_pointer);
+        match right_most {
+            Some(page_number) => self.open_page(page_number),
+            None => self.status = "Page has no right-most pointer".to_string(),
+        }
+    }
+
+    fn go_overflow(&mut self) {
+        if self.page.as_ref().is_some_and(|p| p.page_type == PageType::Overflow) {
+            match self.next_overflow_page() {
+                Some(next) => self.follow_overflow(next),
+                None => self.status = "Last page of the overflow chain".to_string(),
+            }
+            return;
+        }
+        match self.selected_cell().and_then(|c| c.overflow_page()) {
+            Some(overflow) => self.follow_overflow(overflow),
+            None => self.status = "Cell has no overflow page".to_string(),
+        }
+    }
+
+    fn go_parent(&mut self) {
+        match self.parent_page() {
+            Some(parent) => self.open_page(parent),
+            None => self.status = format!("Page {} has no known parent", self.page_number),
+        }
+    }
+
+    fn go_back(&mut self) {
+        if let Some(previous) = self.history.pop() {
+            self.show_page(previous);
+        }
+    }
+
+    /// Scroll the hex pane so the selected cell is in view
+    fn scroll_to_cell(&mut self) {
+        self.hex_scroll = self
+            .selected_cell()
+            .map_or(0, |c| (c.cell_offset() as usize / 16).saturating_sub(2));
+    }
+
+    // ---------------------------------------------------------------------
+    // Input
+    // ---------------------------------------------------------------------
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        if let Some(input) = &mut self.goto {
+            match key.code {
+                KeyCode::Char(c) if c.is_ascii_digit() => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Enter => {
+                    let target = input.parse::<u32>().ok();
+                    self.goto = None;
+                    if let Some(page_number) = target {
+                        self.open_page(page_number);
+                    }
+                }
+                KeyCode::Esc => self.goto = None,
+                _ => {}
+            }
+            return;
+        }
+
+        self.status.clear();
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => self.quit = true,
+            KeyCode::Tab => self.focus = next_focus(self.focus, 1),
+            KeyCode::BackTab => self.focus = next_focus(self.focus, 2),
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::Enter => match self.focus {
+                Focus::Schema => {
+                    if let Some(index) = self.schema_state.selected() {
+                        self.open_tree(index);
+                    }
+                }
+                Focus::Outline => {
+                    if let Some(entry) = self.outline_state.selected().and_then(|i| self.outline.get(i)) {
+                        self.open_page(entry.page_number);
+                    }
+                }
+                Focus::Cells => self.go_child(),
+            },
+            KeyCode::Right | KeyCode::Char('l') => self.go_child(),
+            KeyCode::Char('r') => self.go_right_most(),
+            KeyCode::Left | KeyCode::Char('h') | KeyCode::Char('u') => self.go_parent(),
+            KeyCode::Char('o') => self.go_overflow(),
+            KeyCode::Char('b') | KeyCode::Backspace => self.go_back(),
+            KeyCode::Char('g') => self.goto = Some(String::new()),
+            KeyCode::PageUp => self.hex_scroll = self.hex_scroll.saturating_sub(16),
+            KeyCode::PageDown => self.hex_scroll += 16,
+            _ => {}
+        }
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let (state, len) = match self.focus {
+            Focus::Schema => (&mut self.schema_state, self.trees.len()),
+            Focus::Outline => (&mut self.outline_state, self.outline.len()),
+            Focus::Cells => (&mut self.cell_state, self.page.as_ref().map_or(0, |p| p.cells.len())),
+        };
+        if len == 0 {
+            return;
+        }
+        let current = state.selected().unwrap_or(0) as isize;
+        state.select(Some((current + delta).clamp(0, len as isize - 1) as usize));
+        if self.focus == Focus::Cells {
+            self.scroll_to_cell();
+        }
+    }
+
+    // ---------------------------------------------------------------------
+    // Drawing
+    // ---------------------------------------------------------------------
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] = Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        let [schema, outline, right] =
+            Layout::horizontal([Constraint::Length(28), Constraint::Length(30), Constraint::Min(0)]).areas(main);
+        let [page, cell, hex] =
+            Layout::vertical([Constraint::Percentage(40), Constraint::Percentage(20), Constraint::Percentage(40)])
+                .areas(right);
+
+        self.draw_schema(frame, schema);
+        self.draw_outline(frame, outline);
+        self.draw_page(frame, page);
+        self.draw_cell(frame, cell);
+        self.draw_hex(frame, hex);
+
+        let status_line = match &self.goto {
+            Some(input) => format!("Go to page: {}_", input),
+            None if !self.status.is_empty() => self.status.clone(),
+            None => HELP.to_string(),
+        };
+        frame.render_widget(Paragraph::new(status_line).style(Style::new().fg(Color::Gray)), status);
+    }
+
+    fn block(&self, title: String, focus: Option<Focus>) -> Block<'static> {
+        let style = if focus == Some(self.focus) {
+            Style::new().fg(Color::Cyan)
+        } else {
+            Style::new()
+        };
+        Block::bordered().title(title).border_style(style)
+    }
+
+    fn draw_schema(&mut self, frame: &mut Frame, area: Rect) {
+        let items: Vec<ListItem> = self
+            .trees
+            .iter()
+            .map(|(name, tree_type, root)| {
+                let kind = if *tree_type == BTreeType::Table { "T" } else { "I" };
+                ListItem::new(format!("{} {} ({})", kind, name, root))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(self.block("Schema".to_string(), Some(Focus::Schema)))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, area, &mut self.schema_state);
+    }
+
+    fn draw_outline(&mut self, frame: &mut Frame, area: Rect) {
+        let title = format!("B-tree {}", self.tree_name);
+        let items: Vec<ListItem> = self
+            .outline
+            .iter()
+            .map(|e| {
+                let line = match e.visited {
+                    Some((page_type, cell_count)) => format!(
+                        "{}{} {} [{}]",
+                        "  ".repeat(e.depth),
+                        e.page_number,
+                        short_page_type(page_type),
+                        cell_count
+                    ),
+                    None => format!("{}{} ...", "  ".repeat(e.depth), e.page_number),
+                };
+                let style = if e.page_number == self.page_number {
+                    Style::new().fg(Color::Yellow)
+                } else {
+                    Style::new()
+                };
+                ListItem::new(line).style(style)
+            })
+            .collect();
+        let list = List::new(items)
+            .block(self.block(title, Some(Focus::Outline)))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, area, &mut self.outline_state);
+    }
+
+    fn draw_page(&mut self, frame: &mut Frame, area: Rect) {
+        let Some(page) = &self.page else {
+            let block = self.block(format!("Page {}", self.page_number), Some(Focus::Cells));
+            frame.render_widget(Paragraph::new("Could not parse page").block(block), area);
+            return;
+        };
+
+        let block = self.block(format!("Page {} ({:?})", page.page_number, page.page_type), Some(Focus::Cells));
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let mut lines = Vec::new();
+        if let Some(header) = &page.header {
+            lines.push(Line::from(format!(
+                "cells {}  content start {}  first freeblock {}  fragmented {}{}",
+                header.cell_count,
+                header.cell_content_start,
+                header.first_freeblock,
+                header.fragmented_free_bytes,
+                header.right_most_pointer.map_or(String::new(), |p| format!("  right-most {}", p)),
+            )));
+            lines.push(Line::from(format!(
+                "free {} bytes{}  parent {}",
+                page.free_space,
+                page.free_space_breakdown.as_ref().map_or(String::new(), |f| format!(
+                    " (gap {}, freeblocks {}, fragmented {})",
+                    f.unallocated, f.freeblocks, f.fragmented
+                )),
+                self.parent_page().map_or("-".to_string(), |p| p.to_string()),
+            )));
+            lines.push(Line::from(format!("pointers {:?}", page.cell_pointers)));
+        } else if page.page_type == PageType::Overflow {
+            lines.push(Line::from(format!(
+                "next overflow page {}  reached from {}",
+                self.next_overflow_page().map_or("none".to_string(), |p| p.to_string()),
+                self.parent_page().map_or("-".to_string(), |p| p.to_string()),
+            )));
+        }
+
+        let [header_area, cells_area] =
+            Layout::vertical([Constraint::Length(lines.len() as u16), Constraint::Min(0)]).areas(inner);
+        frame.render_widget(Paragraph::new(lines), header_area);
+
+        let layout = self.page_row_layout();
+        let items: Vec<ListItem> = page.cells.iter().enumerate().map(|(i, c)| ListItem::new(cell_summary(i, c, &layout))).collect();
+        let list = List::new(items).highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, cells_area, &mut self.cell_state);
+    }
+
+    fn draw_cell(&self, frame: &mut Frame, area: Rect) {
+        let mut lines = Vec::new();
+        if let Some(cell) = self.selected_cell() {
+            if let (Some(payload), Some(local)) = (cell.payload_size(), cell.local_payload_size()) {
+                lines.push(Line::from(format!("payload {} bytes, {} on this page", payload, local)));
+            }
+            match cell.payload() {
+                Some(record) => {
+                    let layout = self.page_row_layout();
+                    for (i, value) in layout.materialize(cell.rowid(), &record.values).iter().enumerate() {
+                        let label = column_label(&layout.names, i);
+                        let line = match (record.column_types.get(i), value.source) {
+                            (Some(serial_type), ValueSource::Stored) => {
+                                format!("{} {:?} = {}", label, serial_type, record.values[i].preview(200))
+                            }
+                            (Some(serial_type), source) => {
+                                format!("{} {:?} = {} ({})", label, serial_type, value.value.preview(200), source.label())
+                            }
+                            (None, source) => format!("{} = {} ({})", label, value.value.preview(200), source.label()),
+                        };
+                        lines.push(Line::from(line));
+                    }
+                }
+                None if cell.payload_size().is_some() => lines.push(Line::from("record could not be decoded")),
+                None => {}
+            }
+        }
+        frame.render_widget(Paragraph::new(lines).block(self.block("Cell".to_string(), None)), area);
+    }
+
+    fn draw_hex(&mut self, frame: &mut Frame, area: Rect) {
+        let block = self.block("Hex".to_string(), None);
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let Ok(data) = self.db.read_page_raw(self.page_number) else { return };
+        let total_lines = data.len().div_ceil(16);
+        self.hex_scroll = self.hex_scroll.min(total_lines.saturating_sub(inner.height as usize));
+
+        // Header and pointer array, and the selected cell, are highlighted
+        let header_offset = if self.page_number == 1 { 100 } else { 0 };
+        let header_end = self.page.as_ref().filter(|p| p.header.is_some()).map_or(header_offset, |p| {
+            header_offset + p.page_type.header_size() + p.cell_pointers.len() * 2
+        });
+        let cell_range = self
+            .selected_cell()
+            .map(|c| c.cell_offset() as usize..c.cell_offset() as usize + c.cell_size());
+
+        let byte_style = |offset: usize| {
+            if cell_range.as_ref().is_some_and(|r| r.contains(&offset)) {
+                Style::new().fg(Color::Black).bg(Color::Cyan)
+            } else if (header_offset..header_end).contains(&offset) {
+                Style::new().fg(Color::Yellow)
+            } else {
+                Style::new()
+            }
+        };
+
+        let lines: Vec<Line> = data
+            .chunks(16)
+            .enumerate()
+            .skip(self.hex_scroll)
+            .take(inner.height as usize)
+            .map(|(row, chunk)| {
+                let start = row * 16;
+                let mut spans = vec![Span::styled(format!("{:04x}  ", start), Style::new().fg(Color::DarkGray))];
+                for (i, byte) in chunk.iter().enumerate() {
+                    let separator = if i == 8 { "  " } else { " " };
+                    spans.push(Span::raw(separator));
+                    spans.push(Span::styled(format!("{:02x}", byte), byte_style(start + i)));
+                }
+                spans.push(Span::raw("  "));
+                for (i, byte) in chunk.iter().enumerate() {
+                    let c = if (0x20..0x7f).contains(byte) { *byte as char } else { '.' };
+                    spans.push(Span::styled(c.to_string(), byte_style(start + i)));
+                }
+                Line::from(spans)
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(lines), inner);
+    }
+}
+
+/// Record a visited page in the outline: fill in its type and cell count, and list its
+/// children below it, in order, the first time it is visited. Children already in the
+/// outline, as in a corrupt tree that points back up, are not listed again.
+fn visit_outline(outline: &mut Vec<OutlineEntry>, node: &BTreeNode) {
+    let Some(position) = outline.iter().position(|e| e.page_number == node.page_number) else { return };
+    let first_visit = outline[position].visited.is_none();
+    outline[position].visited = Some((node.page_type, node.cell_count));
+    if !first_visit {
+        return;
+    }
+
+    let depth = outline[position].depth + 1;
+    let mut children = Vec::new();
+    for &child in &node.children {
+        if !outline.iter().chain(&children).any(|e: &OutlineEntry| e.page_number == child) {
+            children.push(OutlineEntry::unvisited(child, depth, Some(node.page_number)));
+        }
+    }
+    outline.splice(position + 1..position + 1, children);
+}
+
+fn next_focus(focus: Focus, step: usize) -> Focus {
+    const ORDER: [Focus; 3] = [Focus::Schema, Focus::Outline, Focus::Cells];
+    let index = ORDER.iter().position(|f| *f == focus).unwrap_or(0);
+    ORDER[(index + step) % ORDER.len()]
+}
+
+fn short_page_type(page_type: PageType) -> &'static str {
+    match page_type {
+        PageType::InteriorTable => "int-table",
+        PageType::LeafTable => "leaf-table",
+        PageType::InteriorIndex => "int-index",
+        PageType::LeafIndex => "leaf-index",
+        PageType::Overflow => "overflow",
+        PageType::FreelistTrunk => "free-trunk",
+        PageType::FreelistLeaf => "free-leaf",
+        PageType::PointerMap => "ptrmap",
+        PageType::LockByte => "lock-byte",
+    }
+}
+
+/// One line per cell: offset, size, key or child, and a preview of the record
+fn cell_summary(index: usize, cell: &Cell, layout: &RowLayout) -> String {
+    let mut line = format!("[{}] @{} {}B", index, cell.cell_offset(), cell.cell_size());
+    if let Some(child) = cell.left_child() {
+        line.push_str(&format!(" child {}", child));
+    }
+    if let Some(rowid) = cell.rowid() {
+        line.push_str(&format!(" rowid {}", rowid));
+    }
+    if let Some(overflow) = cell.overflow_page() {
+        line.push_str(&format!(" overflow {}", overflow));
+    }
+    if let Some(record) = cell.payload() {
+        line.push_str(&format!("  {}", labeled_row(layout, cell.rowid(), &record.values, 20).join(", ")));
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{btree_page, table_interior_cell, table_leaf_cell, text, Pages};
+    use crate::model::Value;
+
+    /// Root page 1 over interior page 2 (leaves 4, 5) and leaf 3
+    fn tree() -> Pages {
+        let leaf = |page, rowid| btree_page(page, 0x0d, &[table_leaf_cell(rowid, &[text("x")])], None);
+        Pages(vec![
+            btree_page(1, 0x05, &[table_interior_cell(2, 20)], Some(3)),
+            btree_page(2, 0x05, &[table_interior_cell(4, 10)], Some(5)),
+            leaf(3, 30),
+            leaf(4, 10),
+            leaf(5, 20),
+        ])
+    }
+
+    fn visit(outline: &mut Vec<OutlineEntry>, pages: &Pages, page_number: u32) {
+        visit_outline(outline, &btree_node(&pages.page(page_number).unwrap(), 0, None));
+    }
+
+    /// Page, depth and whether each entry has been visited
+    fn entries(outline: &[OutlineEntry]) -> Vec<(u32, usize, bool)> {
+        outline.iter().map(|e| (e.page_number, e.depth, e.visited.is_some())).collect()
+    }
+
+    #[test]
+    fn test_outline_grows_from_visited_pages() {
+        let pages = tree();
+        let mut outline = vec![OutlineEntry::unvisited(1, 0, None)];
+
+        visit(&mut outline, &pages, 1);
+        assert_eq!(entries(&outline), [(1, 0, true), (2, 1, false), (3, 1, false)]);
+        assert_eq!(outline[0].visited, Some((PageType::InteriorTable, 1)));
+
+        // Children of a page go directly below it, ahead of its later siblings
+        visit(&mut outline, &pages, 2);
+        assert_eq!(entries(&outline), [(1, 0, true), (2, 1, true), (4, 2, false), (5, 2, false), (3, 1, false)]);
+        assert_eq!(outline[2].parent, Some(2));
+
+        visit(&mut outline, &pages, 5);
+        assert_eq!(outline[3].visited, Some((PageType::LeafTable, 1)));
+    }
+
+    #[test]
+    fn test_outline_revisit_and_cycle() {
+        let mut pages = tree();
+        let mut outline = vec![OutlineEntry::unvisited(1, 0, None)];
+        visit(&mut outline, &pages, 1);
+        visit(&mut outline, &pages, 1);
+        assert_eq!(entries(&outline), [(1, 0, true), (2, 1, false), (3, 1, false)]);
+
+        // Page 2 points back at the root and at its sibling: neither is listed again
+        pages.0[1] = btree_page(2, 0x05, &[table_interior_cell(1, 10), table_interior_cell(3, 15)], Some(4));
+        visit(&mut outline, &pages, 2);
+        assert_eq!(entries(&outline), [(1, 0, true), (2, 1, true), (4, 2, false), (3, 1, false)]);
+
+        // Pages outside the outline are ignored
+        visit(&mut outline, &pages, 5);
+        assert_eq!(outline.len(), 4);
+    }
+
+    #[test]
+    fn test_cell_summary() {
+        let leaf = Pages(vec![btree_page(1, 0x0d, &[table_leaf_cell(7, &[Value::Null, text("ann")])], None)]);
+        let page = leaf.page(1).unwrap();
+        let cell = &page.cells[0];
+        let prefix = format!("[0] @{} {}B rowid 7", cell.cell_offset(), cell.cell_size());
+
+        let layout = RowLayout { rowid_alias: Some(0), ..RowLayout::with_names(vec!["id".into(), "name".into()]) };
+        assert_eq!(cell_summary(0, cell, &layout), format!("{}  id: 7 (rowid alias), name: \"ann\"", prefix));
+
+        let interior = Pages(vec![btree_page(1, 0x05, &[table_interior_cell(9, 42)], Some(3))]);
+        let page = interior.page(1).unwrap();
+        let cell = &page.cells[0];
+        assert_eq!(
+            cell_summary(3, cell, &RowLayout::default()),
+            format!("[3] @{} {}B child 9 rowid 42", cell.cell_offset(), cell.cell_size())
+        );
+    }
+
+    #[test]
+    fn test_next_focus_wraps() {
+        assert_eq!(next_focus(Focus::Schema, 1), Focus::Outline);
+        assert_eq!(next_focus(Focus::Cells, 1), Focus::Schema);
+        // Two steps forward is one step back
+        assert_eq!(next_focus(Focus::Schema, 2), Focus::Cells);
+        assert_eq!(next_focus(Focus::Outline, 2), Focus::Schema);
+    }
+}
@@ -1,5 +1,6 @@
 use serde::Serialize;
-use crate::model::{BTree, DatabaseHeader, Schema, Page, Cell};
+use crate::analyzer::{is_without_rowid, key_description, labeled_row, labeled_values, record_column_names, table_definition, IndexLookup, LookupResult, RowidLookup};
+use crate::model::{BTree, BTreeNode, BTreeType, DatabaseHeader, Schema, Page, Cell, Freelist, PtrmapPage, PageMap, FreeSpace, Freeblock, RowLayout, Stat1Entry, Statistics};
 
 /// Root visualization data structure
 #[derive(Debug, Serialize)]
@@ -8,6 +9,65 @@ pub struct VizData {
     pub schema: VizSchema,
     pub btrees: Vec<VizBTree>,
     pub pages: Vec<VizPage>,
+    pub freelist: VizFreelist,
+    pub ptrmap: Vec<VizPtrmapPage>,
+    /// Owner(s) of every page in the file, in page order
+    pub page_map: Vec<VizPageOwnership>,
+    /// B-tree nodes, pages and the page map are left empty and fetched from
+    /// the `serve` API on demand
+    pub lazy: bool,
+    /// Search path to highlight on the tree
+    pub lookup: Option<VizLookup>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VizLookup {
+    /// B-tree the search ran in
+    pub tree: String,
+    /// Key searched for, for display
+    pub key: String,
+    pub steps: Vec<VizLookupStep>,
+    pub found: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VizLookupStep {
+    pub page_number: u32,
+    pub cell_index: Option<usize>,
+    pub child_page: Option<u32>,
+}
+
+impl VizLookup {
+    pub fn from_rowid_lookup(lookup: &RowidLookup) -> Self {
+        Self {
+            tree: lookup.tree.clone(),
+            key: format!("rowid {}", lookup.rowid),
+            steps: lookup.path.iter()
+                .map(|step| VizLookupStep {
+                    page_number: step.page_number,
+                    cell_index: step.cell_index,
+                    child_page: step.child_page,
+                })
+                .collect(),
+            found: matches!(lookup.result, LookupResult::Found { .. }),
+        }
+    }
+
+    pub fn from_index_lookup(lookup: &IndexLookup) -> Self {
+        let key: Vec<String> = lookup.key.iter().map(|v| v.preview(40)).collect();
+        Self {
+            tree: lookup.tree.clone(),
+            key: format!("key ({})", key.join(", ")),
+            steps: lookup.path.iter()
+                .map(|step| VizLookupStep {
+                    page_number: step.page_number,
+                    cell_index: step.cell_index,
+                    child_page: step.child_page,
+                })
+                .collect(),
+            found: !lookup.matches.is_empty(),
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -19,6 +79,8 @@ pub struct VizDatabaseInfo {
     pub text_encoding: String,
     pub sqlite_version: String,
     pub schema_format: u32,
+    /// WAL commit frame the database is read at, if a WAL was applied
+    pub wal_commit_frame: Option<usize>,
 }
 
 impl VizDatabaseInfo {
@@ -31,6 +93,7 @@ impl VizDatabaseInfo {
             text_encoding: format!("{:?}", header.text_encoding),
             sqlite_version: header.sqlite_version_string(),
             schema_format: header.schema_format,
+            wal_commit_frame: None,
         }
     }
 }
@@ -47,16 +110,67 @@ pub struct VizSchemaEntry {
     pub table_name: String,
     pub root_page: u32,
     pub sql: Option<String>,
+    /// PRIMARY KEY of a WITHOUT ROWID table, which is stored as an index B-tree on it
+    pub without_rowid_key: Option<String>,
+    /// Planner statistics from sqlite_stat1 and sqlite_stat4
+    pub statistics: Option<VizStatistics>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VizStatistics {
+    /// Record column names, for labeling the per-key estimates
+    pub columns: Vec<String>,
+    pub stat1: Option<Stat1Entry>,
+    pub stat4: Vec<VizStat4Sample>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VizStat4Sample {
+    /// `name: value` previews of the sampled entry
+    pub values: Vec<String>,
+    pub equal: Vec<u64>,
+    pub less: Vec<u64>,
+    pub distinct_less: Vec<u64>,
+}
+
+impl VizStatistics {
+    /// Statistics of one B-tree, or None if ANALYZE recorded nothing for it
+    pub fn for_tree(schema: &Schema, statistics: &Statistics, tree: &str) -> Option<Self> {
+        let stat1 = statistics.stat1_for(tree).cloned();
+        let samples: Vec<_> = statistics.stat4_for(tree).collect();
+        if stat1.is_none() && samples.is_empty() {
+            return None;
+        }
+        let columns = record_column_names(schema, tree);
+        Some(Self {
+            stat4: samples
+                .into_iter()
+                .map(|sample| VizStat4Sample {
+                    values: labeled_values(&columns, &sample.sample.values, 40),
+                    equal: sample.equal.clone(),
+                    less: sample.less.clone(),
+                    distinct_less: sample.distinct_less.clone(),
+                })
+                .collect(),
+            columns,
+            stat1,
+        })
+    }
 }
 
 impl VizSchema {
-    pub fn from_schema(schema: &Schema) -> Self {
+    pub fn from_schema(schema: &Schema, statistics: &Statistics) -> Self {
         let tables: Vec<_> = schema.tables()
             .map(|e| VizSchemaEntry {
                 name: e.name.clone(),
                 table_name: e.table_name.clone(),
                 root_page: e.root_page,
                 sql: e.sql.clone(),
+                without_rowid_key: is_without_rowid(e)
+                    .then(|| table_definition(schema, &e.name).ok())
+                    .flatten()
+                    .map(|table| key_description(&table.primary_key)),
+                statistics: VizStatistics::for_tree(schema, statistics, &e.name),
             })
             .collect();
 
@@ -66,6 +180,8 @@ impl VizSchema {
                 table_name: e.table_name.clone(),
                 root_page: e.root_page,
                 sql: e.sql.clone(),
+                without_rowid_key: None,
+                statistics: VizStatistics::for_tree(schema, statistics, &e.name),
             })
             .collect();
 
@@ -73,6 +189,87 @@ impl VizSchema {
     }
 }
 
+#[derive(Debug, Serialize)]
+pub struct VizFreelist {
+    pub first_trunk: u32,
+    pub total_pages: usize,
+    pub trunks: Vec<VizFreelistTrunk>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VizFreelistTrunk {
+    pub page_number: u32,
+    pub next_trunk: Option<u32>,
+    pub leaf_pages: Vec<u32>,
+}
+
+impl VizFreelist {
+    pub fn from_freelist(freelist: &Freelist) -> Self {
+        Self {
+            first_trunk: freelist.first_trunk,
+            total_pages: freelist.total_pages(),
+            trunks: freelist.trunks.iter()
+                .map(|t| VizFreelistTrunk {
+                    page_number: t.page_number,
+                    next_trunk: t.next_trunk,
+                    leaf_pages: t.leaf_pages.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct VizPtrmapPage {
+    pub page_number: u32,
+    pub entries: Vec<VizPtrmapEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VizPtrmapEntry {
+    pub page_number: u32,
+    pub entry_type: String,
+    pub parent_page: u32,
+}
+
+impl VizPtrmapPage {
+    pub fn from_ptrmap_page(page: &PtrmapPage) -> Self {
+        Self {
+            page_number: page.page_number,
+            entries: page.entries.iter()
+                .map(|e| VizPtrmapEntry {
+                    page_number: e.page_number,
+                    entry_type: format!("{:?}", e.entry_type),
+                    parent_page: e.parent_page,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct VizPageOwnership {
+    pub page_number: u32,
+    /// Page type implied by the first owner (None for orphaned and unreadable pages)
+    pub page_type: Option<String>,
+    pub owners: Vec<String>,
+}
+
+impl VizPageOwnership {
+    pub fn from_page_map(map: &PageMap) -> Vec<Self> {
+        (1..=map.page_count)
+            .map(|page_number| {
+                let owners = map.claims.get(&page_number).map(Vec::as_slice).unwrap_or_default();
+                Self {
+                    page_number,
+                    page_type: owners.first().and_then(|o| o.page_type()).map(|t| format!("{:?}", t)),
+                    owners: owners.iter().map(|o| o.to_string()).collect(),
+                }
+            })
+            .collect()
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct VizBTree {
     pub name: String,
@@ -99,6 +296,28 @@ pub struct VizBTreeNode {
     pub overflow_pages: Vec<u32>,
 }
 
+impl VizBTreeNode {
+    pub fn from_node(node: &BTreeNode) -> Self {
+        let overflow_pages: Vec<_> = node.overflow_chains.iter()
+            .flat_map(|c| &c.pages)
+            .copied()
+            .collect();
+
+        Self {
+            id: format!("p{}", node.page_number),
+            page_number: node.page_number,
+            page_type: format!("{:?}", node.page_type),
+            depth: node.depth,
+            cell_count: node.cell_count,
+            children: node.children.clone(),
+            size_used: node.size_used,
+            size_free: node.size_free,
+            has_overflow: !node.overflow_chains.is_empty(),
+            overflow_pages,
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct VizLink {
     pub source: String,
@@ -107,26 +326,22 @@ pub struct VizLink {
 }
 
 impl VizBTree {
+    /// A B-tree known only from the schema, before its pages have been walked
+    pub fn summary(name: &str, tree_type: BTreeType, root_page: u32) -> Self {
+        Self {
+            name: name.to_string(),
+            tree_type: format!("{:?}", tree_type),
+            root_page,
+            depth: 0,
+            total_cells: 0,
+            total_pages: 0,
+            nodes: Vec::new(),
+            links: Vec::new(),
+        }
+    }
+
     pub fn from_btree(btree: &BTree) -> Self {
-        let nodes: Vec<_> = btree.nodes.iter().map(|node| {
-            let overflow_pages: Vec<_> = node.overflow_chains.iter()
-                .flat_map(|c| &c.pages)
-                .copied()
-                .collect();
-
-            VizBTreeNode {
-                id: format!("p{}", node.page_number),
-                page_number: node.page_number,
-                page_type: format!("{:?}", node.page_type),
-                depth: node.depth,
-                cell_count: node.cell_count,
-                children: node.children.clone(),
-                size_used: node.size_used,
-                size_free: node.size_free,
-                has_overflow: !node.overflow_chains.is_empty(),
-                overflow_pages,
-            }
-        }).collect();
+        let nodes: Vec<_> = btree.nodes.iter().map(VizBTreeNode::from_node).collect();
 
         // Build links
         let mut links = Vec::new();
@@ -174,6 +389,8 @@ pub struct VizPage {
     pub page_type: String,
     pub cell_count: usize,
     pub free_space: usize,
+    pub free_space_breakdown: Option<FreeSpace>,
+    pub freeblocks: Vec<Freeblock>,
     pub cell_content_start: u16,
     pub cells: Vec<VizCell>,
 }
@@ -195,21 +412,19 @@ pub struct VizCell {
 }
 
 impl VizPage {
-    pub fn from_page(page: &Page) -> Self {
+    /// Build from a parsed page; `layout` labels record values with the owning B-tree's column
+    /// names and fills in rowid aliases and column defaults
+    pub fn from_page(page: &Page, layout: &RowLayout) -> Self {
         let cells: Vec<_> = page.cells.iter().enumerate().map(|(i, cell)| {
             let (preview, full_content) = match cell {
                 Cell::TableLeaf(c) => {
                     if let Some(record) = &c.payload {
-                        let prev = record.values.iter()
+                        let prev = labeled_row(layout, Some(c.rowid), &record.values, 20)
+                            .into_iter()
                             .take(3)
-                            .map(|v| v.preview(20))
                             .collect::<Vec<_>>()
                             .join(", ");
-                        let full = record.values.iter()
-                            .enumerate()
-                            .map(|(idx, v)| format!("[{}]: {}", idx, v.preview(1000)))
-                            .collect::<Vec<_>>()
-                            .join("\n");
+                        let full = labeled_row(layout, Some(c.rowid), &record.values, 1000).join("\n");
                         (prev, full)
                     } else {
                         let s = format!("rowid={}", c.rowid);
@@ -222,16 +437,12 @@ impl VizPage {
                 }
                 Cell::IndexLeaf(c) => {
                     if let Some(record) = &c.payload {
-                        let prev = record.values.iter()
+                        let prev = labeled_row(layout, None, &record.values, 20)
+                            .into_iter()
                             .take(3)
-                            .map(|v| v.preview(20))
                             .collect::<Vec<_>>()
                             .join(", ");
-                        let full = record.values.iter()
-                            .enumerate()
-                            .map(|(idx, v)| format!("[{}]: {}", idx, v.preview(1000)))
-                            .collect::<Vec<_>>()
-                            .join("\n");
+                        let full = labeled_row(layout, None, &record.values, 1000).join("\n");
                         (prev, full)
                     } else {
                         let s = format!("payload_size={}", c.payload_size);
@@ -241,11 +452,7 @@ impl VizPage {
                 Cell::IndexInterior(c) => {
                     let base = format!("child={}, payload_size={}", c.left_child_page, c.payload_size);
                     if let Some(record) = &c.payload {
-                        let full = record.values.iter()
-                            .enumerate()
-                            .map(|(idx, v)| format!("[{}]: {}", idx, v.preview(1000)))
-                            .collect::<Vec<_>>()
-                            .join("\n");
+                        let full = labeled_row(layout, None, &record.values, 1000).join("\n");
                         (base, full)
                     } else {
                         (base.clone(), base)
@@ -297,6 +504,8 @@ impl VizPage {
             page_type: format!("{:?}", page.page_type),
             cell_count: page.cells.len(),
             free_space: page.free_space,
+            free_space_breakdown: page.free_space_breakdown.clone(),
+            freeblocks: page.freeblocks.clone(),
             cell_content_start,
             cells,
         }
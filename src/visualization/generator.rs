@@ -1,17 +1,33 @@
 use std::path::Path;
 use crate::error::Result;
 use crate::visualization::data::VizData;
-use crate::visualization::templates::{HTML_TEMPLATE, STYLES_CSS, VIZ_JS};
+use crate::model::PageDiffReport;
+use crate::visualization::templates::{HEATMAP_TEMPLATE, HTML_TEMPLATE, STYLES_CSS, VIZ_JS};
 
-/// Generate the HTML visualization file
-pub fn generate_html(viz_data: &VizData, output_path: &Path) -> Result<()> {
+/// Render the visualization page with CSS, JS and data inlined
+pub fn render_html(viz_data: &VizData) -> Result<String> {
     let json_data = serde_json::to_string(viz_data)?;
 
-    // Inline CSS and JS into HTML
-    let html = HTML_TEMPLATE
+    Ok(HTML_TEMPLATE
         .replace("/* __STYLES_PLACEHOLDER__ */", STYLES_CSS)
         .replace("/* __VIZ_JS_PLACEHOLDER__ */", VIZ_JS)
-        .replace("\"__DATA_PLACEHOLDER__\"", &json_data);
+        .replace("\"__DATA_PLACEHOLDER__\"", &json_data))
+}
+
+/// Generate the HTML visualization file
+pub fn generate_html(viz_data: &VizData, output_path: &Path) -> Result<()> {
+    std::fs::write(output_path, render_html(viz_data)?)?;
+    Ok(())
+}
+
+/// Generate the HTML heatmap of pages changed between two databases
+pub fn generate_heatmap_html(report: &PageDiffReport, output_path: &Path) -> Result<()> {
+    let json_data = serde_json::to_string(&serde_json::json!({
+        "report": report,
+        "summary": report.summary(),
+    }))?;
+
+    let html = HEATMAP_TEMPLATE.replace("\"__DATA_PLACEHOLDER__\"", &json_data);
 
     std::fs::write(output_path, html)?;
     Ok(())
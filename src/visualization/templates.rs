@@ -6,3 +6,6 @@ pub const STYLES_CSS: &str = include_str!("../../templates/styles.css");
 
 /// Embedded JavaScript
 pub const VIZ_JS: &str = include_str!("../../templates/viz.js");
+
+/// Embedded page diff heatmap (HTML, CSS and JS in one file)
+pub const HEATMAP_TEMPLATE: &str = include_str!("../../templates/heatmap.html");
@@ -4,20 +4,150 @@ let currentBTree = null;
 let currentZoom = null;
 let simulation = null;
 
+// In `serve` mode (DATA.lazy) B-trees and pages are fetched from the server as needed
+const pageRequests = new Map();
+const btreeRequests = new Map();
+
+async function fetchJson(path) {
+    const response = await fetch(path);
+    if (!response.ok) {
+        throw new Error(`${path}: ${response.status}`);
+    }
+    return response.json();
+}
+
+// Look up a parsed page, fetching it from the server if it is not loaded yet
+async function getPage(pageNum) {
+    const page = DATA.pages.find(p => p.page_number === pageNum);
+    if (page || !DATA.lazy) return page;
+
+    if (!pageRequests.has(pageNum)) {
+        // Name the B-tree the page belongs to, so record values come back labeled with its columns
+        const inTree = currentBTree && currentBTree.nodes.some(n => n.page_number === pageNum);
+        const query = inTree ? `?tree=${encodeURIComponent(currentBTree.name)}` : '';
+        pageRequests.set(pageNum, fetchJson(`/api/page/${pageNum}${query}`)
+            .then(fetched => {
+                DATA.pages.push(fetched);
+                return fetched;
+            })
+            .catch(() => {
+                pageRequests.delete(pageNum);
+                return null;
+            }));
+    }
+    return pageRequests.get(pageNum);
+}
+
+// Look up a B-tree. In lazy mode only its root page is fetched at first; clicking a page
+// fetches its children (see expandNode)
+async function getBTree(name) {
+    const btree = DATA.btrees.find(b => b.name === name);
+    if (!btree) return null;
+    if (!DATA.lazy || btree.nodes.length > 0) return btree;
+
+    if (!btreeRequests.has(name)) {
+        btreeRequests.set(name, fetchNode(btree.root_page, 0)
+            .then(root => {
+                btree.nodes = [root];
+                btree.links = [];
+                return btree;
+            })
+            .catch(() => {
+                btreeRequests.delete(name);
+                return null;
+            }));
+    }
+    return btreeRequests.get(name);
+}
+
+// Fetch the summary of one B-tree page: its type, fill and child page numbers
+function fetchNode(pageNum, depth) {
+    return fetchJson(`/api/node/${pageNum}?depth=${depth}`);
+}
+
+// Child pages of a node that have not been fetched yet
+function unloadedChildren(btree, node) {
+    if (!DATA.lazy) return [];
+    return node.children.filter(child => !btree.nodes.some(n => n.page_number === child));
+}
+
+// Fetch the children of a node of a lazily loaded B-tree and redraw the tree with them
+async function expandNode(btree, pageNum) {
+    const node = btree.nodes.find(n => n.page_number === pageNum);
+    if (!node) return;
+    const missing = unloadedChildren(btree, node);
+    if (missing.length === 0) return;
+
+    const fetched = await Promise.all(missing.map(child => fetchNode(child, node.depth + 1).catch(() => null)));
+    fetched.forEach(child => {
+        // A corrupt tree can reference a page twice, and clicks can overlap
+        if (child && !btree.nodes.some(n => n.page_number === child.page_number)) {
+            btree.nodes.push(child);
+        }
+    });
+    node.children.forEach(child => {
+        const target = `p${child}`;
+        const loaded = btree.nodes.some(n => n.page_number === child);
+        if (loaded && !btree.links.some(l => l.source === node.id && l.target === target)) {
+            btree.links.push({ source: node.id, target, link_type: 'child' });
+        }
+    });
+    if (currentBTree === btree) {
+        renderBTree(btree);
+    }
+}
+
+// Show a clicked page, and in lazy mode load the pages below it
+function selectNode(pageNum) {
+    showPageDetails(pageNum);
+    if (DATA.lazy && currentBTree) {
+        expandNode(currentBTree, pageNum);
+    }
+}
+
 // Initialize visualization
 function init() {
     renderDatabaseInfo();
     renderSchemaList();
+    renderFreelist();
+    renderPtrmap();
+    renderPageStrip();
     setupBTreeSelect();
     setupControls();
     setupResizeHandle();
 
-    // Render first btree by default
-    if (DATA.btrees.length > 0) {
+    // Render the looked-up btree with its search path, or the first btree by default
+    if (DATA.lookup) {
+        const steps = DATA.lookup.steps;
+        selectBTree(DATA.lookup.tree).then(() => showPageDetails(steps[steps.length - 1].page_number));
+    } else if (DATA.btrees.length > 0) {
         selectBTree(DATA.btrees[0].name);
     }
 }
 
+// Step of the highlighted lookup on a page of the current B-tree, if the search visited it
+function lookupStep(pageNum) {
+    if (!DATA.lookup || !currentBTree || currentBTree.name !== DATA.lookup.tree) return null;
+    return DATA.lookup.steps.find(s => s.page_number === pageNum) || null;
+}
+
+// Whether a parent -> child edge is part of the lookup path
+function onLookupPath(parentPage, childPage) {
+    const step = lookupStep(parentPage);
+    return step !== null && step.child_page === childPage;
+}
+
+// Describe what the lookup did on a page
+function lookupStepText(step) {
+    const last = step.child_page === null;
+    if (!last) {
+        return step.cell_index !== null
+            ? `cell #${step.cell_index} → page ${step.child_page}`
+            : `right-most pointer → page ${step.child_page}`;
+    }
+    return DATA.lookup.found ? `found in cell #${step.cell_index}` : 'not found on this leaf';
+}
+
 // Setup resizable detail panel
 function setupResizeHandle() {
     const handle = document.getElementById('resize-handle');
@@ -66,6 +196,7 @@ function renderDatabaseInfo() {
         <p><span class="label">Pages:</span> <span class="value">${info.page_count}</span></p>
         <p><span class="label">Encoding:</span> <span class="value">${info.text_encoding}</span></p>
         <p><span class="label">SQLite:</span> <span class="value">${info.sqlite_version}</span></p>
+        ${info.wal_commit_frame !== null ? `<p><span class="label">WAL:</span> <span class="value">commit frame ${info.wal_commit_frame}</span></p>` : ''}
     `;
 }
 
@@ -79,8 +210,9 @@ function renderSchemaList() {
         html += `
             <div class="schema-item" data-name="${table.name}" data-type="table">
                 <div class="name">${table.name}</div>
-                <div class="type">table</div>
+                <div class="type">${table.without_rowid_key === null ? 'table' : `WITHOUT ROWID table, key (${escapeHtml(table.without_rowid_key)})`}</div>
                 <div class="page">Page ${table.root_page}</div>
+                ${statisticsHtml(table.statistics)}
             </div>
         `;
     });
@@ -92,12 +224,18 @@ function renderSchemaList() {
                 <div class="name">${index.name}</div>
                 <div class="type">index on ${index.table_name}</div>
                 <div class="page">Page ${index.root_page}</div>
+                ${statisticsHtml(index.statistics)}
             </div>
         `;
     });
 
     container.innerHTML = html;
 
+    // Opening the samples should not switch trees
+    container.querySelectorAll('.stat4').forEach(details => {
+        details.addEventListener('click', e => e.stopPropagation());
+    });
+
     // Add click handlers
     container.querySelectorAll('.schema-item').forEach(item => {
         item.addEventListener('click', () => {
@@ -107,6 +245,144 @@ function renderSchemaList() {
     });
 }
 
+// sqlite_stat1 estimates and sqlite_stat4 samples of a schema item
+function statisticsHtml(stats) {
+    if (!stats) return '';
+    let html = '';
+    if (stats.stat1) {
+        const label = i => escapeHtml(stats.columns[i] ?? `[${i}]`);
+        const perKey = stats.stat1.rows_per_key.map((rows, i) => {
+            const prefix = Array.from({ length: i + 1 }, (_, j) => label(j)).join(', ');
+            return `${i === 0 ? prefix : `(${prefix})`} ${rows}`;
+        });
+        let text = `stat1: ${stats.stat1.row_count} rows`;
+        if (perKey.length > 0) text += `; avg rows per key: ${perKey.join(', ')}`;
+        if (stats.stat1.options.length > 0) text += `; ${escapeHtml(stats.stat1.options.join(' '))}`;
+        html += `<div class="stats">${text}</div>`;
+    }
+    if (stats.stat4.length > 0) {
+        const samples = stats.stat4.map(sample => `
+            <li>(${escapeHtml(sample.values.join(', '))})
+                eq ${sample.equal.join(' ')}, lt ${sample.less.join(' ')}, distinct lt ${sample.distinct_less.join(' ')}</li>
+        `).join('');
+        html += `<details class="stat4"><summary>stat4: ${stats.stat4.length} samples</summary><ul>${samples}</ul></details>`;
+    }
+    return html;
+}
+
+// Render freelist trunk chain in sidebar
+function renderFreelist() {
+    const container = document.getElementById('freelist-list');
+    const freelist = DATA.freelist;
+
+    if (freelist.trunks.length === 0) {
+        container.innerHTML = '<p class="freelist-summary">Empty</p>';
+        return;
+    }
+
+    let html = `<p class="freelist-summary">${freelist.total_pages} pages in ${freelist.trunks.length} trunk(s)</p>`;
+    freelist.trunks.forEach(trunk => {
+        html += `
+            <div class="schema-item freelist-item" data-page="${trunk.page_number}">
+                <div class="name">Trunk page ${trunk.page_number}</div>
+                <div class="type">${trunk.leaf_pages.length} leaves${trunk.next_trunk ? ` &rarr; next trunk ${trunk.next_trunk}` : ''}</div>
+                <div class="leaves">${trunk.leaf_pages.join(', ')}</div>
+            </div>
+        `;
+    });
+
+    container.innerHTML = html;
+
+    container.querySelectorAll('.freelist-item').forEach(item => {
+        item.addEventListener('click', () => showPageDetails(parseInt(item.dataset.page)));
+    });
+}
+
+// Render pointer-map pages in sidebar (auto-vacuum databases only)
+function renderPtrmap() {
+    const section = document.getElementById('ptrmap-section');
+    const container = document.getElementById('ptrmap-list');
+
+    if (DATA.ptrmap.length === 0) {
+        section.style.display = 'none';
+        return;
+    }
+
+    let html = '';
+    DATA.ptrmap.forEach(ptrmapPage => {
+        const counts = {};
+        ptrmapPage.entries.forEach(e => {
+            counts[e.entry_type] = (counts[e.entry_type] || 0) + 1;
+        });
+        const summary = Object.entries(counts).map(([type, count]) => `${type}: ${count}`).join(', ');
+        html += `
+            <div class="schema-item ptrmap-item" data-page="${ptrmapPage.page_number}">
+                <div class="name">Ptrmap page ${ptrmapPage.page_number}</div>
+                <div class="type">${ptrmapPage.entries.length} entries</div>
+                <div class="page">${summary}</div>
+            </div>
+        `;
+    });
+
+    container.innerHTML = html;
+
+    container.querySelectorAll('.ptrmap-item').forEach(item => {
+        item.addEventListener('click', () => showPageDetails(parseInt(item.dataset.page)));
+    });
+}
+
+// Render one square per page of the file, colored by owner
+function renderPageStrip() {
+    const pageMap = DATA.page_map;
+    if (DATA.lazy && pageMap.length === 0) {
+        // Mapping every page means walking the whole file, so only do it when asked
+        document.getElementById('page-strip-summary').innerHTML =
+            `${DATA.database_info.page_count} pages <a href="#" id="load-page-map">Load page map</a>`;
+        document.getElementById('load-page-map').addEventListener('click', async (e) => {
+            e.preventDefault();
+            document.getElementById('page-strip-summary').textContent = 'Loading page map...';
+            try {
+                DATA.page_map = await fetchJson('/api/page-map');
+            } catch (err) {
+                document.getElementById('page-strip-summary').textContent = `Could not load page map: ${err.message}`;
+                return;
+            }
+            renderPageStrip();
+        });
+        return;
+    }
+    const svg = d3.select('#page-strip');
+    const width = document.getElementById('page-strip').clientWidth || 800;
+    const size = 8;
+    const gap = 1;
+    const columns = Math.max(1, Math.floor(width / (size + gap)));
+    const rows = Math.ceil(pageMap.length / columns);
+
+    svg.attr('height', rows * (size + gap));
+
+    const orphans = pageMap.filter(p => p.owners.length === 0).length;
+    const duplicates = pageMap.filter(p => p.owners.length > 1).length;
+    document.getElementById('page-strip-summary').textContent =
+        `${pageMap.length} pages, ${orphans} orphaned, ${duplicates} claimed more than once`;
+
+    svg.selectAll('rect')
+        .data(pageMap)
+        .join('rect')
+        .attr('x', (d, i) => (i % columns) * (size + gap))
+        .attr('y', (d, i) => Math.floor(i / columns) * (size + gap))
+        .attr('width', size)
+        .attr('height', size)
+        .attr('class', d => {
+            if (d.owners.length === 0) return 'page-orphan';
+            if (!d.page_type) return 'page-unreadable';
+            const cls = getPageClass(d.page_type);
+            return d.owners.length > 1 ? `${cls} page-duplicate` : cls;
+        })
+        .on('click', (event, d) => showPageDetails(d.page_number))
+        .append('title')
+        .text(d => `Page ${d.page_number}: ${d.owners.length > 0 ? d.owners.join(', ') : 'orphan (no owner)'}`);
+}
+
 // Setup B-tree select dropdown
 function setupBTreeSelect() {
     const select = document.getElementById('btree-select');
@@ -143,21 +419,20 @@ function setupControls() {
         }
     });
 
-    document.getElementById('view-mode').addEventListener('change', (e) => {
+    document.getElementById('view-mode').addEventListener('change', () => {
         if (currentBTree) {
-            if (e.target.value === 'tree') {
-                renderTreeView(currentBTree);
-            } else {
-                renderForceView(currentBTree);
-            }
+            renderBTree(currentBTree);
         }
     });
 }
 
 // Select and render a B-tree
-function selectBTree(name) {
-    const btree = DATA.btrees.find(b => b.name === name);
-    if (!btree) return;
+async function selectBTree(name) {
+    // Update dropdown before a lazy fetch, so a newer selection can be detected
+    document.getElementById('btree-select').value = name;
+
+    const btree = await getBTree(name);
+    if (!btree || document.getElementById('btree-select').value !== name) return;
 
     currentBTree = btree;
 
@@ -166,19 +441,29 @@ function selectBTree(name) {
         item.classList.toggle('active', item.dataset.name === name);
     });
 
-    // Update dropdown
-    document.getElementById('btree-select').value = name;
+    renderBTree(btree);
 
-    // Render based on current view mode
-    const viewMode = document.getElementById('view-mode').value;
-    if (viewMode === 'tree') {
+    // Clear page details
+    clearPageDetails();
+}
+
+// Render a B-tree in the current view mode
+function renderBTree(btree) {
+    if (document.getElementById('view-mode').value === 'tree') {
         renderTreeView(btree);
     } else {
         renderForceView(btree);
     }
+}
 
-    // Clear page details
-    clearPageDetails();
+// CSS class of a node in either view: highlighted on the lookup path, dashed while it has
+// children still to be loaded
+function nodeClass(btree, pageNum) {
+    const node = btree.nodes.find(n => n.page_number === pageNum);
+    let cls = 'node';
+    if (lookupStep(pageNum)) cls += ' lookup-path';
+    if (node && unloadedChildren(btree, node).length > 0) cls += ' collapsed';
+    return cls;
 }
 
 // Render B-tree as hierarchical tree
@@ -220,7 +505,7 @@ function renderTreeView(btree) {
     g.selectAll('.link')
         .data(treeData.links())
         .join('path')
-        .attr('class', 'link')
+        .attr('class', d => onLookupPath(d.source.data.page_number, d.target.data.page_number) ? 'link lookup-path' : 'link')
         .attr('d', d3.linkVertical()
             .x(d => d.x)
             .y(d => d.y));
@@ -229,9 +514,9 @@ function renderTreeView(btree) {
     const nodes = g.selectAll('.node')
         .data(treeData.descendants())
         .join('g')
-        .attr('class', 'node')
+        .attr('class', d => nodeClass(btree, d.data.page_number))
         .attr('transform', d => `translate(${d.x},${d.y})`)
-        .on('click', (event, d) => showPageDetails(d.data.page_number))
+        .on('click', (event, d) => selectNode(d.data.page_number))
         .on('mouseover', showTooltip)
         .on('mouseout', hideTooltip);
 
@@ -269,7 +554,8 @@ function renderForceView(btree) {
         .filter(l => l.link_type === 'child')
         .map(l => ({
             source: l.source,
-            target: l.target
+            target: l.target,
+            on_path: onLookupPath(parseInt(l.source.slice(1)), parseInt(l.target.slice(1)))
         }));
 
     // Create container group for zoom
@@ -295,7 +581,7 @@ function renderForceView(btree) {
     const link = g.selectAll('.link')
         .data(links)
         .join('line')
-        .attr('class', 'link')
+        .attr('class', d => d.on_path ? 'link lookup-path' : 'link')
         .attr('stroke', '#ccc')
         .attr('stroke-width', 1.5);
 
@@ -303,8 +589,8 @@ function renderForceView(btree) {
     const node = g.selectAll('.node')
         .data(nodes)
         .join('g')
-        .attr('class', 'node')
-        .on('click', (event, d) => showPageDetails(d.page_number))
+        .attr('class', d => nodeClass(btree, d.page_number))
+        .on('click', (event, d) => selectNode(d.page_number))
         .on('mouseover', showTooltip)
         .on('mouseout', hideTooltip)
         .call(d3.drag()
@@ -382,19 +668,28 @@ function getPageClass(pageType) {
         'LeafTable': 'page-leaf-table',
         'InteriorIndex': 'page-interior-index',
         'LeafIndex': 'page-leaf-index',
-        'Overflow': 'page-overflow'
+        'Overflow': 'page-overflow',
+        'FreelistTrunk': 'page-freelist-trunk',
+        'FreelistLeaf': 'page-freelist-leaf',
+        'PointerMap': 'page-ptrmap',
+        'LockByte': 'page-lock-byte'
     };
     return typeMap[pageType] || 'page-leaf-table';
 }
 
+// Whether a page type has a B-tree header and cell pointer array
+function isBTreePageType(pageType) {
+    return ['InteriorTable', 'LeafTable', 'InteriorIndex', 'LeafIndex'].includes(pageType);
+}
+
 // Current page for detail view
 let currentDetailPage = null;
 let currentCellLookup = null;
 let selectedCellIndices = new Set(); // Track multiple selected cells
 
 // Show page details
-function showPageDetails(pageNum) {
-    const page = DATA.pages.find(p => p.page_number === pageNum);
+async function showPageDetails(pageNum) {
+    const page = await getPage(pageNum);
     if (!page) {
         document.getElementById('page-info').innerHTML = `<p>Page ${pageNum} details not available</p>`;
         return;
@@ -410,6 +705,10 @@ function showPageDetails(pageNum) {
             <span class="info-label">Type</span>
             <span class="info-value">${page.page_type}</span>
         </div>
+        <div class="info-row">
+            <span class="info-label">Owner</span>
+            <span class="info-value">${pageOwnerText(page.page_number)}</span>
+        </div>
         <div class="info-row">
             <span class="info-label">Cells</span>
             <span class="info-value">${page.cell_count}</span>
@@ -418,6 +717,12 @@ function showPageDetails(pageNum) {
             <span class="info-label">Free Space</span>
             <span class="info-value">${page.free_space} bytes</span>
         </div>
+        ${freeSpaceBreakdownHtml(page)}
+        ${lookupStep(page.page_number) ? `
+        <div class="info-row lookup-info">
+            <span class="info-label">Lookup ${escapeHtml(DATA.lookup.key)}</span>
+            <span class="info-value">${lookupStepText(lookupStep(page.page_number))}</span>
+        </div>` : ''}
         <button class="view-page-btn" onclick="openPageDetailView(${page.page_number})">View Page Structure</button>
     `;
 
@@ -428,6 +733,44 @@ function showPageDetails(pageNum) {
     renderCellsList(page);
 }
 
+// Rows splitting a B-tree page's free space into gap, freeblocks and fragments
+function freeSpaceBreakdownHtml(page) {
+    const free = page.free_space_breakdown;
+    if (!free) return '';
+    let html = `
+        <div class="info-row">
+            <span class="info-label">&nbsp;&nbsp;Unallocated</span>
+            <span class="info-value">${free.unallocated} bytes</span>
+        </div>
+        <div class="info-row">
+            <span class="info-label">&nbsp;&nbsp;Freeblocks</span>
+            <span class="info-value">${free.freeblocks} bytes (${page.freeblocks.length})</span>
+        </div>
+        <div class="info-row">
+            <span class="info-label">&nbsp;&nbsp;Fragmented</span>
+            <span class="info-value">${free.fragmented} bytes</span>
+        </div>
+    `;
+    if (free.freeblock_error || free.unaccounted !== 0) {
+        const problem = free.freeblock_error || `off by ${free.unaccounted} bytes`;
+        html += `
+        <div class="info-row free-space-error">
+            <span class="info-label">Free space</span>
+            <span class="info-value">${escapeHtml(problem)}</span>
+        </div>
+        `;
+    }
+    return html;
+}
+
+// Describe who owns a page, according to the file-wide page map
+function pageOwnerText(pageNum) {
+    if (DATA.page_map.length === 0) return 'page map not loaded';
+    const entry = DATA.page_map.find(p => p.page_number === pageNum);
+    if (!entry || entry.owners.length === 0) return 'orphan (no owner)';
+    return entry.owners.map(escapeHtml).join(', ');
+}
+
 // Render page internal structure
 function renderPageStructure(page) {
     const svg = d3.select('#page-viz');
@@ -454,7 +797,7 @@ function renderPageStructure(page) {
         .attr('stroke', '#ccc');
 
     // Header area (estimate)
-    const headerSize = page.page_number === 1 ? 108 : 8;
+    const headerSize = isBTreePageType(page.page_type) ? (page.page_number === 1 ? 108 : 8) : 0;
     g.append('rect')
         .attr('width', barWidth)
         .attr('height', headerSize * scale)
@@ -509,11 +852,13 @@ function renderPageStructure(page) {
 // Render cells list with expandable content
 function renderCellsList(page) {
     const container = document.getElementById('cells');
+    const step = lookupStep(page.page_number);
     let html = '';
 
     page.cells.forEach(cell => {
+        const onPath = step !== null && step.cell_index === cell.index;
         html += `
-            <div class="cell-item ${cell.has_overflow ? 'has-overflow' : ''}" onclick="toggleCellExpand(this)">
+            <div class="cell-item ${cell.has_overflow ? 'has-overflow' : ''} ${onPath ? 'lookup-cell' : ''}" onclick="toggleCellExpand(this)">
                 <div class="cell-header">
                     <span class="cell-index">#${cell.index}</span>
                     <span class="cell-type">${cell.cell_type}</span>
@@ -630,8 +975,8 @@ function setupContentTabs(containerId) {
 // ===============================
 
 // Open the detailed page view
-function openPageDetailView(pageNum) {
-    const page = DATA.pages.find(p => p.page_number === pageNum);
+async function openPageDetailView(pageNum) {
+    const page = await getPage(pageNum);
     if (!page) return;
 
     currentDetailPage = page;
@@ -716,12 +1061,13 @@ function renderPageGrid(page) {
     const bytesPerRow = 64;
     const rows = Math.ceil(pageSize / bytesPerRow);
 
-    // Calculate regions
+    // Calculate regions (non-B-tree pages have no header or cell pointers)
+    const isBTreePage = isBTreePageType(page.page_type);
     const isPage1 = page.page_number === 1;
     const dbHeaderEnd = isPage1 ? 100 : 0;
     const pageHeaderStart = isPage1 ? 100 : 0;
     const isInterior = page.page_type.includes('Interior');
-    const pageHeaderSize = isInterior ? 12 : 8;
+    const pageHeaderSize = isBTreePage ? (isInterior ? 12 : 8) : 0;
     const pageHeaderEnd = pageHeaderStart + pageHeaderSize;
     const cellPointerEnd = pageHeaderEnd + (page.cell_count * 2);
     const cellContentStart = page.cell_content_start || pageSize;
@@ -792,8 +1138,18 @@ function renderPageGrid(page) {
                         dataAttrs += ' data-cell-start="true"';
                     }
                 } else {
-                    className += ' free';
-                    dataAttrs += ' data-type="free"';
+                    const block = (page.freeblocks || []).find(f => byteOffset >= f.offset && byteOffset < f.offset + f.size);
+                    if (block) {
+                        className += ' freeblock';
+                        dataAttrs += ` data-type="freeblock" data-freeblock="${block.offset}"`;
+                    } else if (isBTreePage && byteOffset >= cellContentStart) {
+                        // Inside the content area but in no cell or freeblock: a fragment
+                        className += ' fragment';
+                        dataAttrs += ' data-type="fragment"';
+                    } else {
+                        className += ' free';
+                        dataAttrs += ' data-type="free"';
+                    }
                 }
             }
 
@@ -835,11 +1191,25 @@ function renderPageGrid(page) {
     });
 
     // Click on free space or header clears selection
-    container.querySelectorAll('.page-byte.free, .page-byte.header').forEach(el => {
+    container.querySelectorAll('.page-byte.free, .page-byte.fragment, .page-byte.header').forEach(el => {
         el.addEventListener('click', () => {
             clearCellSelection();
         });
     });
+
+    // Click on a freeblock shows its extent and chain link
+    container.querySelectorAll('.page-byte.freeblock').forEach(el => {
+        el.addEventListener('click', () => {
+            const block = page.freeblocks.find(f => f.offset === parseInt(el.dataset.freeblock));
+            clearCellSelection();
+            document.getElementById('selection-info').innerHTML = `
+                <div class="multi-cell-info">
+                    <h4>Freeblock @ ${block.offset}</h4>
+                    <div class="meta">${block.size} bytes · next freeblock: ${block.next ?? 'none'}</div>
+                </div>
+            `;
+        });
+    });
 }
 
 // Clear all cell selections